@@ -2,6 +2,9 @@
 
 import { Server } from '@modelcontextprotocol/sdk/server/index.js';
 import { StdioServerTransport } from '@modelcontextprotocol/sdk/server/stdio.js';
+import { SSEServerTransport } from '@modelcontextprotocol/sdk/server/sse.js';
+import { Transport } from '@modelcontextprotocol/sdk/shared/transport.js';
+import http from 'node:http';
 import {
   CallToolRequestSchema,
   ErrorCode,
@@ -16,6 +19,9 @@ import { PermissionHandler } from './permission-handler.js';
 const WORKTREE_PATH = process.env.WORKTREE_PATH;
 const WORKTREE_ID = process.env.WORKTREE_ID;
 const APPROVAL_CALLBACK_URL = process.env.APPROVAL_CALLBACK_URL;
+// 'stdio' (default, one server per client) or 'sse' (long-running HTTP server on MCP_PORT)
+const MCP_TRANSPORT = process.env.MCP_TRANSPORT || 'stdio';
+const MCP_PORT = process.env.MCP_PORT;
 
 if (!WORKTREE_PATH || !WORKTREE_ID) {
   console.error('Missing required environment variables: WORKTREE_PATH, WORKTREE_ID');
@@ -27,7 +33,7 @@ class OrchestraWorktreeMcpServer {
   private worktreeOps: WorktreeOperations;
   private permissionHandler: PermissionHandler;
 
-  constructor() {
+  constructor(processId: string | undefined = process.env.ORCHESTRA_PROCESS_ID) {
     this.server = new Server(
       {
         name: 'orchestra-worktree-mcp-server',
@@ -41,7 +47,7 @@ class OrchestraWorktreeMcpServer {
     );
 
     this.worktreeOps = new WorktreeOperations(WORKTREE_PATH!, WORKTREE_ID!);
-    this.permissionHandler = new PermissionHandler(APPROVAL_CALLBACK_URL);
+    this.permissionHandler = new PermissionHandler(APPROVAL_CALLBACK_URL, processId);
     
     this.setupToolHandlers();
     this.setupErrorHandler();
@@ -349,19 +355,88 @@ class OrchestraWorktreeMcpServer {
     };
   }
 
+  async connect(transport: Transport) {
+    await this.server.connect(transport);
+  }
+
   async run() {
     console.error('🚀 MCP Server: Starting connection process...');
     const transport = new StdioServerTransport();
     console.error('🚀 MCP Server: Created StdioServerTransport');
-    await this.server.connect(transport);
+    await this.connect(transport);
     console.error(`🚀 MCP Server: Connected! Running for worktree: ${WORKTREE_ID}`);
     console.error('🚀 MCP Server: Ready to receive tool calls');
   }
 }
 
+// Serve MCP over SSE so one long-running server can handle many Claude
+// invocations. Each SSE connection gets its own MCP server instance.
+async function runSse(port: number) {
+  const transports = new Map<string, SSEServerTransport>();
+
+  const httpServer = http.createServer(async (req, res) => {
+    const url = new URL(req.url ?? '/', `http://127.0.0.1:${port}`);
+
+    try {
+      if (req.method === 'GET' && url.pathname === '/sse') {
+        const transport = new SSEServerTransport('/message', res);
+        transports.set(transport.sessionId, transport);
+        res.on('close', () => transports.delete(transport.sessionId));
+        // The server is shared by every Claude process in the worktree, so each
+        // connection says which process it belongs to
+        const processId = url.searchParams.get('processId') ?? undefined;
+        await new OrchestraWorktreeMcpServer(processId).connect(transport);
+        console.error(`🔌 MCP: SSE client connected (session ${transport.sessionId})`);
+        return;
+      }
+
+      if (req.method === 'POST' && url.pathname === '/message') {
+        const transport = transports.get(url.searchParams.get('sessionId') ?? '');
+        if (!transport) {
+          res.writeHead(404).end('Unknown session');
+          return;
+        }
+        await transport.handlePostMessage(req, res);
+        return;
+      }
+
+      if (req.method === 'GET' && url.pathname === '/health') {
+        res
+          .writeHead(200, { 'Content-Type': 'application/json' })
+          .end(JSON.stringify({ status: 'ok', worktree_id: WORKTREE_ID, sessions: transports.size }));
+        return;
+      }
+
+      res.writeHead(404).end();
+    } catch (error) {
+      console.error('❌ MCP: Error handling SSE request:', error);
+      if (!res.headersSent) {
+        res.writeHead(500).end();
+      }
+    }
+  });
+
+  await new Promise<void>((resolve, reject) => {
+    httpServer.once('error', reject);
+    httpServer.listen(port, '127.0.0.1', () => resolve());
+  });
+  console.error(`🚀 MCP Server: Listening on http://127.0.0.1:${port}/sse for worktree: ${WORKTREE_ID}`);
+}
+
 // Start the server
-const server = new OrchestraWorktreeMcpServer();
-server.run().catch((error) => {
-  console.error('Failed to run server:', error);
-  process.exit(1);
-});
\ No newline at end of file
+const start = MCP_TRANSPORT === 'sse'
+  ? () => {
+      const port = Number(MCP_PORT);
+      if (!Number.isInteger(port) || port <= 0) {
+        throw new Error(`MCP_PORT must be set to a valid port for SSE transport (got '${MCP_PORT}')`);
+      }
+      return runSse(port);
+    }
+  : () => new OrchestraWorktreeMcpServer().run();
+
+Promise.resolve()
+  .then(start)
+  .catch((error) => {
+    console.error('Failed to run server:', error);
+    process.exit(1);
+  });
\ No newline at end of file
@@ -7,6 +7,7 @@ export interface ApprovalRequest {
   toolName: string;
   input: any;
   worktreeId: string;
+  processId?: string;
   timestamp: number;
   toolUseId?: string;
 }
@@ -18,7 +19,10 @@ export interface ApprovalResponse {
 }
 
 export class PermissionHandler {
-  constructor(private readonly callbackUrl?: string) {}
+  constructor(
+    private readonly callbackUrl?: string,
+    private readonly processId?: string,
+  ) {}
 
   // Storage for polling-based approval requests
   private pendingApprovals = new Map<string, ApprovalResponse>();
@@ -35,6 +39,7 @@ export class PermissionHandler {
       toolName,
       input,
       worktreeId: process.env.WORKTREE_ID!,
+      ...(this.processId && { processId: this.processId }),
       timestamp: Date.now(),
       ...(toolUseId && { toolUseId }),
     };
@@ -172,6 +177,7 @@ export class PermissionHandler {
       toolName,
       input,
       worktreeId: process.env.WORKTREE_ID!,
+      ...(this.processId && { processId: this.processId }),
       timestamp: Date.now(),
     };
 
@@ -0,0 +1,268 @@
+#[cfg(test)]
+mod tests {
+    use crate::agent_backends::{
+        AgentBackend, AgentBackendRegistry, AgentLaunchRequest, AgentLine, AiderBackend,
+        ClaudeCodeBackend, ShellCommandBackend,
+    };
+    use crate::claude_errors::ClaudeErrorCode;
+    use crate::launch_profiles::LaunchProfile;
+    use std::collections::HashMap;
+
+    fn create_test_request<'a>(
+        permission_mode: &'a str,
+        profile: Option<&'a LaunchProfile>,
+    ) -> AgentLaunchRequest<'a> {
+        AgentLaunchRequest {
+            worktree_path: "/tmp/test-worktree",
+            message: "fix the tests",
+            permission_mode,
+            mcp_config_path: None,
+            approval_prompt_available: false,
+            profile,
+            resume_session_id: None,
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn test_registry_contains_builtin_backends() {
+        let registry = AgentBackendRegistry::new();
+        let ids: Vec<String> = registry.list().into_iter().map(|b| b.id).collect();
+
+        assert_eq!(ids, vec!["aider", "claude", "shell"]);
+        assert!(registry.get("claude").unwrap().supports_permission_prompt());
+        assert!(registry.get("nope").is_err());
+    }
+
+    #[test]
+    fn test_claude_backend_permission_args() {
+        let backend = ClaudeCodeBackend;
+
+        let args = backend
+            .build_args(&create_test_request("full", None))
+            .unwrap();
+        assert!(args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert_eq!(args.last().unwrap(), "fix the tests");
+
+        let mut request = create_test_request("mcp", None);
+        request.mcp_config_path = Some("/tmp/config.json");
+        request.approval_prompt_available = true;
+        let args = backend.build_args(&request).unwrap();
+        assert!(args.contains(&"--mcp-config".to_string()));
+        assert!(args.contains(&"mcp__orchestra-worktree__approval_prompt".to_string()));
+
+        // A config with only extra servers is passed without the approval tool
+        let mut request = create_test_request("safe", None);
+        request.mcp_config_path = Some("/tmp/config.json");
+        let args = backend.build_args(&request).unwrap();
+        assert!(args.contains(&"--mcp-config".to_string()));
+        assert!(!args.contains(&"--permission-prompt-tool".to_string()));
+        assert!(args.contains(&"acceptEdits".to_string()));
+
+        // Without an MCP config the mcp mode falls back to accepting edits
+        let args = backend
+            .build_args(&create_test_request("mcp", None))
+            .unwrap();
+        assert!(args.contains(&"acceptEdits".to_string()));
+    }
+
+    #[test]
+    fn test_read_only_launch_args() {
+        let mut request = create_test_request("full", None);
+        request.read_only = true;
+
+        let args = ClaudeCodeBackend.build_args(&request).unwrap();
+        assert!(!args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(!args.contains(&"acceptEdits".to_string()));
+        let disallowed = args
+            .iter()
+            .position(|arg| arg == "--disallowedTools")
+            .unwrap();
+        assert!(args[disallowed + 1].split(',').any(|tool| tool == "Edit"));
+
+        let args = AiderBackend.build_args(&request).unwrap();
+        assert!(args.windows(2).any(|pair| pair == ["--chat-mode", "ask"]));
+
+        let profile = LaunchProfile {
+            name: "script".to_string(),
+            binary_path: Some("/usr/bin/review".to_string()),
+            ..Default::default()
+        };
+        request.profile = Some(&profile);
+        assert!(ShellCommandBackend.build_args(&request).is_err());
+    }
+
+    #[test]
+    fn test_read_only_ignores_profile_tools_and_extra_args() {
+        let profile = LaunchProfile {
+            name: "permissive".to_string(),
+            model: Some("opus".to_string()),
+            allowed_tools: vec!["Bash".to_string(), "Edit".to_string()],
+            disallowed_tools: vec!["WebFetch".to_string()],
+            extra_args: vec!["--dangerously-skip-permissions".to_string()],
+            ..Default::default()
+        };
+        let mut request = create_test_request("safe", Some(&profile));
+        request.read_only = true;
+
+        let args = ClaudeCodeBackend.build_args(&request).unwrap();
+        assert!(!args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(!args.iter().any(|arg| arg.contains("Bash")));
+        let allowed: Vec<&String> = args
+            .iter()
+            .enumerate()
+            .filter(|(_, arg)| *arg == "--allowedTools")
+            .map(|(index, _)| &args[index + 1])
+            .collect();
+        assert_eq!(allowed.len(), 1);
+        assert!(!allowed[0].split(',').any(|tool| tool == "Edit"));
+        // Restrictions and the model still apply
+        assert!(args.contains(&"WebFetch".to_string()));
+        assert!(args.contains(&"opus".to_string()));
+
+        let args = AiderBackend.build_args(&request).unwrap();
+        assert!(!args.contains(&"--dangerously-skip-permissions".to_string()));
+
+        request.read_only = false;
+        let args = ClaudeCodeBackend.build_args(&request).unwrap();
+        assert!(args.contains(&"Bash,Edit".to_string()));
+        assert!(args.contains(&"--dangerously-skip-permissions".to_string()));
+    }
+
+    #[test]
+    fn test_claude_backend_resume_and_profile() {
+        let profile = LaunchProfile {
+            name: "custom".to_string(),
+            binary_path: Some("/opt/claude".to_string()),
+            model: Some("opus".to_string()),
+            env: HashMap::from([("FOO".to_string(), "bar".to_string())]),
+            ..Default::default()
+        };
+        let mut request = create_test_request("safe", Some(&profile));
+        request.resume_session_id = Some("session-123");
+
+        let spec = ClaudeCodeBackend.build_launch(&request).unwrap();
+
+        assert_eq!(spec.program, "/opt/claude");
+        assert_eq!(spec.current_dir, "/tmp/test-worktree");
+        assert!(spec.envs.contains(&("FOO".to_string(), "bar".to_string())));
+        let resume_index = spec.args.iter().position(|a| a == "--resume").unwrap();
+        assert_eq!(spec.args[resume_index + 1], "session-123");
+        assert!(spec.args.contains(&"opus".to_string()));
+    }
+
+    #[test]
+    fn test_claude_backend_reports_run_cost() {
+        let backend = ClaudeCodeBackend;
+        assert_eq!(
+            backend.run_cost(
+                r#"{"type":"result","subtype":"success","total_cost_usd":0.0421,"result":"Done"}"#
+            ),
+            Some(0.0421)
+        );
+        assert_eq!(
+            backend.run_cost(r#"{"type":"assistant","total_cost_usd":1.0}"#),
+            None
+        );
+        assert_eq!(backend.run_cost("plain output"), None);
+        assert_eq!(
+            ShellCommandBackend.run_cost(r#"{"type":"result","total_cost_usd":1.0}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_claude_backend_parses_stream_json() {
+        let backend = ClaudeCodeBackend;
+
+        assert_eq!(
+            backend
+                .parse_stdout_line(r#"{"type":"system","subtype":"init","session_id":"abc-123"}"#),
+            AgentLine::Session("abc-123".to_string())
+        );
+        assert_eq!(
+            backend.parse_stdout_line(
+                r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Done"}]}}"#
+            ),
+            AgentLine::Output {
+                content: "Done".to_string(),
+                error_hint: None
+            }
+        );
+        assert_eq!(
+            backend.parse_stdout_line(r#"{"type":"result","subtype":"success","is_error":false}"#),
+            AgentLine::Completed
+        );
+        assert_eq!(
+            backend.parse_stdout_line(
+                r#"{"type":"result","is_error":true,"result":"API Error: 529 overloaded_error"}"#
+            ),
+            AgentLine::Failed(ClaudeErrorCode::Overloaded)
+        );
+        assert_eq!(
+            backend.parse_stdout_line(r#"{"type":"user","content":"echo"}"#),
+            AgentLine::Ignore
+        );
+    }
+
+    #[test]
+    fn test_aider_backend_args() {
+        let profile = LaunchProfile {
+            name: "aider".to_string(),
+            backend: Some("aider".to_string()),
+            model: Some("gpt-4o".to_string()),
+            ..Default::default()
+        };
+
+        let spec = AiderBackend
+            .build_launch(&create_test_request("safe", Some(&profile)))
+            .unwrap();
+
+        assert_eq!(spec.program, "aider");
+        assert!(spec
+            .args
+            .contains(&"--no-suggest-shell-commands".to_string()));
+        assert_eq!(
+            &spec.args[spec.args.len() - 2..],
+            &["--message".to_string(), "fix the tests".to_string()]
+        );
+
+        let args = AiderBackend
+            .build_args(&create_test_request("full", None))
+            .unwrap();
+        assert!(!args.contains(&"--no-suggest-shell-commands".to_string()));
+    }
+
+    #[test]
+    fn test_shell_backend_requires_binary() {
+        assert!(ShellCommandBackend
+            .build_launch(&create_test_request("safe", None))
+            .is_err());
+
+        let profile = LaunchProfile {
+            name: "script".to_string(),
+            backend: Some("shell".to_string()),
+            binary_path: Some("./scripts/agent.sh".to_string()),
+            extra_args: vec!["--verbose".to_string()],
+            ..Default::default()
+        };
+        let spec = ShellCommandBackend
+            .build_launch(&create_test_request("safe", Some(&profile)))
+            .unwrap();
+
+        assert_eq!(spec.program, "./scripts/agent.sh");
+        assert_eq!(spec.args, vec!["--verbose", "fix the tests"]);
+    }
+
+    #[test]
+    fn test_plain_text_backends_classify_errors() {
+        assert_eq!(
+            ShellCommandBackend.parse_stdout_line("litellm.RateLimitError: rate limit exceeded"),
+            AgentLine::Output {
+                content: "litellm.RateLimitError: rate limit exceeded".to_string(),
+                error_hint: Some(ClaudeErrorCode::RateLimited)
+            }
+        );
+        assert_eq!(AiderBackend.parse_stdout_line("   "), AgentLine::Ignore);
+    }
+}
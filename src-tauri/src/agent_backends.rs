@@ -0,0 +1,413 @@
+use crate::claude_errors::{classify_output_line, classify_result_message, ClaudeErrorCode};
+use crate::launch_profiles::LaunchProfile;
+use crate::read_only::{READ_ONLY_TOOLS, WRITE_TOOLS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+
+pub const DEFAULT_BACKEND: &str = "claude";
+
+/// Everything needed to (re)spawn an agent CLI invocation.
+#[derive(Debug, Clone)]
+pub struct AgentLaunchSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    pub current_dir: String,
+    pub envs: Vec<(String, String)>,
+}
+
+impl AgentLaunchSpec {
+    pub fn spawn(&self) -> std::io::Result<Child> {
+        Command::new(&self.program)
+            .args(&self.args)
+            .current_dir(&self.current_dir)
+            .envs(self.envs.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+    }
+}
+
+/// Inputs a backend needs to build its command line.
+pub struct AgentLaunchRequest<'a> {
+    pub worktree_path: &'a str,
+    pub message: &'a str,
+    pub permission_mode: &'a str,
+    /// MCP config file for this run, when one was prepared.
+    pub mcp_config_path: Option<&'a str>,
+    /// Whether the MCP config includes Orchestra's approval server.
+    pub approval_prompt_available: bool,
+    pub profile: Option<&'a LaunchProfile>,
+    pub resume_session_id: Option<&'a str>,
+    /// The agent may read the worktree but not change it.
+    pub read_only: bool,
+}
+
+/// What a backend made of one line of stdout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentLine {
+    /// Text to show in the chat, with an error code if the line reports a failure.
+    Output {
+        content: String,
+        error_hint: Option<ClaudeErrorCode>,
+    },
+    /// The agent reported that it finished successfully.
+    Completed,
+    /// The agent reported that it finished with an error.
+    Failed(ClaudeErrorCode),
+    /// The agent reported a session id that can be resumed later.
+    Session(String),
+    Ignore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentBackendInfo {
+    pub id: String,
+    pub name: String,
+    pub supports_permission_prompt: bool,
+    pub supports_resume: bool,
+}
+
+/// A CLI coding agent that Orchestra can run inside a worktree.
+pub trait AgentBackend: Send + Sync {
+    fn id(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    /// Binary used when the launch profile does not override it.
+    fn default_program(&self) -> &'static str;
+
+    /// Whether the backend can route tool approvals through the MCP prompt tool.
+    fn supports_permission_prompt(&self) -> bool {
+        false
+    }
+
+    /// Whether the backend accepts an MCP config (needed for extra MCP servers).
+    fn supports_mcp_config(&self) -> bool {
+        false
+    }
+
+    fn supports_resume(&self) -> bool {
+        false
+    }
+
+    fn build_args(&self, request: &AgentLaunchRequest) -> Result<Vec<String>, String>;
+
+    fn parse_stdout_line(&self, line: &str) -> AgentLine;
+
+    fn classify_stderr_line(&self, line: &str) -> Option<ClaudeErrorCode> {
+        classify_output_line(line)
+    }
+
+    /// API cost in USD, if the line reports what the run cost.
+    fn run_cost(&self, _line: &str) -> Option<f64> {
+        None
+    }
+
+    fn build_launch(&self, request: &AgentLaunchRequest) -> Result<AgentLaunchSpec, String> {
+        let program = request
+            .profile
+            .and_then(|p| p.binary_path.as_deref())
+            .filter(|path| !path.trim().is_empty())
+            .unwrap_or(self.default_program())
+            .to_string();
+
+        let mut envs = Vec::new();
+        if let Some(profile) = request.profile {
+            envs.extend(profile.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        Ok(AgentLaunchSpec {
+            program,
+            args: self.build_args(request)?,
+            current_dir: request.worktree_path.to_string(),
+            envs,
+        })
+    }
+
+    fn info(&self) -> AgentBackendInfo {
+        AgentBackendInfo {
+            id: self.id().to_string(),
+            name: self.name().to_string(),
+            supports_permission_prompt: self.supports_permission_prompt(),
+            supports_resume: self.supports_resume(),
+        }
+    }
+}
+
+/// Claude Code in `--print` mode with stream-json output.
+pub struct ClaudeCodeBackend;
+
+impl AgentBackend for ClaudeCodeBackend {
+    fn id(&self) -> &'static str {
+        "claude"
+    }
+
+    fn name(&self) -> &'static str {
+        "Claude Code"
+    }
+
+    fn default_program(&self) -> &'static str {
+        "claude"
+    }
+
+    fn supports_permission_prompt(&self) -> bool {
+        true
+    }
+
+    fn supports_mcp_config(&self) -> bool {
+        true
+    }
+
+    fn supports_resume(&self) -> bool {
+        true
+    }
+
+    fn build_args(&self, request: &AgentLaunchRequest) -> Result<Vec<String>, String> {
+        let mut args: Vec<String> = vec![
+            "--print".to_string(),
+            "--verbose".to_string(),
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+        ];
+        if let Some(profile) = request.profile {
+            if request.read_only {
+                // The profile's tools and raw flags could let a read-only run write
+                let restricted = LaunchProfile {
+                    allowed_tools: Vec::new(),
+                    extra_args: Vec::new(),
+                    ..profile.clone()
+                };
+                args.extend(restricted.cli_args());
+            } else {
+                args.extend(profile.cli_args());
+            }
+        }
+        if let Some(session_id) = request.resume_session_id {
+            args.push("--resume".to_string());
+            args.push(session_id.to_string());
+        }
+
+        if let Some(config_file) = request.mcp_config_path {
+            args.push("--mcp-config".to_string());
+            args.push(config_file.to_string());
+        }
+        if request.read_only {
+            args.push("--allowedTools".to_string());
+            args.push(READ_ONLY_TOOLS.join(","));
+            args.push("--disallowedTools".to_string());
+            args.push(WRITE_TOOLS.join(","));
+        }
+
+        match (request.permission_mode, request.approval_prompt_available) {
+            ("full", _) if !request.read_only => {
+                args.push("--dangerously-skip-permissions".to_string());
+            }
+            ("mcp", true) => {
+                args.push("--permission-prompt-tool".to_string());
+                args.push("mcp__orchestra-worktree__approval_prompt".to_string());
+            }
+            // Read-only runs never pre-approve edits
+            _ if request.read_only => {
+                args.push("--permission-mode".to_string());
+                args.push("default".to_string());
+            }
+            _ => {
+                args.push("--permission-mode".to_string());
+                args.push("acceptEdits".to_string());
+            }
+        }
+
+        args.push(request.message.to_string());
+        Ok(args)
+    }
+
+    fn run_cost(&self, line: &str) -> Option<f64> {
+        let json = serde_json::from_str::<serde_json::Value>(line).ok()?;
+        if json.get("type").and_then(|t| t.as_str()) != Some("result") {
+            return None;
+        }
+        json.get("total_cost_usd").and_then(|cost| cost.as_f64())
+    }
+
+    fn parse_stdout_line(&self, line: &str) -> AgentLine {
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(line) else {
+            return match crate::parse_claude_json_line(line) {
+                Some(content) => AgentLine::Output {
+                    error_hint: classify_output_line(&content),
+                    content,
+                },
+                None => AgentLine::Ignore,
+            };
+        };
+
+        match json.get("type").and_then(|t| t.as_str()) {
+            Some("result") => match classify_result_message(&json) {
+                Some(error_code) => AgentLine::Failed(error_code),
+                None => AgentLine::Completed,
+            },
+            Some("system") => json
+                .get("session_id")
+                .and_then(|s| s.as_str())
+                .map(|s| AgentLine::Session(s.to_string()))
+                .unwrap_or(AgentLine::Ignore),
+            _ => match crate::parse_claude_json_line(line) {
+                Some(content) => AgentLine::Output {
+                    content,
+                    error_hint: None,
+                },
+                None => AgentLine::Ignore,
+            },
+        }
+    }
+}
+
+/// Aider in single-message scripting mode.
+pub struct AiderBackend;
+
+impl AgentBackend for AiderBackend {
+    fn id(&self) -> &'static str {
+        "aider"
+    }
+
+    fn name(&self) -> &'static str {
+        "Aider"
+    }
+
+    fn default_program(&self) -> &'static str {
+        "aider"
+    }
+
+    fn build_args(&self, request: &AgentLaunchRequest) -> Result<Vec<String>, String> {
+        let mut args: Vec<String> = vec![
+            "--yes-always".to_string(),
+            "--no-pretty".to_string(),
+            "--no-stream".to_string(),
+            "--no-check-update".to_string(),
+        ];
+        // Aider has no approval hook, so outside "full" mode it may only edit files
+        if request.permission_mode != "full" || request.read_only {
+            args.push("--no-suggest-shell-commands".to_string());
+        }
+        // Ask mode answers questions without editing files
+        if request.read_only {
+            args.push("--chat-mode".to_string());
+            args.push("ask".to_string());
+        }
+        if let Some(profile) = request.profile {
+            if let Some(model) = &profile.model {
+                args.push("--model".to_string());
+                args.push(model.clone());
+            }
+            if !request.read_only {
+                args.extend(profile.extra_args.iter().cloned());
+            }
+        }
+
+        args.push("--message".to_string());
+        args.push(request.message.to_string());
+        Ok(args)
+    }
+
+    fn parse_stdout_line(&self, line: &str) -> AgentLine {
+        plain_text_line(line)
+    }
+}
+
+/// Runs the profile's `binary_path` with its extra args and the message as the
+/// last argument, streaming stdout as-is.
+pub struct ShellCommandBackend;
+
+impl AgentBackend for ShellCommandBackend {
+    fn id(&self) -> &'static str {
+        "shell"
+    }
+
+    fn name(&self) -> &'static str {
+        "Shell command"
+    }
+
+    fn default_program(&self) -> &'static str {
+        "sh"
+    }
+
+    fn build_args(&self, request: &AgentLaunchRequest) -> Result<Vec<String>, String> {
+        let profile = request
+            .profile
+            .filter(|p| {
+                p.binary_path
+                    .as_deref()
+                    .is_some_and(|b| !b.trim().is_empty())
+            })
+            .ok_or_else(|| {
+                "The shell backend requires a launch profile with a binary_path".to_string()
+            })?;
+        if request.read_only {
+            return Err("The shell backend cannot restrict a command to read-only".to_string());
+        }
+
+        let mut args = profile.extra_args.clone();
+        args.push(request.message.to_string());
+        Ok(args)
+    }
+
+    fn parse_stdout_line(&self, line: &str) -> AgentLine {
+        plain_text_line(line)
+    }
+}
+
+fn plain_text_line(line: &str) -> AgentLine {
+    if line.trim().is_empty() {
+        return AgentLine::Ignore;
+    }
+    AgentLine::Output {
+        content: line.to_string(),
+        error_hint: classify_output_line(line),
+    }
+}
+
+/// The agent backends available to processes, keyed by id.
+pub struct AgentBackendRegistry {
+    backends: HashMap<String, Arc<dyn AgentBackend>>,
+}
+
+impl AgentBackendRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            backends: HashMap::new(),
+        };
+        registry.register(Arc::new(ClaudeCodeBackend));
+        registry.register(Arc::new(AiderBackend));
+        registry.register(Arc::new(ShellCommandBackend));
+        registry
+    }
+
+    pub fn register(&mut self, backend: Arc<dyn AgentBackend>) {
+        self.backends.insert(backend.id().to_string(), backend);
+    }
+
+    pub fn get(&self, id: &str) -> Result<Arc<dyn AgentBackend>, String> {
+        self.backends
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown agent backend: {id}"))
+    }
+
+    pub fn list(&self) -> Vec<AgentBackendInfo> {
+        let mut backends: Vec<AgentBackendInfo> = self
+            .backends
+            .values()
+            .map(|backend| backend.info())
+            .collect();
+        backends.sort_by(|a, b| a.id.cmp(&b.id));
+        backends
+    }
+}
+
+impl Default for AgentBackendRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
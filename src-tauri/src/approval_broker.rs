@@ -0,0 +1,390 @@
+use crate::approval_grants::{ApprovalGrant, GrantStore};
+use crate::command_risk::RiskLevel;
+use crate::mcp_manager::{ApprovalBehavior, ApprovalResponse};
+use crate::metrics::ApprovalStats;
+use crate::redaction::restore_json;
+use crate::tool_schemas::validate_tool_input;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Resolved approvals kept for `get_approval_history`.
+pub const APPROVAL_HISTORY_LIMIT: usize = 200;
+/// How long a blocking approval request waits for the user before it expires.
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalState {
+    Pending,
+    Answered,
+    Expired,
+    Cancelled,
+}
+
+/// A tool approval request and what became of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRecord {
+    pub approval_id: String,
+    pub tool_name: String,
+    /// As shown to the user, with secrets masked.
+    pub input: serde_json::Value,
+    /// The input as the agent sent it, when `input` has secrets masked. Only
+    /// kept while the request is live and never serialized.
+    #[serde(skip)]
+    pub unredacted_input: Option<serde_json::Value>,
+    pub worktree_id: String,
+    /// The Claude process that asked, when the MCP server forwarded it.
+    #[serde(default)]
+    pub process_id: Option<String>,
+    /// The main checkout of the worktree's repo, when known.
+    #[serde(default)]
+    pub repo_path: Option<String>,
+    pub timestamp: u64,
+    pub state: ApprovalState,
+    pub response: Option<ApprovalResponse>,
+    pub resolved_at: Option<u64>,
+}
+
+impl ApprovalRecord {
+    pub fn new(
+        approval_id: String,
+        tool_name: String,
+        input: serde_json::Value,
+        worktree_id: String,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            approval_id,
+            tool_name,
+            input,
+            unredacted_input: None,
+            worktree_id,
+            process_id: None,
+            repo_path: None,
+            timestamp,
+            state: ApprovalState::Pending,
+            response: None,
+            resolved_at: None,
+        }
+    }
+
+    pub fn with_process(mut self, process_id: Option<String>) -> Self {
+        self.process_id = process_id;
+        self
+    }
+
+    pub fn with_repo(mut self, repo_path: Option<String>) -> Self {
+        self.repo_path = repo_path;
+        self
+    }
+
+    /// The input as the agent sent it, which grants are matched against.
+    pub fn original_input(&self) -> &serde_json::Value {
+        self.unredacted_input.as_ref().unwrap_or(&self.input)
+    }
+}
+
+struct LiveApproval {
+    record: ApprovalRecord,
+    /// Set when a caller is blocked waiting for the answer.
+    responder: Option<oneshot::Sender<ApprovalResponse>>,
+}
+
+/// The single store for tool approvals. Requests start out `Pending` and
+/// move exactly once to `Answered`, `Expired` or `Cancelled`, after which
+/// they are kept in a bounded history. Answers can leave behind grants that
+/// allow matching requests later without asking.
+pub struct ApprovalBroker {
+    live: Mutex<HashMap<String, LiveApproval>>,
+    history: Mutex<VecDeque<ApprovalRecord>>,
+    history_limit: usize,
+    grants: Mutex<GrantStore>,
+    stats: Mutex<ApprovalStats>,
+}
+
+impl ApprovalBroker {
+    pub fn new(history_limit: usize) -> Self {
+        Self {
+            live: Mutex::new(HashMap::new()),
+            history: Mutex::new(VecDeque::new()),
+            history_limit,
+            grants: Mutex::new(GrantStore::new()),
+            stats: Mutex::new(ApprovalStats::default()),
+        }
+    }
+
+    /// Record a request whose caller blocks on the returned receiver.
+    pub fn submit(
+        &self,
+        record: ApprovalRecord,
+    ) -> Result<oneshot::Receiver<ApprovalResponse>, String> {
+        let (responder, receiver) = oneshot::channel();
+        self.insert(record, Some(responder))?;
+        Ok(receiver)
+    }
+
+    /// Record a request that nobody waits on; the answer is only stored.
+    pub fn register(&self, record: ApprovalRecord) -> Result<(), String> {
+        self.insert(record, None)
+    }
+
+    fn insert(
+        &self,
+        record: ApprovalRecord,
+        responder: Option<oneshot::Sender<ApprovalResponse>>,
+    ) -> Result<(), String> {
+        let mut live = self.live.lock().unwrap();
+        if live.contains_key(&record.approval_id) {
+            return Err(format!(
+                "Approval request already exists: {}",
+                record.approval_id
+            ));
+        }
+        live.insert(
+            record.approval_id.clone(),
+            LiveApproval { record, responder },
+        );
+        Ok(())
+    }
+
+    /// Answer a pending request, storing the grant an "allow" answer asks
+    /// for. Fails if it is unknown, already resolved, asks for a grant that
+    /// does not fit the request or cannot be saved, or its caller stopped
+    /// waiting (the request is then cancelled).
+    pub fn respond(
+        &self,
+        approval_id: &str,
+        response: ApprovalResponse,
+    ) -> Result<ApprovalRecord, String> {
+        let mut live = self.live.lock().unwrap();
+        let Some(pending) = live.get(approval_id) else {
+            drop(live);
+            return Err(match self.get(approval_id) {
+                Some(record) => format!(
+                    "Approval request {approval_id} is already {:?}",
+                    record.state
+                ),
+                None => format!("Approval request not found: {approval_id}"),
+            });
+        };
+
+        // Check edited input and the grant before answering so a bad response
+        // leaves the request open
+        if let (ApprovalBehavior::Allow, Some(input)) =
+            (&response.behavior, &response.updated_input)
+        {
+            validate_tool_input(&pending.record.tool_name, input)?;
+        }
+        // The user edited the masked input, but the agent needs the real one
+        let mut delivered = response.clone();
+        if let (Some(original), Some(edited)) =
+            (&pending.record.unredacted_input, &response.updated_input)
+        {
+            delivered.updated_input = Some(restore_json(edited, &pending.record.input, original)?);
+        }
+        let grant = match (&response.behavior, &response.grant) {
+            (ApprovalBehavior::Allow, Some(request)) => {
+                ApprovalGrant::from_request(request, &pending.record)?
+            }
+            _ => None,
+        };
+        // Store the grant first, so a repo grant that cannot be saved leaves
+        // the request open
+        if let Some(grant) = grant {
+            self.grants.lock().unwrap().add(grant)?;
+        }
+        let mut approval = live.remove(approval_id).expect("approval is live");
+        drop(live);
+
+        let delivered = match approval.responder.take() {
+            Some(responder) => responder.send(delivered).is_ok(),
+            None => true,
+        };
+
+        if delivered {
+            approval.record.response = Some(response);
+            let record = self.resolve(approval.record, ApprovalState::Answered);
+            self.observe_latency(&record);
+            Ok(record)
+        } else {
+            self.resolve(approval.record, ApprovalState::Cancelled);
+            Err(format!(
+                "Approval request {approval_id} is no longer waiting for an answer"
+            ))
+        }
+    }
+
+    /// A grant that allows `record` without asking, if any.
+    pub fn find_grant(
+        &self,
+        record: &ApprovalRecord,
+        risk: Option<RiskLevel>,
+    ) -> Option<ApprovalGrant> {
+        self.grants.lock().unwrap().find(record, risk)
+    }
+
+    /// Record a request that a grant answered without asking.
+    pub fn record_granted(
+        &self,
+        mut record: ApprovalRecord,
+        grant: &ApprovalGrant,
+    ) -> ApprovalRecord {
+        record.response = Some(ApprovalResponse {
+            behavior: ApprovalBehavior::Allow,
+            message: Some(format!("Allowed by grant {}", grant.grant_id)),
+            updated_input: None,
+            grant: None,
+        });
+        self.resolve(record, ApprovalState::Answered)
+    }
+
+    /// Record a request denied by policy without asking.
+    pub fn record_denied(&self, mut record: ApprovalRecord, message: String) -> ApprovalRecord {
+        record.response = Some(ApprovalResponse {
+            behavior: ApprovalBehavior::Deny,
+            message: Some(message),
+            updated_input: None,
+            grant: None,
+        });
+        self.resolve(record, ApprovalState::Answered)
+    }
+
+    /// Load saved repo grants and keep saving them to `path`.
+    pub fn load_grants(&self, path: std::path::PathBuf) -> Result<(), String> {
+        self.grants.lock().unwrap().load(path)
+    }
+
+    pub fn list_grants(&self) -> Vec<ApprovalGrant> {
+        self.grants.lock().unwrap().list()
+    }
+
+    pub fn revoke_grant(&self, grant_id: &str) -> Result<ApprovalGrant, String> {
+        self.grants.lock().unwrap().revoke(grant_id)
+    }
+
+    pub fn revoke_process_grants(&self, process_id: &str) {
+        self.grants.lock().unwrap().revoke_process(process_id);
+    }
+
+    /// The caller gave up waiting (timeout).
+    pub fn expire(&self, approval_id: &str) -> Option<ApprovalRecord> {
+        self.finish(approval_id, ApprovalState::Expired)
+    }
+
+    /// The request was withdrawn, e.g. the caller disconnected.
+    pub fn cancel(&self, approval_id: &str) -> Option<ApprovalRecord> {
+        self.finish(approval_id, ApprovalState::Cancelled)
+    }
+
+    fn finish(&self, approval_id: &str, state: ApprovalState) -> Option<ApprovalRecord> {
+        let approval = self.live.lock().unwrap().remove(approval_id)?;
+        let record = self.resolve(approval.record, state);
+        self.observe_latency(&record);
+        Some(record)
+    }
+
+    fn resolve(&self, mut record: ApprovalRecord, state: ApprovalState) -> ApprovalRecord {
+        record.state = state;
+        // Secrets are not kept in the history
+        record.unredacted_input = None;
+        record.resolved_at = Some(chrono::Utc::now().timestamp_millis() as u64);
+
+        let outcome = match (&record.state, &record.response) {
+            (ApprovalState::Answered, Some(response))
+                if matches!(response.behavior, ApprovalBehavior::Allow) =>
+            {
+                "approved"
+            }
+            (ApprovalState::Expired, _) => "expired",
+            (ApprovalState::Cancelled, _) => "cancelled",
+            _ => "denied",
+        };
+        *self
+            .stats
+            .lock()
+            .unwrap()
+            .resolved
+            .entry(outcome)
+            .or_default() += 1;
+
+        let mut history = self.history.lock().unwrap();
+        history.push_back(record.clone());
+        while history.len() > self.history_limit {
+            history.pop_front();
+        }
+        record
+    }
+
+    pub fn get(&self, approval_id: &str) -> Option<ApprovalRecord> {
+        if let Some(approval) = self.live.lock().unwrap().get(approval_id) {
+            return Some(approval.record.clone());
+        }
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|record| record.approval_id == approval_id)
+            .cloned()
+    }
+
+    /// Every request still waiting for an answer, oldest first.
+    pub fn list_pending(&self) -> Vec<ApprovalRecord> {
+        let mut pending: Vec<ApprovalRecord> = self
+            .live
+            .lock()
+            .unwrap()
+            .values()
+            .map(|approval| approval.record.clone())
+            .collect();
+        pending.sort_by_key(|record| record.timestamp);
+        pending
+    }
+
+    /// Whether a process still has a request waiting for an answer.
+    pub fn has_pending_for_process(&self, process_id: &str) -> bool {
+        self.live
+            .lock()
+            .unwrap()
+            .values()
+            .any(|approval| approval.record.process_id.as_deref() == Some(process_id))
+    }
+
+    /// Time a request that was waiting on a person took to resolve.
+    fn observe_latency(&self, record: &ApprovalRecord) {
+        if let Some(resolved_at) = record.resolved_at {
+            let waited_ms = resolved_at.saturating_sub(record.timestamp);
+            self.stats
+                .lock()
+                .unwrap()
+                .latency
+                .observe(waited_ms as f64 / 1000.0);
+        }
+    }
+
+    /// Outcome counts and answer latency since startup.
+    pub fn stats(&self) -> ApprovalStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Resolved requests, newest first.
+    pub fn history(&self, limit: usize) -> Vec<ApprovalRecord> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for ApprovalBroker {
+    fn default() -> Self {
+        Self::new(APPROVAL_HISTORY_LIMIT)
+    }
+}
@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Files larger than this are described but not diffed.
+pub const MAX_DIFF_FILE_BYTES: u64 = 1024 * 1024;
+/// Unchanged lines shown around each change.
+pub const DIFF_CONTEXT_LINES: usize = 3;
+/// Above this many line pairs the changed region is shown as a whole
+/// replacement instead of running the quadratic line matcher.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// What an `Edit`, `MultiEdit` or `Write` request would do to a file, sent
+/// with the `tool-approval-request` event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileChangeContext {
+    pub file_path: String,
+    /// Path relative to the worktree root, or `None` if the file is outside it.
+    pub relative_path: Option<String>,
+    pub exists: bool,
+    pub size: Option<u64>,
+    pub diff: Option<String>,
+    /// Why no diff could be produced.
+    pub note: Option<String>,
+}
+
+/// Describe the file change behind a tool approval request. Returns `None`
+/// for tools that do not write files.
+pub fn file_change_context(
+    tool_name: &str,
+    input: &serde_json::Value,
+    worktree_path: &str,
+) -> Option<FileChangeContext> {
+    if !matches!(tool_name, "Edit" | "MultiEdit" | "Write") {
+        return None;
+    }
+    let file_path = input.get("file_path")?.as_str()?;
+
+    let worktree = normalize(Path::new(worktree_path));
+    let path = normalize(&worktree.join(file_path));
+    let relative_path = path
+        .strip_prefix(&worktree)
+        .ok()
+        .map(|relative| relative.to_string_lossy().to_string());
+    let metadata = std::fs::metadata(&path).ok().filter(|m| m.is_file());
+
+    let mut context = FileChangeContext {
+        file_path: path.to_string_lossy().to_string(),
+        relative_path,
+        exists: metadata.is_some(),
+        size: metadata.as_ref().map(|m| m.len()),
+        diff: None,
+        note: None,
+    };
+
+    let current = match &metadata {
+        None => String::new(),
+        Some(m) if m.len() > MAX_DIFF_FILE_BYTES => {
+            context.note = Some(format!(
+                "File is larger than {MAX_DIFF_FILE_BYTES} bytes, no diff shown"
+            ));
+            return Some(context);
+        }
+        Some(_) => match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => {
+                context.note = Some("File is not valid UTF-8 text, no diff shown".to_string());
+                return Some(context);
+            }
+        },
+    };
+
+    match proposed_content(tool_name, input, &current) {
+        Ok(proposed) => {
+            let label = context
+                .relative_path
+                .clone()
+                .unwrap_or_else(|| context.file_path.clone());
+            context.diff = Some(unified_diff(
+                context.exists.then_some(current.as_str()),
+                &proposed,
+                &label,
+            ));
+        }
+        Err(note) => context.note = Some(note),
+    }
+    Some(context)
+}
+
+/// The file content after applying the tool input, mirroring how Claude's
+/// file tools apply it.
+fn proposed_content(
+    tool_name: &str,
+    input: &serde_json::Value,
+    current: &str,
+) -> Result<String, String> {
+    match tool_name {
+        "Write" => input
+            .get("content")
+            .and_then(|c| c.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| "Write request has no content".to_string()),
+        "Edit" => apply_edit(current, input),
+        "MultiEdit" => input
+            .get("edits")
+            .and_then(|e| e.as_array())
+            .ok_or_else(|| "MultiEdit request has no edits".to_string())?
+            .iter()
+            .try_fold(current.to_string(), |content, edit| {
+                apply_edit(&content, edit)
+            }),
+        _ => Err(format!("{tool_name} does not change files")),
+    }
+}
+
+fn apply_edit(current: &str, edit: &serde_json::Value) -> Result<String, String> {
+    let field = |name: &str| {
+        edit.get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| format!("Edit is missing {name}"))
+    };
+    let old_string = field("old_string")?;
+    let new_string = field("new_string")?;
+    let replace_all = edit
+        .get("replace_all")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    // An empty old_string creates a new file
+    if old_string.is_empty() {
+        return if current.is_empty() {
+            Ok(new_string.to_string())
+        } else {
+            Err("old_string is empty but the file already has content".to_string())
+        };
+    }
+
+    match current.matches(old_string).count() {
+        0 => Err("old_string was not found in the file".to_string()),
+        1 => Ok(current.replacen(old_string, new_string, 1)),
+        _ if replace_all => Ok(current.replace(old_string, new_string)),
+        count => Err(format!(
+            "old_string matches {count} times and replace_all is not set"
+        )),
+    }
+}
+
+/// Resolve `.` and `..` without touching the filesystem, so paths to files
+/// that do not exist yet can still be compared.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffOp<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffOp<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<DiffOp> = old[..prefix].iter().map(|l| DiffOp::Equal(l)).collect();
+
+    if old_mid.len() * new_mid.len() > MAX_DIFF_CELLS {
+        ops.extend(old_mid.iter().map(|l| DiffOp::Delete(l)));
+        ops.extend(new_mid.iter().map(|l| DiffOp::Insert(l)));
+    } else {
+        // Longest common subsequence table, filled from the end
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old_mid[i] == new_mid[j] {
+                ops.push(DiffOp::Equal(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1] {
+                ops.push(DiffOp::Delete(old_mid[i]));
+                i += 1;
+            } else {
+                ops.push(DiffOp::Insert(new_mid[j]));
+                j += 1;
+            }
+        }
+        ops.extend(old_mid[i..].iter().map(|l| DiffOp::Delete(l)));
+        ops.extend(new_mid[j..].iter().map(|l| DiffOp::Insert(l)));
+    }
+
+    ops.extend(old[old.len() - suffix..].iter().map(|l| DiffOp::Equal(l)));
+    ops
+}
+
+/// A unified diff from `old` (`None` for a new file) to `new`. Empty when
+/// nothing changes.
+pub fn unified_diff(old: Option<&str>, new: &str, label: &str) -> String {
+    let old_lines: Vec<&str> = old.unwrap_or("").lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    // Line counts before each op, to number the hunks
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for op in &ops {
+        positions.push((old_pos, new_pos));
+        match op {
+            DiffOp::Equal(_) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            DiffOp::Delete(_) => old_pos += 1,
+            DiffOp::Insert(_) => new_pos += 1,
+        }
+    }
+
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Group changes whose context would overlap into one hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &index in &changes {
+        let start = index.saturating_sub(DIFF_CONTEXT_LINES);
+        let end = (index + 1 + DIFF_CONTEXT_LINES).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut diff = match old {
+        Some(_) => format!("--- a/{label}\n+++ b/{label}\n"),
+        None => format!("--- /dev/null\n+++ b/{label}\n"),
+    };
+    for (start, end) in hunks {
+        let hunk = &ops[start..end];
+        let old_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Insert(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Delete(_)))
+            .count();
+        let (old_start, new_start) = positions[start];
+        diff.push_str(&format!(
+            "@@ -{},{old_count} +{},{new_count} @@\n",
+            old_start + usize::from(old_count > 0),
+            new_start + usize::from(new_count > 0),
+        ));
+        for op in hunk {
+            let (prefix, line) = match op {
+                DiffOp::Equal(line) => (' ', line),
+                DiffOp::Delete(line) => ('-', line),
+                DiffOp::Insert(line) => ('+', line),
+            };
+            diff.push(prefix);
+            diff.push_str(line);
+            diff.push('\n');
+        }
+    }
+    diff
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use crate::approval_context::{file_change_context, unified_diff, MAX_DIFF_FILE_BYTES};
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn create_worktree(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "orchestra_approval_context_{name}_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+
+        let diff = unified_diff(Some(old), new, "src/lib.rs");
+        assert_eq!(
+            diff,
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+
+        assert_eq!(unified_diff(Some(old), old, "src/lib.rs"), "");
+    }
+
+    #[test]
+    fn test_unified_diff_new_file() {
+        let diff = unified_diff(None, "one\ntwo\n", "notes.txt");
+        assert_eq!(
+            diff,
+            "--- /dev/null\n+++ b/notes.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n"
+        );
+    }
+
+    #[test]
+    fn test_edit_context_diffs_against_worktree_file() {
+        let worktree = create_worktree("edit");
+        fs::write(worktree.join("src/main.rs"), "fn main() {\n    old();\n}\n").unwrap();
+
+        let context = file_change_context(
+            "Edit",
+            &serde_json::json!({
+                "file_path": worktree.join("src/main.rs"),
+                "old_string": "old()",
+                "new_string": "new()"
+            }),
+            worktree.to_str().unwrap(),
+        )
+        .unwrap();
+
+        assert!(context.exists);
+        assert_eq!(context.size, Some(25));
+        assert_eq!(context.relative_path.as_deref(), Some("src/main.rs"));
+        let diff = context.diff.unwrap();
+        assert!(diff.contains("-    old();\n+    new();\n"), "{diff}");
+        assert!(context.note.is_none());
+
+        fs::remove_dir_all(&worktree).unwrap();
+    }
+
+    #[test]
+    fn test_multi_edit_and_write_contexts() {
+        let worktree = create_worktree("multi");
+        fs::write(worktree.join("a.txt"), "x = 1\ny = 1\n").unwrap();
+
+        let multi = file_change_context(
+            "MultiEdit",
+            &serde_json::json!({
+                "file_path": "a.txt",
+                "edits": [
+                    {"old_string": "x = 1", "new_string": "x = 2"},
+                    {"old_string": "1", "new_string": "3"}
+                ]
+            }),
+            worktree.to_str().unwrap(),
+        )
+        .unwrap();
+        assert!(multi
+            .diff
+            .unwrap()
+            .contains("-x = 1\n-y = 1\n+x = 2\n+y = 3\n"));
+
+        let write = file_change_context(
+            "Write",
+            &serde_json::json!({"file_path": "new/b.txt", "content": "hello\n"}),
+            worktree.to_str().unwrap(),
+        )
+        .unwrap();
+        assert!(!write.exists);
+        assert!(write.size.is_none());
+        assert_eq!(write.relative_path.as_deref(), Some("new/b.txt"));
+        assert!(write.diff.unwrap().starts_with("--- /dev/null\n"));
+
+        fs::remove_dir_all(&worktree).unwrap();
+    }
+
+    #[test]
+    fn test_context_notes_instead_of_diffs() {
+        let worktree = create_worktree("notes");
+        fs::write(worktree.join("dup.txt"), "same\nsame\n").unwrap();
+        let big = vec![b'a'; MAX_DIFF_FILE_BYTES as usize + 1];
+        fs::write(worktree.join("big.txt"), big).unwrap();
+        let root = worktree.to_str().unwrap();
+
+        let ambiguous = file_change_context(
+            "Edit",
+            &serde_json::json!({"file_path": "dup.txt", "old_string": "same", "new_string": "x"}),
+            root,
+        )
+        .unwrap();
+        assert!(ambiguous.diff.is_none());
+        assert!(ambiguous.note.unwrap().contains("2 times"));
+
+        let missing = file_change_context(
+            "Edit",
+            &serde_json::json!({"file_path": "dup.txt", "old_string": "gone", "new_string": "x"}),
+            root,
+        )
+        .unwrap();
+        assert!(missing.note.unwrap().contains("not found"));
+
+        let large = file_change_context(
+            "Write",
+            &serde_json::json!({"file_path": "big.txt", "content": ""}),
+            root,
+        )
+        .unwrap();
+        assert!(large.diff.is_none());
+        assert_eq!(large.size, Some(MAX_DIFF_FILE_BYTES + 1));
+
+        let outside = file_change_context(
+            "Write",
+            &serde_json::json!({"file_path": "../elsewhere.txt", "content": "x"}),
+            root,
+        )
+        .unwrap();
+        assert!(outside.relative_path.is_none());
+
+        assert!(file_change_context("Bash", &serde_json::json!({"command": "ls"}), root).is_none());
+
+        fs::remove_dir_all(&worktree).unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+use crate::approval_broker::ApprovalRecord;
+use crate::command_risk::RiskLevel;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// Grants never auto-approve commands assessed above this level.
+pub const GRANT_MAX_RISK: RiskLevel = RiskLevel::Medium;
+
+/// How far an "allow" answer reaches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantScope {
+    /// Only the request being answered.
+    #[default]
+    Once,
+    /// Later requests from the same Claude process.
+    Process,
+    /// Later requests from any process in the same worktree.
+    Worktree,
+    /// Later requests in any worktree of the same repo, kept across restarts.
+    Repo,
+}
+
+/// Which later requests a grant covers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GrantMatcher {
+    /// The same tool with exactly the same input. Left empty in a response,
+    /// it is filled in from the request being answered.
+    ExactInput {
+        /// As shown, with secrets masked.
+        #[serde(default)]
+        input: serde_json::Value,
+        /// SHA-256 of the unmasked input, set when `input` has secrets masked
+        /// and matched instead of it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        digest: Option<String>,
+    },
+    /// Any use of the tool.
+    Tool,
+    /// `Bash` commands that start with this prefix.
+    CommandPrefix { prefix: String },
+}
+
+/// The "always allow" part of an approval response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrantRequest {
+    pub scope: GrantScope,
+    pub matcher: GrantMatcher,
+}
+
+/// A stored permission to allow matching requests without asking.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalGrant {
+    pub grant_id: String,
+    pub tool_name: String,
+    pub matcher: GrantMatcher,
+    pub scope: GrantScope,
+    /// Set for `Process` grants.
+    pub process_id: Option<String>,
+    /// Set for `Worktree` grants.
+    pub worktree_id: Option<String>,
+    /// Set for `Repo` grants.
+    pub repo_path: Option<String>,
+    pub created_at: String,
+}
+
+impl ApprovalGrant {
+    /// The grant asked for when answering `record`, or `None` for `Once`.
+    pub fn from_request(
+        request: &GrantRequest,
+        record: &ApprovalRecord,
+    ) -> Result<Option<Self>, String> {
+        let mut grant = Self {
+            grant_id: uuid::Uuid::new_v4().to_string(),
+            tool_name: record.tool_name.clone(),
+            matcher: request.matcher.clone(),
+            scope: request.scope,
+            process_id: None,
+            worktree_id: None,
+            repo_path: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        match request.scope {
+            GrantScope::Once => return Ok(None),
+            GrantScope::Process => {
+                grant.process_id = Some(record.process_id.clone().ok_or(
+                    "This request did not say which process sent it, so it cannot be allowed for the process",
+                )?);
+            }
+            GrantScope::Worktree => grant.worktree_id = Some(record.worktree_id.clone()),
+            GrantScope::Repo => {
+                grant.repo_path = Some(
+                    record
+                        .repo_path
+                        .clone()
+                        .ok_or("The repo of this worktree is unknown")?,
+                );
+            }
+        }
+
+        match &mut grant.matcher {
+            GrantMatcher::ExactInput { input, digest } if input.is_null() => {
+                *input = record.input.clone();
+                *digest = record.unredacted_input.as_ref().map(input_digest);
+            }
+            GrantMatcher::CommandPrefix { prefix } => {
+                if record.tool_name != "Bash" {
+                    return Err("Command prefixes can only be granted for Bash".to_string());
+                }
+                if prefix.trim().is_empty() {
+                    return Err("Command prefix cannot be empty".to_string());
+                }
+            }
+            _ => {}
+        }
+
+        Ok(Some(grant))
+    }
+
+    pub fn matches(&self, record: &ApprovalRecord) -> bool {
+        let in_scope = match self.scope {
+            GrantScope::Once => false,
+            GrantScope::Process => {
+                self.process_id.is_some() && self.process_id == record.process_id
+            }
+            GrantScope::Worktree => self.worktree_id.as_ref() == Some(&record.worktree_id),
+            GrantScope::Repo => self.repo_path.is_some() && self.repo_path == record.repo_path,
+        };
+        if !in_scope || self.tool_name != record.tool_name {
+            return false;
+        }
+
+        match &self.matcher {
+            GrantMatcher::ExactInput {
+                digest: Some(digest),
+                ..
+            } => *digest == input_digest(record.original_input()),
+            GrantMatcher::ExactInput { input, .. } => input == record.original_input(),
+            GrantMatcher::Tool => true,
+            GrantMatcher::CommandPrefix { prefix } => record
+                .original_input()
+                .get("command")
+                .and_then(|command| command.as_str())
+                .is_some_and(|command| command_has_prefix(command.trim(), prefix.trim())),
+        }
+    }
+
+    fn covers_same_requests(&self, other: &ApprovalGrant) -> bool {
+        self.tool_name == other.tool_name
+            && self.matcher == other.matcher
+            && self.scope == other.scope
+            && self.process_id == other.process_id
+            && self.worktree_id == other.worktree_id
+            && self.repo_path == other.repo_path
+    }
+}
+
+/// Hex SHA-256 of a tool input, so a grant can match an input with secrets
+/// without storing them.
+fn input_digest(input: &serde_json::Value) -> String {
+    Sha256::digest(input.to_string().as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Whether `command` is `prefix` followed by nothing or more arguments. A
+/// command that chains, pipes, redirects or substitutes never matches, so
+/// allowing `npm test` does not allow `npm test && rm -rf ~`.
+fn command_has_prefix(command: &str, prefix: &str) -> bool {
+    let Some(rest) = command.strip_prefix(prefix) else {
+        return false;
+    };
+    if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return false;
+    }
+    !command.contains([';', '&', '|', '`', '>', '<', '\n']) && !command.contains("$(")
+}
+
+/// Grants held by the approval broker. Repo grants are saved to `path` when
+/// one is set; the others end with the app.
+#[derive(Debug, Default)]
+pub struct GrantStore {
+    grants: Vec<ApprovalGrant>,
+    path: Option<PathBuf>,
+}
+
+impl GrantStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load saved repo grants from `path` and save future changes there.
+    pub fn load(&mut self, path: PathBuf) -> Result<(), String> {
+        let saved: Vec<ApprovalGrant> = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+
+        self.grants.retain(|grant| grant.scope != GrantScope::Repo);
+        self.grants.extend(
+            saved
+                .into_iter()
+                .filter(|grant| grant.scope == GrantScope::Repo),
+        );
+        self.path = Some(path);
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let repo_grants: Vec<&ApprovalGrant> = self
+            .grants
+            .iter()
+            .filter(|grant| grant.scope == GrantScope::Repo)
+            .collect();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(&repo_grants).map_err(|e| e.to_string())?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to save approval grants to {}: {e}", path.display()))
+    }
+
+    /// Store a grant. An existing grant covering the same requests is kept instead.
+    pub fn add(&mut self, grant: ApprovalGrant) -> Result<ApprovalGrant, String> {
+        if let Some(existing) = self.grants.iter().find(|g| g.covers_same_requests(&grant)) {
+            return Ok(existing.clone());
+        }
+        self.grants.push(grant.clone());
+        if grant.scope == GrantScope::Repo {
+            if let Err(e) = self.save() {
+                self.grants.pop();
+                return Err(e);
+            }
+        }
+        Ok(grant)
+    }
+
+    /// The first grant allowing `record`. Requests assessed above
+    /// [`GRANT_MAX_RISK`] always go to the user.
+    pub fn find(&self, record: &ApprovalRecord, risk: Option<RiskLevel>) -> Option<ApprovalGrant> {
+        if risk.is_some_and(|level| level > GRANT_MAX_RISK) {
+            return None;
+        }
+        self.grants
+            .iter()
+            .find(|grant| grant.matches(record))
+            .cloned()
+    }
+
+    pub fn list(&self) -> Vec<ApprovalGrant> {
+        self.grants.clone()
+    }
+
+    pub fn revoke(&mut self, grant_id: &str) -> Result<ApprovalGrant, String> {
+        let index = self
+            .grants
+            .iter()
+            .position(|grant| grant.grant_id == grant_id)
+            .ok_or_else(|| format!("Approval grant not found: {grant_id}"))?;
+        let grant = self.grants.remove(index);
+        if grant.scope == GrantScope::Repo {
+            self.save()?;
+        }
+        Ok(grant)
+    }
+
+    /// Drop the grants of a process that has finished.
+    pub fn revoke_process(&mut self, process_id: &str) {
+        self.grants
+            .retain(|grant| grant.process_id.as_deref() != Some(process_id));
+    }
+}
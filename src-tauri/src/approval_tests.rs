@@ -1,18 +1,18 @@
 #[cfg(test)]
 mod tests {
+    use crate::approval_broker::{ApprovalBroker, ApprovalState};
+    use crate::approval_grants::{GrantMatcher, GrantRequest, GrantScope};
     use crate::mcp_manager::{
         handle_approval_request, ApprovalBehavior, ApprovalResponse, HttpAppState,
         HttpApprovalRequest,
     };
     use axum::extract::{Json, State};
 
-    use std::collections::HashMap;
     use std::sync::Arc;
-    use tokio::sync::Mutex;
 
     fn create_test_state() -> HttpAppState {
         HttpAppState {
-            pending_http_approvals: Arc::new(Mutex::new(HashMap::new())),
+            approvals: Arc::new(ApprovalBroker::default()),
             app_handle: None,
         }
     }
@@ -28,6 +28,7 @@ mod tests {
             tool_name: "execute_command".to_string(),
             input: serde_json::json!({"command": "ls"}),
             worktree_id: "test-worktree".to_string(),
+            process_id: None,
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
         };
 
@@ -46,15 +47,14 @@ mod tests {
             behavior: ApprovalBehavior::Allow, // Uppercase enum variant
             message: None,
             updated_input: None,
+            grant: None,
         };
 
         // Send the approval response
-        {
-            let mut pending = state.pending_http_approvals.lock().await;
-            if let Some(pending_approval) = pending.remove("test-123") {
-                let _ = pending_approval.response_tx.send(approval_response);
-            }
-        }
+        state
+            .approvals
+            .respond("test-123", approval_response)
+            .unwrap();
 
         // Wait for handler to complete and get the HTTP response
         let result = handler_task.await.unwrap();
@@ -78,6 +78,7 @@ mod tests {
             tool_name: "write_file".to_string(),
             input: serde_json::json!({"path": "/test/file.txt", "content": "test"}),
             worktree_id: "test-worktree".to_string(),
+            process_id: None,
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
         };
 
@@ -94,14 +95,13 @@ mod tests {
             behavior: ApprovalBehavior::Deny, // Uppercase enum variant
             message: Some("User denied the operation".to_string()),
             updated_input: None,
+            grant: None,
         };
 
-        {
-            let mut pending = state.pending_http_approvals.lock().await;
-            if let Some(pending_approval) = pending.remove("test-deny-456") {
-                let _ = pending_approval.response_tx.send(approval_response);
-            }
-        }
+        state
+            .approvals
+            .respond("test-deny-456", approval_response)
+            .unwrap();
 
         let result = handler_task.await.unwrap();
         assert!(result.is_ok());
@@ -128,6 +128,7 @@ mod tests {
             tool_name: "execute_command".to_string(),
             input: original_input,
             worktree_id: "test-worktree".to_string(),
+            process_id: None,
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
         };
 
@@ -149,14 +150,13 @@ mod tests {
             behavior: ApprovalBehavior::Allow,
             message: Some("Approved with modifications".to_string()),
             updated_input: Some(modified_input.clone()),
+            grant: None,
         };
 
-        {
-            let mut pending = state.pending_http_approvals.lock().await;
-            if let Some(pending_approval) = pending.remove("test-update-789") {
-                let _ = pending_approval.response_tx.send(approval_response);
-            }
-        }
+        state
+            .approvals
+            .respond("test-update-789", approval_response)
+            .unwrap();
 
         let result = handler_task.await.unwrap();
         assert!(result.is_ok());
@@ -180,6 +180,7 @@ mod tests {
             tool_name: "execute_command".to_string(),
             input: serde_json::json!({"command": "ls"}),
             worktree_id: "test-worktree".to_string(),
+            process_id: None,
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
         };
 
@@ -200,6 +201,68 @@ mod tests {
 
         // Should timeout, indicating proper error handling
         assert!(result.is_err());
+
+        // Dropping the handler cancels the request instead of leaving it pending
+        assert!(state.approvals.list_pending().is_empty());
+        assert_eq!(
+            state.approvals.get("nonexistent-request").unwrap().state,
+            ApprovalState::Cancelled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_granted_requests_skip_the_dialog() {
+        let state = create_test_state();
+        let request = |request_id: &str| HttpApprovalRequest {
+            request_id: request_id.to_string(),
+            tool_name: "Bash".to_string(),
+            input: serde_json::json!({"command": "npm test"}),
+            worktree_id: "test-worktree".to_string(),
+            process_id: Some("proc-1".to_string()),
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        };
+
+        let state_clone = state.clone();
+        let first = request("first");
+        let handler_task =
+            tokio::spawn(
+                async move { handle_approval_request(State(state_clone), Json(first)).await },
+            );
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        state
+            .approvals
+            .respond(
+                "first",
+                ApprovalResponse {
+                    behavior: ApprovalBehavior::Allow,
+                    message: None,
+                    updated_input: None,
+                    grant: Some(GrantRequest {
+                        scope: GrantScope::Process,
+                        matcher: GrantMatcher::CommandPrefix {
+                            prefix: "npm test".to_string(),
+                        },
+                    }),
+                },
+            )
+            .unwrap();
+        assert!(handler_task.await.unwrap().is_ok());
+
+        // The same command from the same process is allowed without an answer
+        let response = tokio::time::timeout(
+            tokio::time::Duration::from_secs(1),
+            handle_approval_request(State(state.clone()), Json(request("second"))),
+        )
+        .await
+        .expect("granted request should not wait for the user")
+        .unwrap();
+        assert_eq!(response.0["behavior"], "allow");
+        assert_eq!(
+            state.approvals.get("second").unwrap().state,
+            ApprovalState::Answered
+        );
+        assert!(state.approvals.list_pending().is_empty());
     }
 
     #[test]
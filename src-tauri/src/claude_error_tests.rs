@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use crate::claude_errors::{
+        classify_output_line, classify_result_message, classify_spawn_error, ClaudeErrorCode,
+        RetryPolicy,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_classify_spawn_error_not_found() {
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "No such file");
+        assert_eq!(
+            classify_spawn_error(&error),
+            ClaudeErrorCode::BinaryNotFound
+        );
+
+        let error = std::io::Error::other("boom");
+        assert_eq!(classify_spawn_error(&error), ClaudeErrorCode::Unknown);
+    }
+
+    #[test]
+    fn test_classify_output_lines() {
+        let test_cases = vec![
+            (
+                "Invalid API key · Please run /login",
+                Some(ClaudeErrorCode::NotAuthenticated),
+            ),
+            (
+                r#"API Error: 429 {"type":"error","error":{"type":"rate_limit_error"}}"#,
+                Some(ClaudeErrorCode::RateLimited),
+            ),
+            (
+                r#"API Error: 529 {"type":"error","error":{"type":"overloaded_error"}}"#,
+                Some(ClaudeErrorCode::Overloaded),
+            ),
+            ("Prompt is too long", Some(ClaudeErrorCode::ContextTooLong)),
+            (
+                "error: unknown option '--bogus'",
+                Some(ClaudeErrorCode::InvalidFlags),
+            ),
+            (
+                "Error: Invalid MCP configuration: mcpServers must be an object",
+                Some(ClaudeErrorCode::McpConfigError),
+            ),
+            (
+                "bwrap: No permissions to creating new namespace",
+                Some(ClaudeErrorCode::SandboxUnavailable),
+            ),
+            ("Reading src/main.rs", None),
+            ("", None),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(
+                classify_output_line(input),
+                expected,
+                "Failed for input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_result_message() {
+        let success = serde_json::json!({
+            "type": "result",
+            "subtype": "success",
+            "is_error": false,
+            "result": "All done"
+        });
+        assert_eq!(classify_result_message(&success), None);
+
+        let overloaded = serde_json::json!({
+            "type": "result",
+            "subtype": "success",
+            "is_error": true,
+            "result": "API Error: 529 overloaded_error"
+        });
+        assert_eq!(
+            classify_result_message(&overloaded),
+            Some(ClaudeErrorCode::Overloaded)
+        );
+
+        let max_turns = serde_json::json!({
+            "type": "result",
+            "subtype": "error_max_turns",
+            "is_error": false
+        });
+        assert_eq!(
+            classify_result_message(&max_turns),
+            Some(ClaudeErrorCode::Unknown)
+        );
+    }
+
+    #[test]
+    fn test_error_code_serialization() {
+        let json = serde_json::to_string(&ClaudeErrorCode::ContextTooLong).unwrap();
+        assert_eq!(json, "\"context_too_long\"");
+
+        let code: ClaudeErrorCode = serde_json::from_str("\"mcp_config_error\"").unwrap();
+        assert_eq!(code, ClaudeErrorCode::McpConfigError);
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            initial_delay_ms: 1_000,
+            max_delay_ms: 5_000,
+        };
+
+        assert_eq!(policy.delay_for_attempt(1), Duration::from_millis(1_000));
+        assert_eq!(policy.delay_for_attempt(2), Duration::from_millis(2_000));
+        assert_eq!(policy.delay_for_attempt(3), Duration::from_millis(4_000));
+        assert_eq!(policy.delay_for_attempt(4), Duration::from_millis(5_000));
+        assert_eq!(policy.delay_for_attempt(100), Duration::from_millis(5_000));
+    }
+
+    #[test]
+    fn test_retry_policy_only_retries_transient_errors() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.should_retry(ClaudeErrorCode::RateLimited, 0));
+        assert!(policy.should_retry(ClaudeErrorCode::Overloaded, 2));
+        assert!(!policy.should_retry(ClaudeErrorCode::Overloaded, 3));
+        assert!(!policy.should_retry(ClaudeErrorCode::NotAuthenticated, 0));
+        assert!(!policy.should_retry(ClaudeErrorCode::ContextTooLong, 0));
+    }
+}
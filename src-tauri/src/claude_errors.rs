@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Structured error codes reported on `claude-completed` when a Claude run fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaudeErrorCode {
+    BinaryNotFound,
+    NotAuthenticated,
+    RateLimited,
+    Overloaded,
+    ContextTooLong,
+    InvalidFlags,
+    McpConfigError,
+    SandboxUnavailable,
+    Unknown,
+}
+
+impl ClaudeErrorCode {
+    /// Rate-limit and overload errors are transient and worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ClaudeErrorCode::RateLimited | ClaudeErrorCode::Overloaded
+        )
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ClaudeErrorCode::BinaryNotFound => {
+                "Claude Code CLI not found. Make sure 'claude' is installed and in PATH."
+            }
+            ClaudeErrorCode::NotAuthenticated => {
+                "Claude Code is not authenticated. Run 'claude' in a terminal and log in."
+            }
+            ClaudeErrorCode::RateLimited => "The Anthropic API rate limit was reached.",
+            ClaudeErrorCode::Overloaded => "The Anthropic API is temporarily overloaded.",
+            ClaudeErrorCode::ContextTooLong => {
+                "The conversation is too long for the model's context window."
+            }
+            ClaudeErrorCode::InvalidFlags => {
+                "Claude Code rejected its command-line flags. The installed CLI version may be incompatible."
+            }
+            ClaudeErrorCode::McpConfigError => "Claude Code failed to load the MCP configuration.",
+            ClaudeErrorCode::SandboxUnavailable => {
+                "bubblewrap could not create the sandbox. Unprivileged user namespaces may need to be enabled."
+            }
+            ClaudeErrorCode::Unknown => "Claude Code exited with an error.",
+        }
+    }
+}
+
+/// Classify a failure to spawn the `claude` binary.
+pub fn classify_spawn_error(error: &std::io::Error) -> ClaudeErrorCode {
+    match error.kind() {
+        std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied => {
+            ClaudeErrorCode::BinaryNotFound
+        }
+        _ => ClaudeErrorCode::Unknown,
+    }
+}
+
+/// Classify a single line of Claude output (stderr, plain stdout or the text of
+/// an error `result` message). Returns `None` if the line does not look like a
+/// known failure.
+pub fn classify_output_line(line: &str) -> Option<ClaudeErrorCode> {
+    let line = line.trim().to_lowercase();
+    if line.is_empty() {
+        return None;
+    }
+
+    if line.contains("invalid api key")
+        || line.contains("please run /login")
+        || line.contains("not authenticated")
+        || line.contains("authentication_error")
+        || line.contains("oauth token has expired")
+        || line.contains("api error: 401")
+    {
+        return Some(ClaudeErrorCode::NotAuthenticated);
+    }
+
+    if line.contains("rate_limit_error")
+        || line.contains("rate limit")
+        || line.contains("api error: 429")
+    {
+        return Some(ClaudeErrorCode::RateLimited);
+    }
+
+    if line.contains("overloaded_error")
+        || line.contains("overloaded")
+        || line.contains("api error: 529")
+    {
+        return Some(ClaudeErrorCode::Overloaded);
+    }
+
+    if line.contains("prompt is too long")
+        || line.contains("input is too long")
+        || line.contains("context_length_exceeded")
+        || line.contains("context length")
+    {
+        return Some(ClaudeErrorCode::ContextTooLong);
+    }
+
+    let mentions_mcp_config = line.contains("mcp config")
+        || line.contains("mcp configuration")
+        || line.contains("--mcp-config");
+    if mentions_mcp_config
+        && (line.contains("error")
+            || line.contains("invalid")
+            || line.contains("failed")
+            || line.contains("not found"))
+    {
+        return Some(ClaudeErrorCode::McpConfigError);
+    }
+
+    if line.starts_with("bwrap:") {
+        return Some(ClaudeErrorCode::SandboxUnavailable);
+    }
+
+    if line.starts_with("error: unknown option")
+        || line.starts_with("error: option '")
+        || line.starts_with("error: required option")
+        || line.starts_with("error: missing required argument")
+        || line.starts_with("error: too many arguments")
+    {
+        return Some(ClaudeErrorCode::InvalidFlags);
+    }
+
+    None
+}
+
+/// Classify a stream-json `result` message. Returns `None` for successful results.
+pub fn classify_result_message(json: &serde_json::Value) -> Option<ClaudeErrorCode> {
+    let is_error = json
+        .get("is_error")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let subtype = json.get("subtype").and_then(|s| s.as_str()).unwrap_or("");
+
+    if !is_error && !subtype.starts_with("error") {
+        return None;
+    }
+
+    let text = json.get("result").and_then(|r| r.as_str()).unwrap_or("");
+    Some(classify_output_line(text).unwrap_or(ClaudeErrorCode::Unknown))
+}
+
+/// How transient Claude failures are retried.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay_ms: 2_000,
+            max_delay_ms: 60_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff delay before retry number `attempt` (starting at 1).
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let factor = 1u64
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        let delay = self.initial_delay_ms.saturating_mul(factor);
+        Duration::from_millis(delay.min(self.max_delay_ms))
+    }
+
+    pub fn should_retry(&self, error: ClaudeErrorCode, attempt: u32) -> bool {
+        error.is_retryable() && attempt < self.max_retries
+    }
+}
@@ -0,0 +1,1049 @@
+use crate::approval_context::normalize;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How deep `bash -c`, `eval` and `$(...)` are followed.
+const MAX_NESTING: usize = 3;
+
+/// Ordered from harmless to dangerous, so rules can compare against a ceiling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskCategory {
+    Destructive,
+    Network,
+    OutsideWorktree,
+    PrivilegeEscalation,
+    HistoryRewrite,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskReason {
+    pub category: RiskCategory,
+    pub level: RiskLevel,
+    pub message: String,
+}
+
+/// Assessment of a shell command, sent with Bash approval requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandRisk {
+    pub level: RiskLevel,
+    pub reasons: Vec<RiskReason>,
+}
+
+/// Assess the command of a `Bash` tool request. Returns `None` for other tools.
+pub fn tool_risk(
+    tool_name: &str,
+    input: &serde_json::Value,
+    worktree_path: Option<&str>,
+) -> Option<CommandRisk> {
+    if tool_name != "Bash" {
+        return None;
+    }
+    let command = input.get("command")?.as_str()?;
+    Some(assess_command(command, worktree_path))
+}
+
+/// Parse a shell command and flag what it could do. `worktree_path` is used
+/// to tell writes inside the worktree from writes elsewhere.
+pub fn assess_command(command: &str, worktree_path: Option<&str>) -> CommandRisk {
+    let mut analyzer = Analyzer {
+        worktree_path,
+        reasons: Vec::new(),
+    };
+    analyzer.analyze(command, 0);
+
+    CommandRisk {
+        level: analyzer
+            .reasons
+            .iter()
+            .map(|reason| reason.level)
+            .max()
+            .unwrap_or(RiskLevel::Low),
+        reasons: analyzer.reasons,
+    }
+}
+
+/// Every word and redirect target of a shell command, including those of
+/// scripts passed to `sh -c` or `eval` and of command substitutions.
+pub fn command_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    collect_words(command, 0, &mut words);
+    words
+}
+
+fn collect_words(command: &str, depth: usize, words: &mut Vec<String>) {
+    if depth > MAX_NESTING {
+        return;
+    }
+    let Tokens {
+        tokens,
+        substitutions,
+    } = tokenize(command);
+
+    for substitution in substitutions {
+        collect_words(&substitution, depth + 1, words);
+    }
+    for token in tokens {
+        if let Token::Word(word) = token {
+            // A quoted word with spaces may be a script of its own
+            if word.contains(char::is_whitespace) {
+                collect_words(&word, depth + 1, words);
+            } else {
+                words.push(word);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// `;`, `&&`, `||`, `|`, `&` or a newline.
+    Separator(String),
+    /// `>` or `>>`; the next word is the target.
+    Redirect,
+    /// `<`; the next word is read, not run.
+    Input,
+}
+
+/// A shell command line split into tokens, with the contents of command
+/// substitutions collected separately.
+struct Tokens {
+    tokens: Vec<Token>,
+    substitutions: Vec<String>,
+}
+
+fn tokenize(command: &str) -> Tokens {
+    let mut tokens = Vec::new();
+    let mut substitutions = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    fn finish(tokens: &mut Vec<Token>, word: &mut String, in_word: &mut bool) {
+        if *in_word {
+            tokens.push(Token::Word(std::mem::take(word)));
+            *in_word = false;
+        }
+    }
+
+    // Read `$( ... )` up to its matching parenthesis
+    fn read_substitution(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+        let mut depth = 1;
+        let mut inner = String::new();
+        for c in chars.by_ref() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            inner.push(c);
+        }
+        inner
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => finish(&mut tokens, &mut word, &mut in_word),
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    if next != '\n' {
+                        word.push(next);
+                        in_word = true;
+                    }
+                }
+            }
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    word.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            if let Some(next) = chars.next() {
+                                word.push(next);
+                            }
+                        }
+                        '$' if chars.peek() == Some(&'(') => {
+                            chars.next();
+                            substitutions.push(read_substitution(&mut chars));
+                            word.push_str("$(...)");
+                        }
+                        '`' => {
+                            substitutions.push(chars.by_ref().take_while(|&c| c != '`').collect());
+                            word.push_str("$(...)");
+                        }
+                        _ => word.push(c),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'(') => {
+                chars.next();
+                substitutions.push(read_substitution(&mut chars));
+                word.push_str("$(...)");
+                in_word = true;
+            }
+            '`' => {
+                substitutions.push(chars.by_ref().take_while(|&c| c != '`').collect());
+                word.push_str("$(...)");
+                in_word = true;
+            }
+            '#' if !in_word => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                tokens.push(Token::Separator("\n".to_string()));
+            }
+            ';' | '\n' | '(' | ')' | '{' | '}' => {
+                finish(&mut tokens, &mut word, &mut in_word);
+                tokens.push(Token::Separator(";".to_string()));
+            }
+            '&' | '|' => {
+                finish(&mut tokens, &mut word, &mut in_word);
+                let separator = if chars.peek() == Some(&c) {
+                    chars.next();
+                    format!("{c}{c}")
+                } else {
+                    c.to_string()
+                };
+                tokens.push(Token::Separator(separator));
+            }
+            '>' | '<' => {
+                // A leading file descriptor (`2>`) is not part of any word
+                if in_word && word.chars().all(|c| c.is_ascii_digit()) {
+                    word.clear();
+                    in_word = false;
+                }
+                finish(&mut tokens, &mut word, &mut in_word);
+                if chars.peek() == Some(&'>') {
+                    chars.next();
+                }
+                if chars.peek() == Some(&'&') {
+                    // `2>&1` duplicates a descriptor and writes no file
+                    chars.next();
+                    while chars
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_digit() || *c == '-')
+                    {
+                        chars.next();
+                    }
+                } else if c == '>' {
+                    tokens.push(Token::Redirect);
+                } else {
+                    tokens.push(Token::Input);
+                }
+            }
+            _ => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    finish(&mut tokens, &mut word, &mut in_word);
+
+    Tokens {
+        tokens,
+        substitutions,
+    }
+}
+
+/// One simple command of a command line.
+#[derive(Default)]
+struct SimpleCommand {
+    words: Vec<String>,
+    redirect_targets: Vec<String>,
+    /// Receives the output of the previous command through a pipe.
+    piped: bool,
+}
+
+fn split_commands(tokens: Vec<Token>) -> Vec<SimpleCommand> {
+    let mut commands = Vec::new();
+    let mut current = SimpleCommand::default();
+    let mut pending_redirect = None;
+
+    for token in tokens {
+        match token {
+            Token::Word(word) => match pending_redirect.take() {
+                Some(Token::Redirect) => current.redirect_targets.push(word),
+                Some(_) => {}
+                None => current.words.push(word),
+            },
+            Token::Redirect | Token::Input => pending_redirect = Some(token),
+            Token::Separator(separator) => {
+                let piped = separator == "|";
+                commands.push(std::mem::take(&mut current));
+                current.piped = piped;
+            }
+        }
+    }
+    commands.push(current);
+    commands.retain(|command| !command.words.is_empty() || !command.redirect_targets.is_empty());
+    commands
+}
+
+fn program_name(word: &str) -> &str {
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+/// `NAME=value` before a command.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        name.chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+fn is_flag(word: &str) -> bool {
+    word.starts_with('-') && word.len() > 1
+}
+
+/// Whether short or long flags include one of `short` (e.g. `r` in `-rf`) or `long`.
+fn has_flag(args: &[String], short: &[char], long: &[&str]) -> bool {
+    args.iter().any(|arg| {
+        if let Some(name) = arg.strip_prefix("--") {
+            long.iter()
+                .any(|l| name == *l || name.starts_with(&format!("{l}=")))
+        } else if let Some(letters) = arg.strip_prefix('-') {
+            letters.chars().any(|c| short.contains(&c))
+        } else {
+            false
+        }
+    })
+}
+
+const WRAPPERS: &[&str] = &[
+    "env", "nice", "nohup", "time", "timeout", "command", "exec", "xargs", "stdbuf", "ionice",
+];
+const PRIVILEGE_COMMANDS: &[&str] = &["sudo", "doas", "su", "pkexec", "runas"];
+/// `sudo`/`doas` options whose value is the next word, as in `sudo -u root`.
+const PRIVILEGE_VALUE_OPTIONS: &[&str] =
+    &["-u", "-g", "-U", "-C", "-D", "-h", "-p", "-r", "-t", "-T"];
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+const NETWORK_COMMANDS: &[&str] = &[
+    "curl", "wget", "nc", "ncat", "netcat", "ssh", "scp", "sftp", "ftp", "telnet", "socat",
+];
+const DISK_COMMANDS: &[&str] = &["mkfs", "fdisk", "parted", "wipefs", "sfdisk"];
+const POWER_COMMANDS: &[&str] = &["shutdown", "reboot", "halt", "poweroff"];
+
+struct Analyzer<'a> {
+    worktree_path: Option<&'a str>,
+    reasons: Vec<RiskReason>,
+}
+
+impl Analyzer<'_> {
+    fn flag(&mut self, category: RiskCategory, level: RiskLevel, message: impl Into<String>) {
+        let reason = RiskReason {
+            category,
+            level,
+            message: message.into(),
+        };
+        if !self.reasons.contains(&reason) {
+            self.reasons.push(reason);
+        }
+    }
+
+    fn analyze(&mut self, command: &str, depth: usize) {
+        if depth > MAX_NESTING {
+            return;
+        }
+        let Tokens {
+            tokens,
+            substitutions,
+        } = tokenize(command);
+
+        for substitution in substitutions {
+            self.analyze(&substitution, depth + 1);
+        }
+
+        let commands = split_commands(tokens);
+        for (index, command) in commands.iter().enumerate() {
+            for target in &command.redirect_targets {
+                self.check_write(target, "Redirects output");
+            }
+
+            // `curl ... | sh` runs whatever was downloaded
+            let runs_stdin = command
+                .words
+                .first()
+                .is_some_and(|word| SHELLS.contains(&program_name(word)));
+            if command.piped && runs_stdin && index > 0 {
+                let source = &commands[index - 1];
+                if source
+                    .words
+                    .first()
+                    .is_some_and(|word| NETWORK_COMMANDS.contains(&program_name(word)))
+                {
+                    self.flag(
+                        RiskCategory::Network,
+                        RiskLevel::Critical,
+                        "Pipes a download straight into a shell",
+                    );
+                }
+            }
+
+            self.analyze_words(&command.words, depth);
+        }
+    }
+
+    fn analyze_words(&mut self, words: &[String], depth: usize) {
+        // Skip `FOO=bar` assignments and wrappers to find the real program
+        let mut start = 0;
+        while let Some(word) = words.get(start) {
+            let name = program_name(word);
+            if is_assignment(word) {
+                start += 1;
+            } else if WRAPPERS.contains(&name) {
+                start += 1;
+                // Wrapper options such as `timeout 10` or `nice -n 5`
+                while words
+                    .get(start)
+                    .is_some_and(|w| is_flag(w) || w.chars().all(|c| c.is_ascii_digit()))
+                {
+                    start += 1;
+                }
+            } else if PRIVILEGE_COMMANDS.contains(&name) {
+                self.flag(
+                    RiskCategory::PrivilegeEscalation,
+                    RiskLevel::High,
+                    format!("Runs a command with elevated privileges ({name})"),
+                );
+                start += 1;
+                while let Some(option) = words.get(start).filter(|w| is_flag(w)) {
+                    start += if PRIVILEGE_VALUE_OPTIONS.contains(&option.as_str()) {
+                        2
+                    } else {
+                        1
+                    };
+                }
+            } else {
+                break;
+            }
+        }
+
+        let Some(program) = words.get(start) else {
+            return;
+        };
+        let program = program_name(program);
+        let args = &words[start + 1..];
+
+        match program {
+            _ if SHELLS.contains(&program) || program == "eval" => {
+                let script = if program == "eval" {
+                    Some(args.join(" "))
+                } else {
+                    args.iter()
+                        .position(|arg| arg == "-c")
+                        .and_then(|index| args.get(index + 1).cloned())
+                };
+                if let Some(script) = script {
+                    self.analyze(&script, depth + 1);
+                }
+            }
+            "rm" | "shred" | "unlink" | "rmdir" => self.check_remove(program, args),
+            "mv" => {
+                self.flag(RiskCategory::Destructive, RiskLevel::Medium, "Moves files");
+                self.check_write_args(args, 0, "Moves files");
+            }
+            "cp" | "ln" | "install" => {
+                if let Some(target) = args.iter().rev().find(|arg| !is_flag(arg)) {
+                    self.check_write(target, "Copies files");
+                }
+            }
+            "touch" | "mkdir" | "tee" | "truncate" => {
+                if program == "truncate" {
+                    self.flag(
+                        RiskCategory::Destructive,
+                        RiskLevel::Medium,
+                        "Truncates files",
+                    );
+                }
+                self.check_write_args(args, 0, "Writes files");
+            }
+            "chmod" | "chown" | "chgrp" => self.check_permissions(program, args),
+            "sed" | "perl" if has_flag(args, &['i'], &["in-place"]) => {
+                self.check_write_args(args, 1, "Edits files in place");
+            }
+            "dd" => {
+                if let Some(target) = args.iter().find_map(|arg| arg.strip_prefix("of=")) {
+                    if target.starts_with("/dev/") && !is_pseudo_device(target) {
+                        self.flag(
+                            RiskCategory::Destructive,
+                            RiskLevel::Critical,
+                            format!("Writes raw data to a device ({target})"),
+                        );
+                    } else {
+                        self.flag(
+                            RiskCategory::Destructive,
+                            RiskLevel::High,
+                            "Overwrites a file with dd",
+                        );
+                        self.check_write(target, "Writes files");
+                    }
+                }
+            }
+            "find"
+                if args.iter().any(|arg| arg == "-delete")
+                    || args.windows(2).any(|pair| {
+                        pair[0].starts_with("-exec") && program_name(&pair[1]) == "rm"
+                    }) =>
+            {
+                self.flag(
+                    RiskCategory::Destructive,
+                    RiskLevel::High,
+                    "Deletes files found by find",
+                );
+            }
+            "kill" | "pkill" | "killall" => {
+                self.flag(
+                    RiskCategory::Destructive,
+                    RiskLevel::Medium,
+                    "Terminates processes",
+                );
+            }
+            "git" => self.check_git(args),
+            "rsync" => {
+                if args.iter().any(|arg| is_remote_path(arg)) {
+                    self.flag(
+                        RiskCategory::Network,
+                        RiskLevel::Medium,
+                        "Transfers files over the network (rsync)",
+                    );
+                }
+                if has_flag(args, &[], &["delete", "delete-after", "delete-before"]) {
+                    self.flag(
+                        RiskCategory::Destructive,
+                        RiskLevel::High,
+                        "Deletes files missing from the source (rsync --delete)",
+                    );
+                }
+            }
+            "npm" | "pnpm" | "yarn" | "pip" | "pip3" | "cargo" | "gem" | "go" | "docker" => {
+                self.check_package_manager(program, args)
+            }
+            _ if NETWORK_COMMANDS.contains(&program) => {
+                self.flag(
+                    RiskCategory::Network,
+                    RiskLevel::Medium,
+                    format!("Accesses the network ({program})"),
+                );
+                if program == "curl" || program == "wget" {
+                    let output = args.iter().enumerate().find_map(|(index, arg)| {
+                        matches!(arg.as_str(), "-o" | "-O" | "--output" | "--output-document")
+                            .then(|| args.get(index + 1))
+                            .flatten()
+                    });
+                    if let Some(output) = output {
+                        self.check_write(output, "Downloads to");
+                    }
+                }
+            }
+            _ if DISK_COMMANDS.iter().any(|disk| program.starts_with(disk)) => {
+                self.flag(
+                    RiskCategory::Destructive,
+                    RiskLevel::Critical,
+                    format!("Modifies disks or filesystems ({program})"),
+                );
+            }
+            _ if POWER_COMMANDS.contains(&program) => {
+                self.flag(
+                    RiskCategory::Destructive,
+                    RiskLevel::Critical,
+                    format!("Shuts down or restarts the machine ({program})"),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn check_remove(&mut self, program: &str, args: &[String]) {
+        let recursive = has_flag(args, &['r', 'R'], &["recursive"]);
+        let targets: Vec<&String> = args.iter().filter(|arg| !is_flag(arg)).collect();
+
+        let catastrophic = targets.iter().any(|target| {
+            matches!(
+                target.trim_end_matches('/'),
+                "" | "/*" | "~" | "$HOME" | "${HOME}" | "*" | "." | ".."
+            ) || target.as_str() == "/"
+        });
+
+        if recursive && catastrophic {
+            self.flag(
+                RiskCategory::Destructive,
+                RiskLevel::Critical,
+                format!("Recursively deletes a root, home or whole directory ({program})"),
+            );
+        } else if recursive {
+            self.flag(
+                RiskCategory::Destructive,
+                RiskLevel::High,
+                format!("Recursively deletes files ({program})"),
+            );
+        } else {
+            self.flag(
+                RiskCategory::Destructive,
+                RiskLevel::Medium,
+                format!("Deletes files ({program})"),
+            );
+        }
+
+        for target in targets {
+            self.check_write(target, "Deletes");
+        }
+    }
+
+    fn check_permissions(&mut self, program: &str, args: &[String]) {
+        let mode = args.iter().find(|arg| !is_flag(arg));
+        if program == "chmod" {
+            if let Some(mode) = mode {
+                // `u+s`, `g+s` or an octal mode with the setuid/setgid digit set
+                let setuid = (!mode.chars().all(|c| c.is_ascii_digit()) && mode.contains('s'))
+                    || (mode.len() == 4
+                        && mode.chars().all(|c| c.is_ascii_digit())
+                        && matches!(mode.chars().next(), Some('2'..='7')));
+                if setuid {
+                    self.flag(
+                        RiskCategory::PrivilegeEscalation,
+                        RiskLevel::High,
+                        "Sets setuid or setgid bits",
+                    );
+                }
+            }
+        }
+        if has_flag(args, &['R'], &["recursive"]) {
+            self.flag(
+                RiskCategory::Destructive,
+                RiskLevel::Medium,
+                format!("Recursively changes permissions or ownership ({program})"),
+            );
+        }
+        self.check_write_args(args, 1, "Changes permissions of");
+    }
+
+    fn check_git(&mut self, args: &[String]) {
+        // Skip global options such as `-C <dir>` to find the subcommand
+        let mut index = 0;
+        while let Some(arg) = args.get(index) {
+            if arg == "-C" || arg == "-c" {
+                index += 2;
+            } else if is_flag(arg) {
+                index += 1;
+            } else {
+                break;
+            }
+        }
+        let Some(subcommand) = args.get(index) else {
+            return;
+        };
+        let rest = &args[index + 1..];
+
+        match subcommand.as_str() {
+            "push" => {
+                self.flag(
+                    RiskCategory::Network,
+                    RiskLevel::Medium,
+                    "Pushes to a remote (git push)",
+                );
+                if has_flag(rest, &['f'], &["force", "force-with-lease", "mirror"])
+                    || rest.iter().any(|arg| arg.starts_with('+'))
+                {
+                    self.flag(
+                        RiskCategory::HistoryRewrite,
+                        RiskLevel::High,
+                        "Force-pushes, overwriting remote history",
+                    );
+                }
+                if has_flag(rest, &['d'], &["delete"])
+                    || rest.iter().any(|arg| arg.starts_with(':'))
+                {
+                    self.flag(
+                        RiskCategory::Destructive,
+                        RiskLevel::High,
+                        "Deletes a remote branch",
+                    );
+                }
+            }
+            "fetch" | "pull" | "clone" | "ls-remote" | "submodule" => {
+                self.flag(
+                    RiskCategory::Network,
+                    RiskLevel::Medium,
+                    format!("Accesses a remote (git {subcommand})"),
+                );
+            }
+            "reset" if has_flag(rest, &[], &["hard"]) => {
+                self.flag(
+                    RiskCategory::HistoryRewrite,
+                    RiskLevel::High,
+                    "Moves the branch and discards uncommitted changes (git reset --hard)",
+                );
+            }
+            "rebase" => {
+                self.flag(
+                    RiskCategory::HistoryRewrite,
+                    RiskLevel::Medium,
+                    "Rewrites commits (git rebase)",
+                );
+            }
+            "commit" if has_flag(rest, &[], &["amend"]) => {
+                self.flag(
+                    RiskCategory::HistoryRewrite,
+                    RiskLevel::Medium,
+                    "Rewrites the last commit (git commit --amend)",
+                );
+            }
+            "filter-branch" | "filter-repo" => {
+                self.flag(
+                    RiskCategory::HistoryRewrite,
+                    RiskLevel::High,
+                    format!("Rewrites repository history (git {subcommand})"),
+                );
+            }
+            "reflog"
+                if rest
+                    .first()
+                    .is_some_and(|arg| arg == "expire" || arg == "delete") =>
+            {
+                self.flag(
+                    RiskCategory::HistoryRewrite,
+                    RiskLevel::High,
+                    "Drops reflog entries",
+                );
+            }
+            "update-ref" if has_flag(rest, &['d'], &[]) => {
+                self.flag(
+                    RiskCategory::HistoryRewrite,
+                    RiskLevel::High,
+                    "Deletes a ref (git update-ref -d)",
+                );
+            }
+            "branch"
+                if has_flag(rest, &['D'], &[])
+                    || (has_flag(rest, &['d'], &["delete"])
+                        && has_flag(rest, &['f'], &["force"])) =>
+            {
+                self.flag(
+                    RiskCategory::HistoryRewrite,
+                    RiskLevel::Medium,
+                    "Force-deletes a branch",
+                );
+            }
+            "clean" if has_flag(rest, &['f'], &["force"]) => {
+                self.flag(
+                    RiskCategory::Destructive,
+                    RiskLevel::High,
+                    "Deletes untracked files (git clean)",
+                );
+            }
+            "checkout" | "restore" if rest.iter().any(|arg| arg == "--" || arg == ".") => {
+                self.flag(
+                    RiskCategory::Destructive,
+                    RiskLevel::Medium,
+                    format!("Discards uncommitted changes (git {subcommand})"),
+                );
+            }
+            "stash"
+                if rest
+                    .first()
+                    .is_some_and(|arg| arg == "drop" || arg == "clear") =>
+            {
+                self.flag(
+                    RiskCategory::Destructive,
+                    RiskLevel::Medium,
+                    "Drops stashed changes",
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn check_package_manager(&mut self, program: &str, args: &[String]) {
+        let Some(subcommand) = args.iter().find(|arg| !is_flag(arg)) else {
+            return;
+        };
+        let network = match program {
+            "npm" | "pnpm" | "yarn" => matches!(
+                subcommand.as_str(),
+                "install" | "i" | "add" | "ci" | "publish" | "update" | "upgrade" | "dlx"
+            ),
+            "pip" | "pip3" => matches!(subcommand.as_str(), "install" | "download"),
+            "cargo" => matches!(
+                subcommand.as_str(),
+                "install" | "publish" | "fetch" | "update"
+            ),
+            "gem" => subcommand == "install",
+            "go" => matches!(subcommand.as_str(), "get" | "install"),
+            "docker" => matches!(subcommand.as_str(), "pull" | "push" | "login"),
+            _ => false,
+        };
+        if network {
+            self.flag(
+                RiskCategory::Network,
+                RiskLevel::Medium,
+                format!("Downloads or publishes packages ({program} {subcommand})"),
+            );
+        }
+    }
+
+    /// Check every non-flag argument after the first `skip` as a write target.
+    fn check_write_args(&mut self, args: &[String], skip: usize, action: &str) {
+        for target in args.iter().filter(|arg| !is_flag(arg)).skip(skip) {
+            self.check_write(target, action);
+        }
+    }
+
+    fn check_write(&mut self, target: &str, action: &str) {
+        if self.is_outside_worktree(target) {
+            self.flag(
+                RiskCategory::OutsideWorktree,
+                RiskLevel::High,
+                format!("{action} {target}, outside the worktree"),
+            );
+        }
+    }
+
+    fn is_outside_worktree(&self, target: &str) -> bool {
+        if target.starts_with('~') || target.starts_with("$HOME") || target.starts_with("${HOME}") {
+            return true;
+        }
+        if is_pseudo_device(target) || target.contains('$') {
+            return false;
+        }
+
+        let path = Path::new(target);
+        let temp_dir = std::env::temp_dir();
+        if path.starts_with("/tmp") || path.starts_with(&temp_dir) {
+            return false;
+        }
+
+        match self.worktree_path {
+            Some(worktree) => {
+                let worktree = normalize(Path::new(worktree));
+                !normalize(&worktree.join(path)).starts_with(&worktree)
+            }
+            None => path.is_absolute() || target.starts_with(".."),
+        }
+    }
+}
+
+fn is_pseudo_device(target: &str) -> bool {
+    matches!(
+        target,
+        "/dev/null" | "/dev/stdout" | "/dev/stderr" | "/dev/tty"
+    ) || target.starts_with("/dev/fd/")
+}
+
+fn is_remote_path(arg: &str) -> bool {
+    !is_flag(arg)
+        && arg
+            .split_once(':')
+            .is_some_and(|(host, _)| !host.is_empty() && !host.contains('/'))
+}
+
+/// Programs that only read files or print information.
+const READ_ONLY_PROGRAMS: &[&str] = &[
+    "ls",
+    "cat",
+    "head",
+    "tail",
+    "wc",
+    "grep",
+    "egrep",
+    "fgrep",
+    "rg",
+    "ag",
+    "fd",
+    "tree",
+    "pwd",
+    "echo",
+    "printf",
+    "true",
+    "false",
+    "test",
+    "[",
+    "which",
+    "type",
+    "file",
+    "stat",
+    "du",
+    "df",
+    "uniq",
+    "cut",
+    "tr",
+    "diff",
+    "cmp",
+    "jq",
+    "date",
+    "whoami",
+    "uname",
+    "basename",
+    "dirname",
+    "realpath",
+    "readlink",
+    "nl",
+    "column",
+    "md5sum",
+    "sha1sum",
+    "sha256sum",
+    "cloc",
+    "tokei",
+    "id",
+    "hostname",
+    "ps",
+    "less",
+    "more",
+    "sort",
+    "sed",
+    "find",
+];
+/// `git` subcommands that never change the repo.
+const READ_ONLY_GIT_SUBCOMMANDS: &[&str] = &[
+    "status",
+    "log",
+    "diff",
+    "show",
+    "blame",
+    "rev-parse",
+    "ls-files",
+    "ls-tree",
+    "grep",
+    "describe",
+    "shortlog",
+    "cat-file",
+    "rev-list",
+    "name-rev",
+    "merge-base",
+    "whatchanged",
+];
+
+/// Whether a shell command can be shown to only read: every program in it,
+/// nested scripts and substitutions included, is known not to write, and
+/// output only goes to the terminal or `/dev/null`. Anything unrecognized
+/// counts as writing.
+pub fn command_is_read_only(command: &str) -> bool {
+    read_only_at(command, 0)
+}
+
+fn read_only_at(command: &str, depth: usize) -> bool {
+    if depth > MAX_NESTING {
+        return false;
+    }
+    let Tokens {
+        tokens,
+        substitutions,
+    } = tokenize(command);
+    if !substitutions
+        .iter()
+        .all(|substitution| read_only_at(substitution, depth + 1))
+    {
+        return false;
+    }
+
+    split_commands(tokens).iter().all(|command| {
+        command
+            .redirect_targets
+            .iter()
+            .all(|target| is_pseudo_device(target))
+            && words_read_only(&command.words, depth)
+    })
+}
+
+fn words_read_only(words: &[String], depth: usize) -> bool {
+    let mut start = 0;
+    while let Some(word) = words.get(start) {
+        if is_assignment(word) {
+            start += 1;
+        } else if WRAPPERS.contains(&program_name(word)) {
+            start += 1;
+            while words
+                .get(start)
+                .is_some_and(|w| is_flag(w) || w.chars().all(|c| c.is_ascii_digit()))
+            {
+                start += 1;
+            }
+        } else {
+            break;
+        }
+    }
+
+    let Some(program) = words.get(start) else {
+        return true;
+    };
+    let program = program_name(program);
+    let args = &words[start + 1..];
+
+    match program {
+        _ if SHELLS.contains(&program) => match args {
+            [flag, script, ..] if flag == "-c" => read_only_at(script, depth + 1),
+            _ => false,
+        },
+        "git" => git_read_only(args),
+        "sed" => !has_flag(args, &['i'], &["in-place"]),
+        "sort" => !has_flag(args, &['o'], &["output"]),
+        "find" => !args.iter().any(|arg| {
+            matches!(
+                arg.as_str(),
+                "-delete" | "-exec" | "-execdir" | "-ok" | "-okdir" | "-fprint" | "-fprintf"
+            )
+        }),
+        _ => READ_ONLY_PROGRAMS.contains(&program),
+    }
+}
+
+fn git_read_only(args: &[String]) -> bool {
+    let mut index = 0;
+    while let Some(arg) = args.get(index) {
+        if arg == "-C" || arg == "-c" {
+            index += 2;
+        } else if is_flag(arg) {
+            index += 1;
+        } else {
+            break;
+        }
+    }
+    let Some(subcommand) = args.get(index) else {
+        return true;
+    };
+    let rest = &args[index + 1..];
+
+    match subcommand.as_str() {
+        // Listing only; a name would create one
+        "branch" | "tag" | "remote" => rest.iter().all(|arg| {
+            matches!(
+                arg.as_str(),
+                "-a" | "-r" | "-v" | "-vv" | "-l" | "--list" | "--all" | "--remotes" | "--verbose"
+            ) || arg.starts_with("--contains")
+                || arg.starts_with("--merged")
+                || arg.starts_with("--no-merged")
+                || arg.starts_with("--sort")
+        }),
+        "stash" => matches!(rest.first().map(String::as_str), Some("list" | "show")),
+        "config" => has_flag(rest, &['l'], &["get", "get-all", "get-regexp", "list"]),
+        "diff" => !has_flag(rest, &[], &["output"]),
+        subcommand => READ_ONLY_GIT_SUBCOMMANDS.contains(&subcommand),
+    }
+}
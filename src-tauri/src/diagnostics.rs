@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::mcp_manager::MCP_SERVER_PATH_ENV;
+
+/// `git worktree remove` was added in git 2.17.
+pub const MIN_GIT_VERSION: Version = Version(2, 17, 0);
+/// First Claude Code release with `--permission-prompt-tool` and stream-json in `--print` mode.
+pub const MIN_CLAUDE_VERSION: Version = Version(1, 0, 0);
+/// The MCP SDK used by the worktree server requires Node 18.
+pub const MIN_NODE_VERSION: Version = Version(18, 0, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Version(pub u32, pub u32, pub u32);
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// Find the first `major.minor[.patch]` version number in a tool's `--version` output.
+pub fn parse_version(output: &str) -> Option<Version> {
+    output
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|token| token.contains('.'))
+        .find_map(|token| {
+            let mut parts = token.split('.').filter(|p| !p.is_empty());
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            let patch = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+            Some(Version(major, minor, patch))
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub version: Option<String>,
+    pub fix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentReport {
+    pub ok: bool,
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl EnvironmentReport {
+    pub fn new(checks: Vec<DiagnosticCheck>) -> Self {
+        Self {
+            ok: checks.iter().all(|c| c.status != CheckStatus::Error),
+            checks,
+        }
+    }
+}
+
+/// Run `program --version` and return its combined output.
+pub fn run_version_command(program: &str) -> Result<String, String> {
+    let output = Command::new(program)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("Failed to run '{program} --version': {e}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "'{program} --version' failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+fn check_version(
+    name: &str,
+    version_output: Result<String, String>,
+    minimum: Version,
+    missing_fix: &str,
+    outdated_fix: &str,
+) -> DiagnosticCheck {
+    let output = match version_output {
+        Ok(output) => output,
+        Err(e) => {
+            return DiagnosticCheck {
+                name: name.to_string(),
+                status: CheckStatus::Error,
+                detail: e,
+                version: None,
+                fix: Some(missing_fix.to_string()),
+            }
+        }
+    };
+
+    match parse_version(&output) {
+        Some(version) if version < minimum => DiagnosticCheck {
+            name: name.to_string(),
+            status: CheckStatus::Error,
+            detail: format!("Version {version} is not supported (minimum {minimum})"),
+            version: Some(version.to_string()),
+            fix: Some(outdated_fix.to_string()),
+        },
+        Some(version) => DiagnosticCheck {
+            name: name.to_string(),
+            status: CheckStatus::Ok,
+            detail: format!("Version {version}"),
+            version: Some(version.to_string()),
+            fix: None,
+        },
+        None => DiagnosticCheck {
+            name: name.to_string(),
+            status: CheckStatus::Warning,
+            detail: format!("Could not parse version from: {}", output.trim()),
+            version: None,
+            fix: None,
+        },
+    }
+}
+
+pub fn evaluate_git(version_output: Result<String, String>) -> DiagnosticCheck {
+    let mut check = check_version(
+        "git",
+        version_output,
+        MIN_GIT_VERSION,
+        "Install git 2.17 or newer and make sure it is in PATH.",
+        "Upgrade git to 2.17 or newer for `git worktree remove` support.",
+    );
+    if check.status == CheckStatus::Ok {
+        check.detail = format!("{} with worktree support", check.detail);
+    }
+    check
+}
+
+pub fn evaluate_claude(program: &str, version_output: Result<String, String>) -> DiagnosticCheck {
+    check_version(
+        "claude",
+        version_output,
+        MIN_CLAUDE_VERSION,
+        &format!(
+            "Install Claude Code with `npm install -g @anthropic-ai/claude-code` and make sure '{program}' is in PATH."
+        ),
+        "Update Claude Code with `claude update` or `npm install -g @anthropic-ai/claude-code@latest`.",
+    )
+}
+
+pub fn evaluate_node(version_output: Result<String, String>) -> DiagnosticCheck {
+    check_version(
+        "node",
+        version_output,
+        MIN_NODE_VERSION,
+        "Install Node.js 18 or newer; it is required to run the MCP approval server.",
+        "Upgrade Node.js to version 18 or newer.",
+    )
+}
+
+pub fn evaluate_mcp_server(server_path: Result<String, String>) -> DiagnosticCheck {
+    match server_path {
+        Ok(path) => DiagnosticCheck {
+            name: "mcp-server".to_string(),
+            status: CheckStatus::Ok,
+            detail: format!("Found MCP server at {path}"),
+            version: None,
+            fix: None,
+        },
+        Err(e) => DiagnosticCheck {
+            name: "mcp-server".to_string(),
+            status: CheckStatus::Error,
+            detail: e,
+            version: None,
+            fix: Some(format!(
+                "Point {MCP_SERVER_PATH_ENV} or the MCP server path override at mcp-server/dist/index.js, or reinstall Orchestra to restore the bundled server."
+            )),
+        },
+    }
+}
+
+/// Cached `--version` results for Claude binaries so every spawn does not pay
+/// for a version probe.
+#[derive(Default)]
+pub struct ClaudeVersionGate {
+    checked: Mutex<HashMap<String, Option<Version>>>,
+}
+
+impl ClaudeVersionGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuse to launch a Claude binary older than `MIN_CLAUDE_VERSION`.
+    /// Binaries that cannot be probed are let through so the spawn error is
+    /// reported and classified as usual.
+    pub fn ensure_supported(&self, program: &str) -> Result<(), String> {
+        let cached = self.checked.lock().unwrap().get(program).copied();
+        // Probe without the lock so concurrent starts do not queue behind it
+        let version = cached.unwrap_or_else(|| {
+            let version = run_version_command(program)
+                .ok()
+                .and_then(|o| parse_version(&o));
+            self.checked
+                .lock()
+                .unwrap()
+                .insert(program.to_string(), version);
+            version
+        });
+
+        match version {
+            Some(version) if version < MIN_CLAUDE_VERSION => Err(format!(
+                "Claude Code {version} is not supported; Orchestra requires {MIN_CLAUDE_VERSION} or newer. Update with `claude update`."
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.checked.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{
+        evaluate_claude, evaluate_git, evaluate_mcp_server, evaluate_node, parse_version,
+        CheckStatus, ClaudeVersionGate, EnvironmentReport, Version,
+    };
+
+    #[test]
+    fn test_parse_version_formats() {
+        let test_cases = vec![
+            ("git version 2.39.2", Some(Version(2, 39, 2))),
+            ("1.0.51 (Claude Code)", Some(Version(1, 0, 51))),
+            ("v20.11.0", Some(Version(20, 11, 0))),
+            ("git version 2.17.windows.1", Some(Version(2, 17, 0))),
+            ("version 3.4", Some(Version(3, 4, 0))),
+            ("no version here", None),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse_version(input), expected, "Failed for input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        assert!(Version(2, 17, 0) > Version(2, 9, 5));
+        assert!(Version(1, 0, 0) > Version(0, 2, 125));
+        assert_eq!(Version(1, 2, 3).to_string(), "1.2.3");
+    }
+
+    #[test]
+    fn test_evaluate_git() {
+        let check = evaluate_git(Ok("git version 2.43.0\n".to_string()));
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert_eq!(check.version.as_deref(), Some("2.43.0"));
+        assert!(check.detail.contains("worktree support"));
+
+        let check = evaluate_git(Ok("git version 2.7.4".to_string()));
+        assert_eq!(check.status, CheckStatus::Error);
+        assert!(check.fix.unwrap().contains("2.17"));
+    }
+
+    #[test]
+    fn test_evaluate_claude_missing_and_outdated() {
+        let check = evaluate_claude("claude", Err("No such file or directory".to_string()));
+        assert_eq!(check.status, CheckStatus::Error);
+        assert!(check
+            .fix
+            .unwrap()
+            .contains("npm install -g @anthropic-ai/claude-code"));
+
+        let check = evaluate_claude("claude", Ok("0.2.9 (Claude Code)".to_string()));
+        assert_eq!(check.status, CheckStatus::Error);
+        assert!(check.detail.contains("not supported"));
+
+        let check = evaluate_claude("claude", Ok("unknown build".to_string()));
+        assert_eq!(check.status, CheckStatus::Warning);
+    }
+
+    #[test]
+    fn test_report_ok_ignores_warnings() {
+        let report = EnvironmentReport::new(vec![
+            evaluate_node(Ok("v20.11.0".to_string())),
+            evaluate_claude("claude", Ok("dev build".to_string())),
+        ]);
+        assert!(report.ok);
+
+        let report = EnvironmentReport::new(vec![
+            evaluate_node(Ok("v16.20.0".to_string())),
+            evaluate_mcp_server(Ok("/opt/mcp-server/dist/index.js".to_string())),
+        ]);
+        assert!(!report.ok);
+        assert_eq!(report.checks[1].status, CheckStatus::Ok);
+    }
+
+    #[test]
+    fn test_version_gate_allows_unprobeable_binaries() {
+        let gate = ClaudeVersionGate::new();
+        assert!(gate
+            .ensure_supported("/nonexistent/orchestra-test-claude")
+            .is_ok());
+    }
+}
@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use crate::launch_profiles::{LaunchProfile, LaunchProfileStore};
+    use std::collections::HashMap;
+
+    fn create_test_profile(name: &str) -> LaunchProfile {
+        LaunchProfile {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_profile_cli_args() {
+        let profile = LaunchProfile {
+            name: "reviewer".to_string(),
+            backend: None,
+            binary_path: Some("/opt/claude/bin/claude".to_string()),
+            model: Some("sonnet".to_string()),
+            max_turns: Some(5),
+            allowed_tools: vec!["Read".to_string(), "Bash(git log:*)".to_string()],
+            disallowed_tools: vec!["Write".to_string()],
+            append_system_prompt: Some("Only review.".to_string()),
+            env: HashMap::new(),
+            extra_args: vec!["--add-dir".to_string(), "../shared".to_string()],
+        };
+
+        assert_eq!(
+            profile.cli_args(),
+            vec![
+                "--model",
+                "sonnet",
+                "--max-turns",
+                "5",
+                "--allowedTools",
+                "Read,Bash(git log:*)",
+                "--disallowedTools",
+                "Write",
+                "--append-system-prompt",
+                "Only review.",
+                "--add-dir",
+                "../shared",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_profile_adds_no_args() {
+        let profile = create_test_profile("empty");
+
+        assert!(profile.cli_args().is_empty());
+    }
+
+    #[test]
+    fn test_profile_deserialization_defaults() {
+        let profile: LaunchProfile =
+            serde_json::from_str(r#"{"name":"fast","model":"haiku"}"#).unwrap();
+
+        assert_eq!(profile.model.as_deref(), Some("haiku"));
+        assert!(profile.allowed_tools.is_empty());
+        assert!(profile.env.is_empty());
+    }
+
+    #[test]
+    fn test_store_rejects_unnamed_profile() {
+        let mut store = LaunchProfileStore::new();
+        assert!(store.save(create_test_profile("  ")).is_err());
+        assert!(store.list().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let mut store = LaunchProfileStore::new();
+        store.save(create_test_profile("message")).unwrap();
+        store.save(create_test_profile("worktree")).unwrap();
+        store.save(create_test_profile("repo")).unwrap();
+
+        store
+            .set_repo_default("/repo".to_string(), Some("repo".to_string()))
+            .unwrap();
+        assert_eq!(
+            store
+                .resolve(None, "wt-1", Some("/repo"))
+                .unwrap()
+                .unwrap()
+                .name,
+            "repo"
+        );
+
+        store
+            .set_worktree_default("wt-1".to_string(), Some("worktree".to_string()))
+            .unwrap();
+        assert_eq!(
+            store
+                .resolve(None, "wt-1", Some("/repo"))
+                .unwrap()
+                .unwrap()
+                .name,
+            "worktree"
+        );
+
+        assert_eq!(
+            store
+                .resolve(Some("message"), "wt-1", Some("/repo"))
+                .unwrap()
+                .unwrap()
+                .name,
+            "message"
+        );
+
+        assert!(store.resolve(None, "wt-2", None).unwrap().is_none());
+        assert!(store.resolve(Some("missing"), "wt-1", None).is_err());
+    }
+
+    #[test]
+    fn test_remove_profile_clears_defaults() {
+        let mut store = LaunchProfileStore::new();
+        store.save(create_test_profile("temp")).unwrap();
+        store
+            .set_worktree_default("wt-1".to_string(), Some("temp".to_string()))
+            .unwrap();
+
+        store.remove("temp").unwrap();
+
+        assert!(store.resolve(None, "wt-1", None).unwrap().is_none());
+        assert!(store.remove("temp").is_err());
+        assert!(store
+            .set_worktree_default("wt-1".to_string(), Some("temp".to_string()))
+            .is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A named way of invoking an agent CLI (Claude Code unless `backend` says otherwise).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LaunchProfile {
+    pub name: String,
+    #[serde(default)]
+    pub backend: Option<String>,
+    pub binary_path: Option<String>,
+    pub model: Option<String>,
+    pub max_turns: Option<u32>,
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+    pub append_system_prompt: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+impl LaunchProfile {
+    /// Claude CLI arguments contributed by this profile, placed before the permission flags.
+    pub fn cli_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(model) = &self.model {
+            args.push("--model".to_string());
+            args.push(model.clone());
+        }
+        if let Some(max_turns) = self.max_turns {
+            args.push("--max-turns".to_string());
+            args.push(max_turns.to_string());
+        }
+        if !self.allowed_tools.is_empty() {
+            args.push("--allowedTools".to_string());
+            args.push(self.allowed_tools.join(","));
+        }
+        if !self.disallowed_tools.is_empty() {
+            args.push("--disallowedTools".to_string());
+            args.push(self.disallowed_tools.join(","));
+        }
+        if let Some(prompt) = &self.append_system_prompt {
+            args.push("--append-system-prompt".to_string());
+            args.push(prompt.clone());
+        }
+        args.extend(self.extra_args.iter().cloned());
+
+        args
+    }
+}
+
+/// Named launch profiles plus the per-worktree and per-repo defaults.
+#[derive(Debug, Default)]
+pub struct LaunchProfileStore {
+    profiles: HashMap<String, LaunchProfile>,
+    worktree_defaults: HashMap<String, String>,
+    repo_defaults: HashMap<String, String>,
+}
+
+impl LaunchProfileStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list(&self) -> Vec<LaunchProfile> {
+        let mut profiles: Vec<LaunchProfile> = self.profiles.values().cloned().collect();
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        profiles
+    }
+
+    pub fn save(&mut self, profile: LaunchProfile) -> Result<(), String> {
+        if profile.name.trim().is_empty() {
+            return Err("Launch profile name cannot be empty".to_string());
+        }
+        self.profiles.insert(profile.name.clone(), profile);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        if self.profiles.remove(name).is_none() {
+            return Err(format!("Launch profile not found: {name}"));
+        }
+        self.worktree_defaults.retain(|_, profile| profile != name);
+        self.repo_defaults.retain(|_, profile| profile != name);
+        Ok(())
+    }
+
+    pub fn set_worktree_default(
+        &mut self,
+        worktree_id: String,
+        profile_name: Option<String>,
+    ) -> Result<(), String> {
+        Self::set_default(
+            &self.profiles,
+            &mut self.worktree_defaults,
+            worktree_id,
+            profile_name,
+        )
+    }
+
+    pub fn set_repo_default(
+        &mut self,
+        repo_path: String,
+        profile_name: Option<String>,
+    ) -> Result<(), String> {
+        Self::set_default(
+            &self.profiles,
+            &mut self.repo_defaults,
+            repo_path,
+            profile_name,
+        )
+    }
+
+    fn set_default(
+        profiles: &HashMap<String, LaunchProfile>,
+        defaults: &mut HashMap<String, String>,
+        key: String,
+        profile_name: Option<String>,
+    ) -> Result<(), String> {
+        match profile_name {
+            Some(name) => {
+                if !profiles.contains_key(&name) {
+                    return Err(format!("Launch profile not found: {name}"));
+                }
+                defaults.insert(key, name);
+            }
+            None => {
+                defaults.remove(&key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pick the profile for a run: the explicitly requested one, then the
+    /// worktree default, then the repo default. `Ok(None)` means use the
+    /// built-in Claude invocation.
+    pub fn resolve(
+        &self,
+        requested: Option<&str>,
+        worktree_id: &str,
+        repo_path: Option<&str>,
+    ) -> Result<Option<LaunchProfile>, String> {
+        if let Some(name) = requested {
+            return self
+                .profiles
+                .get(name)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| format!("Launch profile not found: {name}"));
+        }
+
+        let name = self
+            .worktree_defaults
+            .get(worktree_id)
+            .or_else(|| repo_path.and_then(|repo| self.repo_defaults.get(repo)));
+
+        Ok(name.and_then(|name| self.profiles.get(name)).cloned())
+    }
+}
@@ -1,17 +1,68 @@
-use axum::{routing::post, Router};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::{AppHandle, Emitter, Manager, State};
 use tower_http::cors::CorsLayer;
 use uuid::Uuid;
 
+mod agent_backends;
+mod approval_broker;
+mod approval_context;
+mod approval_grants;
+mod claude_errors;
+mod command_risk;
+mod diagnostics;
+mod launch_profiles;
+mod logging;
+mod mcp_config;
+mod mcp_logs;
 mod mcp_manager;
-use mcp_manager::{ApprovalRequest, ApprovalResponse, HttpAppState, McpManager};
+mod mcp_supervisor;
+mod metrics;
+mod port_allocator;
+mod process_metrics;
+mod protected_paths;
+mod read_only;
+mod redaction;
+mod resource_limits;
+mod sandbox;
+mod tool_schemas;
+use agent_backends::{
+    AgentBackend, AgentBackendInfo, AgentBackendRegistry, AgentLaunchRequest, AgentLaunchSpec,
+    AgentLine, DEFAULT_BACKEND,
+};
+use approval_broker::{ApprovalRecord, APPROVAL_HISTORY_LIMIT};
+use approval_grants::ApprovalGrant;
+use claude_errors::{classify_spawn_error, ClaudeErrorCode, RetryPolicy};
+use diagnostics::{ClaudeVersionGate, EnvironmentReport};
+use launch_profiles::{LaunchProfile, LaunchProfileStore};
+use logging::{AppLogEntry, LogQuery, Logging, DEFAULT_APP_LOG_TAIL};
+use mcp_config::{
+    ExtraMcpServer, ExtraMcpServerStore, McpConfigBuilder, McpTemplateContext, PROCESS_ID_ENV,
+};
+use mcp_logs::{LogEntry, LogLevel, DEFAULT_LOG_TAIL};
+use mcp_manager::{
+    ApprovalRequest, ApprovalResponse, HttpAppState, McpManager, McpTransport, APPROVAL_SERVER_PORT,
+};
+use mcp_supervisor::{McpServerState, McpServerStatus, SUPERVISOR_INTERVAL};
+use metrics::{MetricsSnapshot, RunMetrics};
+use process_metrics::{MetricsSampler, ProcessMetrics, SampleTarget, METRICS_INTERVAL};
+use protected_paths::{ProtectedPathStore, WorktreeSnapshot};
+use read_only::WorktreeStatus;
+use redaction::{load_patterns, save_patterns, LineRedactor, Redactor, REDACTION_PATTERNS_FILE};
+use resource_limits::{AppliedLimits, ProcessLimits, ResourceLimits, ResourceUsage};
+use sandbox::{
+    check_available, wrap_launch, NetworkBridge, SandboxMounts, SandboxNetwork, SandboxOptions,
+    SandboxViolation,
+};
 
 #[cfg(test)]
 mod tests;
@@ -22,6 +73,66 @@ mod tests_extended;
 #[cfg(test)]
 mod approval_tests;
 
+#[cfg(test)]
+mod claude_error_tests;
+
+#[cfg(test)]
+mod launch_profile_tests;
+
+#[cfg(test)]
+mod agent_backend_tests;
+
+#[cfg(test)]
+mod diagnostics_tests;
+
+#[cfg(test)]
+mod mcp_runtime_tests;
+
+#[cfg(test)]
+mod mcp_config_tests;
+
+#[cfg(test)]
+mod mcp_supervisor_tests;
+
+#[cfg(test)]
+mod mcp_log_tests;
+
+#[cfg(test)]
+mod port_allocator_tests;
+
+#[cfg(test)]
+mod approval_broker_tests;
+
+#[cfg(test)]
+mod approval_context_tests;
+
+#[cfg(test)]
+mod command_risk_tests;
+
+#[cfg(test)]
+mod approval_grants_tests;
+
+#[cfg(test)]
+mod tool_schemas_tests;
+
+#[cfg(test)]
+mod protected_paths_tests;
+
+#[cfg(test)]
+mod logging_tests;
+#[cfg(test)]
+mod metrics_tests;
+#[cfg(test)]
+mod process_metrics_tests;
+#[cfg(test)]
+mod read_only_tests;
+#[cfg(test)]
+mod redaction_tests;
+#[cfg(test)]
+mod resource_limits_tests;
+#[cfg(test)]
+mod sandbox_tests;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WorktreeConfig {
     pub id: String,
@@ -38,10 +149,179 @@ pub struct ClaudeProcess {
     pub id: String,
     pub worktree_id: String,
     pub pid: Option<u32>,
-    pub status: String, // 'idle' | 'running' | 'stopped' | 'error'
+    pub status: String, // 'idle' | 'running' | 'awaiting_approval' | 'stopped' | 'error'
     pub task: Option<String>,
     pub started_at: Option<String>,
     pub last_activity: Option<String>,
+    #[serde(default)]
+    pub launch_profile: Option<String>,
+    #[serde(default = "default_backend_id")]
+    pub backend: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Started with `read_only`: write tools are unavailable and the worktree
+    /// is checked for changes when the run ends.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Changes found in the worktree after a read-only run.
+    #[serde(default)]
+    pub read_only_violations: Vec<String>,
+    /// Set when the agent runs inside the sandbox.
+    #[serde(default)]
+    pub sandbox: Option<SandboxOptions>,
+    /// CPU, memory, file and process limits the run was started with.
+    #[serde(default)]
+    pub resource_limits: Option<AppliedLimits>,
+    /// Sampled by `list_processes` while the process runs.
+    #[serde(default)]
+    pub resource_usage: Option<ResourceUsage>,
+    /// Secrets masked in the process's output and approval requests.
+    #[serde(default)]
+    pub redactions: u64,
+}
+
+/// Move a process between `running` and `awaiting_approval` while it has a
+/// tool approval open, and tell the UI. Processes in any other state are left alone.
+pub(crate) fn set_process_awaiting_approval(
+    app_handle: &AppHandle,
+    process_id: &str,
+    awaiting: bool,
+) {
+    let (from, to) = if awaiting {
+        ("running", "awaiting_approval")
+    } else {
+        ("awaiting_approval", "running")
+    };
+
+    let changed = match app_handle
+        .state::<AppState>()
+        .processes
+        .lock()
+        .unwrap()
+        .get_mut(process_id)
+    {
+        Some(process) if process.status == from => {
+            process.status = to.to_string();
+            true
+        }
+        _ => false,
+    };
+
+    if changed {
+        let _ = app_handle.emit(
+            "claude-process-status",
+            &serde_json::json!({ "process_id": process_id, "status": to }),
+        );
+    }
+}
+
+/// The checkout path of a worktree, from the backend's worktree list or, for
+/// worktrees created by the frontend, from the MCP server started for it.
+pub(crate) async fn worktree_path_for(app_handle: &AppHandle, worktree_id: &str) -> Option<String> {
+    let state = app_handle.state::<AppState>();
+    let known = state
+        .worktrees
+        .lock()
+        .unwrap()
+        .get(worktree_id)
+        .map(|worktree| worktree.path.clone());
+    match known {
+        Some(path) => Some(path),
+        None => state
+            .mcp_manager
+            .server_for_worktree(worktree_id)
+            .await
+            .map(|server| server.worktree_path),
+    }
+}
+
+/// Whether tool requests from a process must not change anything. A request
+/// that names its process is judged by that process alone; one that does not
+/// counts as read-only while a read-only process is live in the worktree.
+pub(crate) fn process_is_read_only(
+    app_handle: &AppHandle,
+    process_id: Option<&str>,
+    worktree_id: &str,
+) -> bool {
+    let state = app_handle.state::<AppState>();
+    if let Some(process_id) = process_id {
+        return state
+            .processes
+            .lock()
+            .unwrap()
+            .get(process_id)
+            .is_some_and(|process| process.read_only);
+    }
+
+    let live: Vec<String> = state
+        .running_processes
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    let processes = state.processes.lock().unwrap();
+    processes.values().any(|process| {
+        process.read_only
+            && process.worktree_id == worktree_id
+            && matches!(process.status.as_str(), "running" | "awaiting_approval")
+            && live.contains(&process.id)
+    })
+}
+
+/// The protected path patterns of a repo, empty when it is unknown.
+pub(crate) fn protected_patterns_for(
+    app_handle: &AppHandle,
+    repo_path: Option<&str>,
+) -> Vec<String> {
+    repo_path
+        .map(|repo| {
+            app_handle
+                .state::<AppState>()
+                .protected_paths
+                .lock()
+                .unwrap()
+                .patterns(repo)
+        })
+        .unwrap_or_default()
+}
+
+/// The main checkout of a worktree's repo: the backend's record, or else the
+/// directory holding git's common dir.
+pub(crate) fn repo_path_for(
+    app_handle: &AppHandle,
+    worktree_id: &str,
+    worktree_path: Option<&str>,
+) -> Option<String> {
+    let known = app_handle
+        .state::<AppState>()
+        .worktrees
+        .lock()
+        .unwrap()
+        .get(worktree_id)
+        .map(|worktree| worktree.base_repo.clone());
+    if known.is_some() {
+        return known;
+    }
+
+    let worktree_path = worktree_path?;
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-common-dir"])
+        .current_dir(worktree_path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let common_dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    std::path::Path::new(worktree_path)
+        .join(common_dir)
+        .parent()
+        .map(|repo| repo.to_string_lossy().to_string())
+}
+
+fn default_backend_id() -> String {
+    DEFAULT_BACKEND.to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -121,9 +401,7 @@ fn parse_claude_json_line(line: &str) -> Option<String> {
 
                             // Only show text content
                             if !text_results.is_empty() {
-                                let result = text_results.join("\n");
-                                eprintln!("FINAL RESPONSE: {result}");
-                                Some(result)
+                                Some(text_results.join("\n"))
                             } else {
                                 None
                             }
@@ -169,6 +447,17 @@ pub struct AppState {
     pub processes: Mutex<HashMap<String, ClaudeProcess>>,
     pub running_processes: Mutex<HashMap<String, Arc<Mutex<Option<Child>>>>>,
     pub mcp_manager: McpManager,
+    pub retry_policy: Mutex<RetryPolicy>,
+    pub launch_profiles: Mutex<LaunchProfileStore>,
+    pub agent_backends: AgentBackendRegistry,
+    pub claude_version_gate: ClaudeVersionGate,
+    pub extra_mcp_servers: Mutex<ExtraMcpServerStore>,
+    pub protected_paths: Mutex<ProtectedPathStore>,
+    pub resource_limits: Mutex<ResourceLimits>,
+    pub process_metrics: Mutex<MetricsSampler>,
+    pub run_metrics: Mutex<RunMetrics>,
+    pub logging: Logging,
+    pub redactor: Mutex<Arc<Redactor>>,
 }
 
 impl Default for AppState {
@@ -178,6 +467,17 @@ impl Default for AppState {
             processes: Mutex::new(HashMap::new()),
             running_processes: Mutex::new(HashMap::new()),
             mcp_manager: McpManager::new(),
+            retry_policy: Mutex::new(RetryPolicy::default()),
+            launch_profiles: Mutex::new(LaunchProfileStore::new()),
+            agent_backends: AgentBackendRegistry::new(),
+            claude_version_gate: ClaudeVersionGate::new(),
+            extra_mcp_servers: Mutex::new(ExtraMcpServerStore::new()),
+            protected_paths: Mutex::new(ProtectedPathStore::new()),
+            resource_limits: Mutex::new(ResourceLimits::default()),
+            process_metrics: Mutex::new(MetricsSampler::new()),
+            run_metrics: Mutex::new(RunMetrics::default()),
+            logging: Logging::default(),
+            redactor: Mutex::new(Arc::new(Redactor::default())),
         }
     }
 }
@@ -238,6 +538,8 @@ async fn list_worktrees(state: State<'_, AppState>) -> Result<Vec<WorktreeConfig
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "process", skip_all, fields(process_id, worktree_id = %worktree_id))]
 async fn start_claude_process(
     app_handle: AppHandle,
     state: State<'_, AppState>,
@@ -245,8 +547,33 @@ async fn start_claude_process(
     worktree_id: String,
     user_message: String,
     permission_mode: Option<String>,
+    launch_profile: Option<String>,
+    repo_path: Option<String>,
+    backend: Option<String>,
+    resume_session_id: Option<String>,
+    read_only: Option<bool>,
+    sandbox: Option<SandboxOptions>,
 ) -> Result<ClaudeProcess, String> {
+    let read_only = read_only.unwrap_or(false);
+    if let Some(options) = &sandbox {
+        check_available(options)?;
+    }
     let process_id = Uuid::new_v4().to_string();
+    tracing::Span::current().record("process_id", process_id.as_str());
+
+    // Resolve the launch profile: per message, then per worktree, then per repo
+    let worktree = state.worktrees.lock().unwrap().get(&worktree_id).cloned();
+    let repo_path = repo_path.or_else(|| worktree.as_ref().map(|wt| wt.base_repo.clone()));
+    let profile = state.launch_profiles.lock().unwrap().resolve(
+        launch_profile.as_deref(),
+        &worktree_id,
+        repo_path.as_deref(),
+    )?;
+
+    let backend_id = backend
+        .or_else(|| profile.as_ref().and_then(|p| p.backend.clone()))
+        .unwrap_or_else(|| DEFAULT_BACKEND.to_string());
+    let agent_backend = state.agent_backends.get(&backend_id)?;
 
     // Create the Claude process record
     let mut claude_process = ClaudeProcess {
@@ -257,93 +584,199 @@ async fn start_claude_process(
         task: Some(user_message.clone()),
         started_at: Some(chrono::Utc::now().to_rfc3339()),
         last_activity: Some(chrono::Utc::now().to_rfc3339()),
+        launch_profile: profile.as_ref().map(|p| p.name.clone()),
+        backend: backend_id.clone(),
+        session_id: resume_session_id.clone(),
+        read_only,
+        read_only_violations: Vec::new(),
+        sandbox: sandbox.clone(),
+        resource_limits: None,
+        resource_usage: None,
+        redactions: 0,
     };
 
-    // Spawn Claude Code process with print mode and stream-json output
-    let mut cmd = Command::new("claude");
-    cmd.arg("--print")
-        .arg("--verbose")
-        .arg("--output-format")
-        .arg("stream-json");
-
     // Set permission mode based on user preference
-    eprintln!("🔧 Permission mode: {permission_mode:?}");
-    match permission_mode.as_deref().unwrap_or("safe") {
-        "full" => {
-            cmd.arg("--dangerously-skip-permissions");
+    tracing::debug!(?permission_mode, "Permission mode");
+    let permission_mode = permission_mode.unwrap_or_else(|| "safe".to_string());
+    let mut mcp_builder = McpConfigBuilder::new();
+    let mut approval_prompt_available = false;
+    // Local servers a network-restricted sandbox must still reach
+    let mut local_ports = vec![APPROVAL_SERVER_PORT];
+    if permission_mode == "mcp" && agent_backend.supports_permission_prompt() {
+        // Connect to this worktree's MCP server, starting it if needed
+        match state
+            .mcp_manager
+            .ensure_server(
+                worktree_id.clone(),
+                worktree_path.clone(),
+                app_handle.clone(),
+            )
+            .await
+        {
+            Ok(server_config) => {
+                tracing::info!(
+                    server_id = %server_config.server_id,
+                    "Connecting the agent to the worktree's MCP server"
+                );
+                mcp_builder = match server_config.sse_url() {
+                    // Reuse the worktree's long-running SSE server
+                    Some(url) => mcp_builder.orchestra_sse_server(&url, &process_id),
+                    None => mcp_builder.orchestra_server(
+                        &server_config.server_path,
+                        &worktree_path,
+                        &worktree_id,
+                        &process_id,
+                    ),
+                };
+                approval_prompt_available = true;
+                local_ports.extend(server_config.port);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    "No MCP server available for the worktree, falling back to safe mode"
+                );
+            }
         }
-        "mcp" => {
-            // Connect to our MCP server for this worktree
-            // We need to find the server path for this worktree
-            eprintln!("🔍 Looking for MCP server for worktree: {worktree_id}");
-            let servers = state.mcp_manager.list_servers().await;
-            eprintln!("🔍 Available MCP servers: {servers:?}");
-            let server_for_worktree = servers.iter().find(|s| s.worktree_id == worktree_id);
-
-            if let Some(server_config) = server_for_worktree {
-                // Create MCP config JSON for Claude Code
-                let mcp_config = serde_json::json!({
-                    "mcpServers": {
-                        "orchestra-worktree": {
-                            "command": "node",
-                            "args": [server_config.server_path],
-                            "env": {
-                                "WORKTREE_PATH": worktree_path,
-                                "WORKTREE_ID": worktree_id
-                            }
-                        }
-                    }
-                });
+    }
 
-                // Write config to temporary file
-                let config_file = format!("/tmp/mcp_config_{worktree_id}.json");
-                if let Err(e) = std::fs::write(&config_file, mcp_config.to_string()) {
-                    eprintln!("Failed to write MCP config: {e}");
-                    cmd.arg("--permission-mode").arg("acceptEdits");
-                } else {
-                    cmd.arg("--mcp-config")
-                        .arg(&config_file)
-                        .arg("--permission-prompt-tool")
-                        .arg("mcp__orchestra-worktree__approval_prompt");
-                    eprintln!("🔗 Connecting Claude to MCP server: {} using config: {} with permission tool", 
-                        server_config.server_id, config_file);
-                }
-            } else {
-                eprintln!(
-                    "⚠️  No MCP server found for worktree {worktree_id}, falling back to safe mode"
-                );
-                cmd.arg("--permission-mode").arg("acceptEdits");
+    // User-defined MCP servers for this repo and worktree
+    if agent_backend.supports_mcp_config() {
+        let extra_servers = state
+            .extra_mcp_servers
+            .lock()
+            .unwrap()
+            .resolve(&worktree_id, repo_path.as_deref());
+        mcp_builder = mcp_builder.extra_servers(
+            &extra_servers,
+            &McpTemplateContext {
+                worktree_path: &worktree_path,
+                worktree_id: &worktree_id,
+                repo_path: repo_path.as_deref(),
+                branch: worktree.as_ref().map(|wt| wt.branch.as_str()),
+            },
+        );
+    }
+
+    // Write a private config for this run; it is removed when the process ends
+    let mut mcp_config = None;
+    if !mcp_builder.is_empty() {
+        match mcp_builder.write_private(&std::env::temp_dir(), &process_id) {
+            Ok(config) => {
+                tracing::debug!(path = %config.path().display(), "Using MCP config");
+                mcp_config = Some(config);
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to write MCP config");
+                approval_prompt_available = false;
             }
         }
-        _ => {
-            cmd.arg("--permission-mode").arg("acceptEdits");
+    }
+
+    let mcp_config_path = mcp_config.as_ref().map(|config| config.path_str());
+    let mut launch_spec = agent_backend.build_launch(&AgentLaunchRequest {
+        worktree_path: &worktree_path,
+        message: &user_message,
+        permission_mode: &permission_mode,
+        mcp_config_path: mcp_config_path.as_deref(),
+        approval_prompt_available,
+        profile: profile.as_ref(),
+        resume_session_id: resume_session_id.as_deref(),
+        read_only,
+    })?;
+    launch_spec.envs.insert(
+        0,
+        (
+            "APPROVAL_ENDPOINT".to_string(),
+            format!("http://localhost:{APPROVAL_SERVER_PORT}/api/approval-request"),
+        ),
+    );
+    launch_spec
+        .envs
+        .push((PROCESS_ID_ENV.to_string(), process_id.clone()));
+
+    if backend_id == DEFAULT_BACKEND {
+        state
+            .claude_version_gate
+            .ensure_supported(&launch_spec.program)?;
+    }
+
+    // Keep the agent to its worktree, and off the network when restricted
+    let mut network_bridge = None;
+    if let Some(options) = &sandbox {
+        let mut mounts = SandboxMounts::for_worktree(&worktree_path);
+        mounts.read_only.extend(mcp_config_path.clone());
+        if options.network == SandboxNetwork::Restricted {
+            let violation_handle = app_handle.clone();
+            let violation_process_id = process_id.clone();
+            network_bridge = Some(NetworkBridge::start(
+                &process_id,
+                &local_ports,
+                options.allowed_hosts(),
+                Arc::new(move |violation| {
+                    report_sandbox_violation(&violation_handle, &violation_process_id, &violation)
+                }),
+            )?);
         }
+        launch_spec = wrap_launch(&launch_spec, &mounts, network_bridge.as_ref());
     }
 
-    let child = cmd
-        .arg(&user_message)
-        .current_dir(&worktree_path)
-        .env(
-            "APPROVAL_ENDPOINT",
-            "http://localhost:8080/api/approval-request",
-        )
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+    // Keep a runaway run from starving the other worktrees
+    let limits = state.resource_limits.lock().unwrap().clone();
+    let process_limits = match limits.is_empty() {
+        true => None,
+        false => Some(ProcessLimits::prepare(&process_id, &limits)?),
+    };
+    if let Some(process_limits) = &process_limits {
+        launch_spec = process_limits.wrap_launch(&launch_spec);
+        let applied = process_limits.applied().clone();
+        for reason in &applied.unenforced {
+            let output = ProcessOutput {
+                process_id: process_id.clone(),
+                content: format!("⚠️ Resource limit not applied: {reason}"),
+                is_error: false,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            };
+            let _ = app_handle.emit("claude-output", &output);
+        }
+        claude_process.resource_limits = Some(applied);
+    }
+
+    // The run's repo, for its cost total and protected paths
+    let run_repo = repo_path
+        .clone()
+        .or_else(|| repo_path_for(&app_handle, &worktree_id, Some(&worktree_path)));
+
+    // Note what the worktree already had changed, for the protected path check
+    // at the end. Repos without protected paths have nothing to check.
+    let snapshot = (!protected_patterns_for(&app_handle, run_repo.as_deref()).is_empty())
+        .then(|| WorktreeSnapshot::capture(&worktree_path));
+    let status_before = match read_only {
+        true => Some(WorktreeStatus::capture(&worktree_path).map_err(|e| {
+            format!("Cannot start a read-only run without checking the worktree: {e}")
+        })?),
+        false => None,
+    };
+
+    let child = launch_spec
         .spawn()
-        .map_err(|e| {
-            format!(
-                "Failed to start Claude Code: {e}. Make sure 'claude' is installed and in PATH."
-            )
+        .map_err(|e| match classify_spawn_error(&e) {
+            ClaudeErrorCode::BinaryNotFound if backend_id != DEFAULT_BACKEND => format!(
+                "Failed to start {}: {e}. Make sure '{}' is installed and in PATH.",
+                agent_backend.name(),
+                launch_spec.program
+            ),
+            error_code => format!(
+                "Failed to start {}: {e}. {}",
+                agent_backend.name(),
+                error_code.description()
+            ),
         })?;
 
     claude_process.pid = Some(child.id());
     claude_process.status = "running".to_string();
 
-    eprintln!(
-        "CREATED CLAUDE PROCESS: ID={}, WorktreeID={}, PID={:?}",
-        claude_process.id, claude_process.worktree_id, claude_process.pid
-    );
+    tracing::info!(pid = ?claude_process.pid, "Started agent process");
 
     // Store the child process
     let child_arc = Arc::new(Mutex::new(Some(child)));
@@ -360,161 +793,590 @@ async fn start_claude_process(
         .unwrap()
         .insert(process_id.clone(), claude_process.clone());
 
+    let retry_policy = state.retry_policy.lock().unwrap().clone();
+    let approvals = state.mcp_manager.approvals.clone();
+
     // Handle the child process in a thread
-    let process_id_clone = process_id.clone();
-    let app_handle_clone = app_handle.clone();
+    let started = std::time::Instant::now();
+    let span = tracing::Span::current();
+    thread::spawn(move || {
+        let _span = span.enter();
+        let success = monitor_claude_process(
+            app_handle.clone(),
+            process_id.clone(),
+            run_repo.clone(),
+            agent_backend,
+            launch_spec,
+            child_arc,
+            retry_policy,
+        );
+        finish_process(&app_handle, &process_id, success);
+
+        // Retries reuse the config, so only remove it once monitoring is done
+        drop(mcp_config);
+        drop(network_bridge);
+        drop(process_limits);
+        approvals.revoke_process_grants(&process_id);
+        record_process_duration(&app_handle, &process_id, started.elapsed());
+
+        if let Some(status_before) = status_before {
+            verify_read_only_run(&app_handle, &process_id, &worktree_path, &status_before);
+        }
+        if let Some(snapshot) = snapshot {
+            report_protected_changes(
+                &app_handle,
+                &process_id,
+                &worktree_id,
+                &worktree_path,
+                run_repo.as_deref(),
+                &snapshot,
+            );
+        }
+    });
 
-    // Create completion_sent at the right scope level
-    let completion_sent = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    Ok(claude_process)
+}
 
-    thread::spawn(move || {
-        let child_opt = {
-            let mut guard = child_arc.lock().unwrap();
-            guard.take()
-        };
+/// Tell the UI that the sandbox blocked something, in the chat and as an event.
+fn report_sandbox_violation(
+    app_handle: &AppHandle,
+    process_id: &str,
+    violation: &SandboxViolation,
+) {
+    tracing::warn!(process_id, kind = ?violation.kind, detail = %violation.detail, "Sandbox violation");
+    let output = ProcessOutput {
+        process_id: process_id.to_string(),
+        content: format!("🛡️ Sandbox: {}", violation.detail),
+        is_error: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = app_handle.emit("claude-output", &output);
+    let _ = app_handle.emit(
+        "sandbox-violation",
+        &serde_json::json!({
+            "process_id": process_id,
+            "kind": violation.kind,
+            "detail": violation.detail,
+        }),
+    );
+}
 
-        if let Some(mut child) = child_opt {
-            // Take stdout and stderr
-            let stdout = child.stdout.take();
-            let stderr = child.stderr.take();
-
-            // Stream stdout
-            if let Some(stdout) = stdout {
-                let reader = BufReader::new(stdout);
-                let process_id_stdout = process_id_clone.clone();
-                let app_handle_stdout = app_handle_clone.clone();
-
-                let completion_sent_clone = completion_sent.clone();
-
-                thread::spawn(move || {
-                    for line in reader.lines().map_while(Result::ok) {
-                        // Check if this is a result line (indicates completion)
-                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
-                            if let Some(msg_type) = json.get("type").and_then(|t| t.as_str()) {
-                                if msg_type == "result" {
-                                    // Only emit completion once
-                                    if !completion_sent_clone
-                                        .swap(true, std::sync::atomic::Ordering::SeqCst)
-                                    {
-                                        eprintln!(
-                                            "COMPLETION: Process {process_id_stdout} finished"
-                                        );
-                                        let _ = app_handle_stdout.emit(
-                                            "claude-completed",
-                                            &serde_json::json!({
-                                                "process_id": process_id_stdout,
-                                                "success": true
-                                            }),
-                                        );
-                                    }
-                                    continue;
-                                }
-                            }
-                        }
+/// Compare the worktree with its state before a read-only run and mark the
+/// run as violating if anything changed.
+fn verify_read_only_run(
+    app_handle: &AppHandle,
+    process_id: &str,
+    worktree_path: &str,
+    before: &WorktreeStatus,
+) {
+    let violations = match WorktreeStatus::capture(worktree_path) {
+        Ok(after) => before.changes_since(&after),
+        Err(e) => vec![format!("Could not check the worktree: {e}")],
+    };
+    if violations.is_empty() {
+        return;
+    }
+    tracing::warn!(
+        process_id,
+        ?violations,
+        "Read-only process changed its worktree"
+    );
 
-                        // Parse Claude's JSON output and extract meaningful content
-                        if let Some(parsed_content) = parse_claude_json_line(&line) {
-                            let output = ProcessOutput {
-                                process_id: process_id_stdout.clone(),
-                                content: parsed_content,
-                                is_error: false,
-                                timestamp: chrono::Utc::now().to_rfc3339(),
-                            };
-                            eprintln!(
-                                "EMITTING CLAUDE-OUTPUT: Process={}, Content={}",
-                                output.process_id, output.content
-                            );
-                            let _ = app_handle_stdout.emit("claude-output", &output);
+    if let Some(process) = app_handle
+        .state::<AppState>()
+        .processes
+        .lock()
+        .unwrap()
+        .get_mut(process_id)
+    {
+        process.read_only_violations = violations.clone();
+    }
+
+    let output = ProcessOutput {
+        process_id: process_id.to_string(),
+        content: format!(
+            "⛔ This read-only run changed the worktree:\n{}",
+            violations.join("\n")
+        ),
+        is_error: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = app_handle.emit("claude-output", &output);
+    let _ = app_handle.emit(
+        "read-only-violation",
+        &serde_json::json!({
+            "process_id": process_id,
+            "violations": violations,
+        }),
+    );
+}
+
+/// Flag protected files that changed in the worktree during a run, e.g.
+/// through a command that did not name them.
+fn report_protected_changes(
+    app_handle: &AppHandle,
+    process_id: &str,
+    worktree_id: &str,
+    worktree_path: &str,
+    repo_path: Option<&str>,
+    snapshot: &WorktreeSnapshot,
+) {
+    let patterns = protected_patterns_for(app_handle, repo_path);
+    let changes = match snapshot.protected_changes(worktree_path, &patterns) {
+        Ok(changes) if changes.is_empty() => return,
+        Ok(changes) => changes,
+        Err(e) => {
+            tracing::error!(process_id, error = %e, "Failed to check protected paths");
+            return;
+        }
+    };
+    tracing::warn!(process_id, ?changes, "Process changed protected paths");
+
+    let paths: Vec<&str> = changes.iter().map(|change| change.path.as_str()).collect();
+    let output = ProcessOutput {
+        process_id: process_id.to_string(),
+        content: format!(
+            "⛔ Protected files changed in this worktree: {}",
+            paths.join(", ")
+        ),
+        is_error: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = app_handle.emit("claude-output", &output);
+    let _ = app_handle.emit(
+        "protected-paths-changed",
+        &serde_json::json!({
+            "process_id": process_id,
+            "worktree_id": worktree_id,
+            "changes": changes,
+        }),
+    );
+}
+
+/// How often a watched child is checked for exit. The child stays in its
+/// shared slot rather than in a blocking `wait`, so `stop_claude_process` can kill it.
+const CHILD_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Result of watching a single Claude invocation until it exits.
+struct ClaudeRunOutcome {
+    success: bool,
+    error_code: Option<ClaudeErrorCode>,
+    completion_sent: bool,
+    /// `stop_claude_process` took the child away.
+    stopped: bool,
+}
+
+fn emit_claude_completed(
+    app_handle: &AppHandle,
+    process_id: &str,
+    success: bool,
+    error_code: Option<ClaudeErrorCode>,
+) {
+    let _ = app_handle.emit(
+        "claude-completed",
+        &serde_json::json!({
+            "process_id": process_id,
+            "success": success,
+            "error_code": error_code,
+            "error_message": error_code.map(|code| code.description()),
+        }),
+    );
+}
+
+/// Watch a Claude process, retrying rate-limit and overload failures with
+/// exponential backoff until the retry policy is exhausted. Returns whether
+/// the last run succeeded.
+fn monitor_claude_process(
+    app_handle: AppHandle,
+    process_id: String,
+    repo_path: Option<String>,
+    agent_backend: Arc<dyn AgentBackend>,
+    launch_spec: AgentLaunchSpec,
+    child: Arc<Mutex<Option<Child>>>,
+    retry_policy: RetryPolicy,
+) -> bool {
+    let mut attempt = 0;
+    // Still registered under this run, i.e. not stopped by the user
+    let registered = |running: &HashMap<String, Arc<Mutex<Option<Child>>>>| {
+        running
+            .get(&process_id)
+            .is_some_and(|slot| Arc::ptr_eq(slot, &child))
+    };
+
+    loop {
+        let outcome = watch_claude_run(
+            &app_handle,
+            &process_id,
+            repo_path.as_deref(),
+            &agent_backend,
+            &child,
+        );
+        if outcome.stopped {
+            return false;
+        }
+
+        if let Some(error_code) = outcome.error_code.filter(|_| !outcome.success) {
+            let still_registered = registered(
+                &app_handle
+                    .state::<AppState>()
+                    .running_processes
+                    .lock()
+                    .unwrap(),
+            );
+
+            if still_registered && retry_policy.should_retry(error_code, attempt) {
+                attempt += 1;
+                let delay = retry_policy.delay_for_attempt(attempt);
+                tracing::warn!(
+                    ?error_code,
+                    attempt,
+                    max_retries = retry_policy.max_retries,
+                    ?delay,
+                    "Agent run failed, retrying"
+                );
+
+                let retry_output = ProcessOutput {
+                    process_id: process_id.clone(),
+                    content: format!(
+                        "{} Retrying in {}s (attempt {attempt}/{})",
+                        error_code.description(),
+                        delay.as_secs(),
+                        retry_policy.max_retries
+                    ),
+                    is_error: true,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                };
+                let _ = app_handle.emit("claude-output", &retry_output);
+                let _ = app_handle.emit(
+                    "claude-retry",
+                    &serde_json::json!({
+                        "process_id": process_id,
+                        "attempt": attempt,
+                        "max_retries": retry_policy.max_retries,
+                        "delay_ms": delay.as_millis() as u64,
+                        "error_code": error_code,
+                    }),
+                );
+
+                thread::sleep(delay);
+
+                // Hold the registry while respawning so a stop cannot slip in between
+                let state = app_handle.state::<AppState>();
+                let running = state.running_processes.lock().unwrap();
+                if !registered(&running) {
+                    tracing::info!("Agent stopped during the retry backoff");
+                    return false;
+                }
+                match launch_spec.spawn() {
+                    Ok(new_child) => {
+                        if let Some(process) = state.processes.lock().unwrap().get_mut(&process_id)
+                        {
+                            process.pid = Some(new_child.id());
+                            process.last_activity = Some(chrono::Utc::now().to_rfc3339());
                         }
+                        *child.lock().unwrap() = Some(new_child);
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to respawn the process for a retry");
+                        emit_claude_completed(
+                            &app_handle,
+                            &process_id,
+                            false,
+                            Some(classify_spawn_error(&e)),
+                        );
+                        return false;
                     }
-                });
+                }
             }
+        }
 
-            // Stream stderr
-            if let Some(stderr) = stderr {
-                let reader = BufReader::new(stderr);
-                let process_id_stderr = process_id_clone.clone();
-                let app_handle_stderr = app_handle_clone.clone();
+        // Only emit fallback completion if primary completion wasn't sent
+        if !outcome.completion_sent {
+            tracing::debug!("Emitting fallback completion");
+            emit_claude_completed(
+                &app_handle,
+                &process_id,
+                outcome.success,
+                outcome.error_code,
+            );
+        } else {
+            tracing::debug!("Completion already sent, skipping fallback");
+        }
+        return outcome.success;
+    }
+}
 
-                thread::spawn(move || {
-                    for line in reader.lines().map_while(Result::ok) {
-                        let output = ProcessOutput {
-                            process_id: process_id_stderr.clone(),
-                            content: line,
-                            is_error: true,
-                            timestamp: chrono::Utc::now().to_rfc3339(),
-                        };
-                        let _ = app_handle_stderr.emit("claude-output", &output);
-                    }
-                });
-            }
+/// Move a process whose agent exited to `completed` or `error` and forget its
+/// child. Processes the user stopped keep their `stopped` status.
+fn finish_process(app_handle: &AppHandle, process_id: &str, success: bool) {
+    let state = app_handle.state::<AppState>();
+    state.running_processes.lock().unwrap().remove(process_id);
+
+    let status = if success { "completed" } else { "error" };
+    let changed = match state.processes.lock().unwrap().get_mut(process_id) {
+        Some(process) if process.status != "stopped" => {
+            process.status = status.to_string();
+            process.last_activity = Some(chrono::Utc::now().to_rfc3339());
+            true
+        }
+        _ => false,
+    };
+    if changed {
+        let _ = app_handle.emit(
+            "claude-process-status",
+            &serde_json::json!({ "process_id": process_id, "status": status }),
+        );
+    }
+}
 
-            // Wait for process completion
-            let process_id_wait = process_id_clone;
-            let app_handle_wait = app_handle_clone;
-            let completion_sent_wait = completion_sent;
+/// Stream a single agent invocation's output until it exits and classify any failure.
+fn watch_claude_run(
+    app_handle: &AppHandle,
+    process_id: &str,
+    repo_path: Option<&str>,
+    agent_backend: &Arc<dyn AgentBackend>,
+    child: &Arc<Mutex<Option<Child>>>,
+) -> ClaudeRunOutcome {
+    let completion_sent = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    // Errors the backend reports as its final result are authoritative; errors
+    // matched in stderr or plain output only explain an unsuccessful exit.
+    let result_error: Arc<Mutex<Option<ClaudeErrorCode>>> = Arc::new(Mutex::new(None));
+    let detected_error: Arc<Mutex<Option<ClaudeErrorCode>>> = Arc::new(Mutex::new(None));
+    let sandboxed = app_handle
+        .state::<AppState>()
+        .processes
+        .lock()
+        .unwrap()
+        .get(process_id)
+        .is_some_and(|process| process.sandbox.is_some());
 
-            thread::spawn(move || {
-                match child.wait() {
-                    Ok(status) => {
-                        eprintln!(
-                            "PROCESS WAIT: Process {process_id_wait} exited with status: {status:?}"
+    // Take stdout and stderr
+    let (stdout, stderr) = match child.lock().unwrap().as_mut() {
+        Some(child) => (child.stdout.take(), child.stderr.take()),
+        None => (None, None),
+    };
+    let mut readers = Vec::new();
+
+    // Stream stdout
+    if let Some(stdout) = stdout {
+        let reader = BufReader::new(stdout);
+        let process_id_stdout = process_id.to_string();
+        let app_handle_stdout = app_handle.clone();
+        let repo_stdout = repo_path.unwrap_or("unknown").to_string();
+        let completion_sent_clone = completion_sent.clone();
+        let result_error_stdout = result_error.clone();
+        let detected_error_stdout = detected_error.clone();
+
+        let backend_stdout = agent_backend.clone();
+        let span = tracing::Span::current();
+
+        readers.push(thread::spawn(move || {
+            let _span = span.enter();
+            let mut line_redactor = LineRedactor::default();
+            for line in reader.lines().map_while(Result::ok) {
+                if let Some(cost_usd) = backend_stdout.run_cost(&line) {
+                    record_run_cost(&app_handle_stdout, &repo_stdout, cost_usd);
+                }
+                // The line is parsed as the agent wrote it, since masking could
+                // break its JSON; secrets are masked in what is shown and logged
+                let redactor = redactor(&app_handle_stdout);
+                // Tool results that failed on the read-only mounts
+                if sandboxed && line.contains("\"is_error\":true") {
+                    let (shown, _) = redactor.redact(&line);
+                    if let Some(violation) = SandboxViolation::from_output_line(&shown) {
+                        report_sandbox_violation(
+                            &app_handle_stdout,
+                            &process_id_stdout,
+                            &violation,
                         );
-                        // Only emit completion events for errors, not successful completion
-                        if !status.success() {
-                            let completion_output = ProcessOutput {
-                                process_id: process_id_wait.clone(),
-                                content: format!("Process exited with code: {:?}", status.code()),
-                                is_error: true,
-                                timestamp: chrono::Utc::now().to_rfc3339(),
-                            };
-                            let _ = app_handle_wait.emit("claude-output", &completion_output);
-                        }
-                        // Only emit fallback completion if primary completion wasn't sent
-                        if !completion_sent_wait.load(std::sync::atomic::Ordering::SeqCst) {
-                            eprintln!(
-                                "FALLBACK COMPLETION: Emitting completion for process {process_id_wait}"
-                            );
-                            let _ = app_handle_wait.emit(
-                                "claude-completed",
-                                &serde_json::json!({
-                                    "process_id": process_id_wait,
-                                    "success": status.success()
-                                }),
-                            );
-                        } else {
-                            eprintln!(
-                                "SKIPPING FALLBACK: Primary completion already sent for process {process_id_wait}"
+                    }
+                }
+                match backend_stdout.parse_stdout_line(&line) {
+                    AgentLine::Failed(error_code) => {
+                        // Let the wait thread decide between retrying and failing
+                        tracing::warn!(?error_code, "Agent reported an error result");
+                        result_error_stdout
+                            .lock()
+                            .unwrap()
+                            .get_or_insert(error_code);
+                    }
+                    AgentLine::Completed => {
+                        // Only emit completion once
+                        if !completion_sent_clone.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                            tracing::info!("Agent run completed");
+                            emit_claude_completed(
+                                &app_handle_stdout,
+                                &process_id_stdout,
+                                true,
+                                None,
                             );
                         }
                     }
-                    Err(e) => {
-                        eprintln!("PROCESS ERROR: Process {process_id_wait} failed: {e}");
-                        let completion_output = ProcessOutput {
-                            process_id: process_id_wait.clone(),
-                            content: format!("Process error: {e}"),
-                            is_error: true,
+                    AgentLine::Session(session_id) => {
+                        if let Some(process) = app_handle_stdout
+                            .state::<AppState>()
+                            .processes
+                            .lock()
+                            .unwrap()
+                            .get_mut(&process_id_stdout)
+                        {
+                            process.session_id = Some(session_id);
+                        }
+                    }
+                    AgentLine::Output {
+                        content,
+                        error_hint,
+                    } => {
+                        if let Some(error_code) = error_hint {
+                            detected_error_stdout
+                                .lock()
+                                .unwrap()
+                                .get_or_insert(error_code);
+                        }
+                        let Some((content, redactions)) = line_redactor.redact(&redactor, &content)
+                        else {
+                            continue;
+                        };
+                        record_redactions(&app_handle_stdout, &process_id_stdout, redactions);
+                        let output = ProcessOutput {
+                            process_id: process_id_stdout.clone(),
+                            content,
+                            is_error: false,
                             timestamp: chrono::Utc::now().to_rfc3339(),
                         };
-                        let _ = app_handle_wait.emit("claude-output", &completion_output);
-                        // Always emit completion for errors
-                        let _ = app_handle_wait.emit(
-                            "claude-completed",
-                            &serde_json::json!({
-                                "process_id": process_id_wait,
-                                "success": false
-                            }),
+                        tracing::debug!(content = %output.content, "Agent output");
+                        let _ = app_handle_stdout.emit("claude-output", &output);
+                    }
+                    AgentLine::Ignore => {}
+                }
+            }
+        }));
+    }
+
+    // Stream stderr
+    if let Some(stderr) = stderr {
+        let reader = BufReader::new(stderr);
+        let process_id_stderr = process_id.to_string();
+        let app_handle_stderr = app_handle.clone();
+        let detected_error_stderr = detected_error.clone();
+        let backend_stderr = agent_backend.clone();
+        let span = tracing::Span::current();
+
+        readers.push(thread::spawn(move || {
+            let _span = span.enter();
+            let mut line_redactor = LineRedactor::default();
+            for line in reader.lines().map_while(Result::ok) {
+                let redactor = redactor(&app_handle_stderr);
+                let Some((line, redactions)) = line_redactor.redact(&redactor, &line) else {
+                    continue;
+                };
+                record_redactions(&app_handle_stderr, &process_id_stderr, redactions);
+                if let Some(error_code) = backend_stderr.classify_stderr_line(&line) {
+                    detected_error_stderr
+                        .lock()
+                        .unwrap()
+                        .get_or_insert(error_code);
+                }
+                if sandboxed {
+                    if let Some(violation) = SandboxViolation::from_output_line(&line) {
+                        report_sandbox_violation(
+                            &app_handle_stderr,
+                            &process_id_stderr,
+                            &violation,
                         );
                     }
                 }
-            });
+                let output = ProcessOutput {
+                    process_id: process_id_stderr.clone(),
+                    content: line,
+                    is_error: true,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                };
+                let _ = app_handle_stderr.emit("claude-output", &output);
+            }
+        }));
+    }
+
+    // Wait for process completion
+    let exit_status = loop {
+        let exited = match child.lock().unwrap().as_mut() {
+            Some(child) => child.try_wait().transpose(),
+            None => break None,
+        };
+        if exited.is_some() {
+            break exited;
         }
-    });
+        thread::sleep(CHILD_POLL_INTERVAL);
+    };
 
-    Ok(claude_process)
+    // Drain the output readers so every line has been classified
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    let Some(exit_status) = exit_status else {
+        tracing::info!("Agent process stopped");
+        return ClaudeRunOutcome {
+            success: false,
+            error_code: None,
+            completion_sent: completion_sent.load(std::sync::atomic::Ordering::SeqCst),
+            stopped: true,
+        };
+    };
+
+    let result_error = *result_error.lock().unwrap();
+    let detected_error = result_error.or(*detected_error.lock().unwrap());
+    let completion_sent = completion_sent.load(std::sync::atomic::Ordering::SeqCst);
+
+    match exit_status {
+        Ok(status) => {
+            tracing::info!(?status, "Agent process exited");
+            // Only emit completion events for errors, not successful completion
+            if !status.success() {
+                let completion_output = ProcessOutput {
+                    process_id: process_id.to_string(),
+                    content: format!("Process exited with code: {:?}", status.code()),
+                    is_error: true,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                };
+                let _ = app_handle.emit("claude-output", &completion_output);
+            }
+
+            let success = status.success() && result_error.is_none();
+            ClaudeRunOutcome {
+                success,
+                error_code: if success {
+                    None
+                } else {
+                    Some(detected_error.unwrap_or(ClaudeErrorCode::Unknown))
+                },
+                completion_sent,
+                stopped: false,
+            }
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to wait for the agent process");
+            let completion_output = ProcessOutput {
+                process_id: process_id.to_string(),
+                content: format!("Process error: {e}"),
+                is_error: true,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            };
+            let _ = app_handle.emit("claude-output", &completion_output);
+            // Always emit completion for errors
+            ClaudeRunOutcome {
+                success: false,
+                error_code: Some(detected_error.unwrap_or(ClaudeErrorCode::Unknown)),
+                completion_sent: false,
+                stopped: false,
+            }
+        }
+    }
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn send_message_to_claude(
     app_handle: AppHandle,
     state: State<'_, AppState>,
@@ -522,6 +1384,12 @@ async fn send_message_to_claude(
     worktree_id: String,
     message: String,
     permission_mode: Option<String>,
+    launch_profile: Option<String>,
+    repo_path: Option<String>,
+    backend: Option<String>,
+    resume_session_id: Option<String>,
+    read_only: Option<bool>,
+    sandbox: Option<SandboxOptions>,
 ) -> Result<(), String> {
     // For additional messages, we spawn a new Claude process
     // since --print mode exits after one response
@@ -532,6 +1400,12 @@ async fn send_message_to_claude(
         worktree_id,
         message,
         permission_mode,
+        launch_profile,
+        repo_path,
+        backend,
+        resume_session_id,
+        read_only,
+        sandbox,
     )
     .await?;
     Ok(())
@@ -544,6 +1418,7 @@ async fn stop_claude_process(state: State<'_, AppState>, process_id: String) ->
         if let Ok(mut child_guard) = child_arc.lock() {
             if let Some(mut child) = child_guard.take() {
                 let _ = child.kill();
+                let _ = child.wait();
             }
         }
     }
@@ -559,8 +1434,245 @@ async fn stop_claude_process(state: State<'_, AppState>, process_id: String) ->
 
 #[tauri::command]
 async fn list_processes(state: State<'_, AppState>) -> Result<Vec<ClaudeProcess>, String> {
-    let processes = state.processes.lock().unwrap();
-    Ok(processes.values().cloned().collect())
+    // Only children still being monitored: an exited child's pid may be reused
+    let live: Vec<String> = state
+        .running_processes
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    let mut processes: Vec<ClaudeProcess> =
+        state.processes.lock().unwrap().values().cloned().collect();
+    for process in &mut processes {
+        let active = matches!(process.status.as_str(), "running" | "awaiting_approval")
+            && live.contains(&process.id);
+        process.resource_usage = match (active, process.pid) {
+            (true, Some(pid)) => ResourceUsage::sample(
+                pid,
+                process
+                    .resource_limits
+                    .as_ref()
+                    .and_then(|limits| limits.cgroup.as_deref()),
+            ),
+            _ => None,
+        };
+    }
+    Ok(processes)
+}
+
+#[tauri::command]
+async fn get_retry_policy(state: State<'_, AppState>) -> Result<RetryPolicy, String> {
+    Ok(state.retry_policy.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_retry_policy(state: State<'_, AppState>, policy: RetryPolicy) -> Result<(), String> {
+    *state.retry_policy.lock().unwrap() = policy;
+    Ok(())
+}
+
+/// Recent metrics samples of a running process, oldest first.
+#[tauri::command]
+async fn get_process_metrics(
+    state: State<'_, AppState>,
+    process_id: String,
+) -> Result<Vec<ProcessMetrics>, String> {
+    Ok(state.process_metrics.lock().unwrap().history(&process_id))
+}
+
+#[tauri::command]
+async fn get_resource_limits(state: State<'_, AppState>) -> Result<ResourceLimits, String> {
+    Ok(state.resource_limits.lock().unwrap().clone())
+}
+
+/// Limits for agent processes started from now on.
+#[tauri::command]
+async fn set_resource_limits(
+    state: State<'_, AppState>,
+    limits: ResourceLimits,
+) -> Result<(), String> {
+    limits.validate()?;
+    *state.resource_limits.lock().unwrap() = limits;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_redaction_patterns(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(state.redactor.lock().unwrap().custom_patterns().to_vec())
+}
+
+/// Replace the user-defined redaction regexes, kept across restarts. A
+/// `secret` named group limits the mask to that part of the match.
+#[tauri::command]
+async fn set_redaction_patterns(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    patterns: Vec<String>,
+) -> Result<(), String> {
+    let patterns = patterns
+        .into_iter()
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect();
+    let redactor = Redactor::new(patterns)?;
+    // Saved first, so the patterns in effect are the ones a restart loads
+    if let Ok(config_dir) = app_handle.path().app_config_dir() {
+        save_patterns(
+            &config_dir.join(REDACTION_PATTERNS_FILE),
+            redactor.custom_patterns(),
+        )?;
+    }
+    *state.redactor.lock().unwrap() = Arc::new(redactor);
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_launch_profiles(state: State<'_, AppState>) -> Result<Vec<LaunchProfile>, String> {
+    Ok(state.launch_profiles.lock().unwrap().list())
+}
+
+#[tauri::command]
+async fn save_launch_profile(
+    state: State<'_, AppState>,
+    profile: LaunchProfile,
+) -> Result<(), String> {
+    state.launch_profiles.lock().unwrap().save(profile)
+}
+
+#[tauri::command]
+async fn delete_launch_profile(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    state.launch_profiles.lock().unwrap().remove(&name)
+}
+
+#[tauri::command]
+async fn set_worktree_launch_profile(
+    state: State<'_, AppState>,
+    worktree_id: String,
+    profile_name: Option<String>,
+) -> Result<(), String> {
+    state
+        .launch_profiles
+        .lock()
+        .unwrap()
+        .set_worktree_default(worktree_id, profile_name)
+}
+
+#[tauri::command]
+async fn set_repo_launch_profile(
+    state: State<'_, AppState>,
+    repo_path: String,
+    profile_name: Option<String>,
+) -> Result<(), String> {
+    state
+        .launch_profiles
+        .lock()
+        .unwrap()
+        .set_repo_default(repo_path, profile_name)
+}
+
+#[tauri::command]
+async fn get_worktree_mcp_servers(
+    state: State<'_, AppState>,
+    worktree_id: String,
+) -> Result<Vec<ExtraMcpServer>, String> {
+    Ok(state
+        .extra_mcp_servers
+        .lock()
+        .unwrap()
+        .worktree_servers(&worktree_id))
+}
+
+#[tauri::command]
+async fn set_worktree_mcp_servers(
+    state: State<'_, AppState>,
+    worktree_id: String,
+    servers: Vec<ExtraMcpServer>,
+) -> Result<(), String> {
+    state
+        .extra_mcp_servers
+        .lock()
+        .unwrap()
+        .set_worktree_servers(worktree_id, servers)
+}
+
+#[tauri::command]
+async fn get_repo_mcp_servers(
+    state: State<'_, AppState>,
+    repo_path: String,
+) -> Result<Vec<ExtraMcpServer>, String> {
+    Ok(state
+        .extra_mcp_servers
+        .lock()
+        .unwrap()
+        .repo_servers(&repo_path))
+}
+
+#[tauri::command]
+async fn set_repo_mcp_servers(
+    state: State<'_, AppState>,
+    repo_path: String,
+    servers: Vec<ExtraMcpServer>,
+) -> Result<(), String> {
+    state
+        .extra_mcp_servers
+        .lock()
+        .unwrap()
+        .set_repo_servers(repo_path, servers)
+}
+
+#[tauri::command]
+async fn get_repo_protected_paths(
+    state: State<'_, AppState>,
+    repo_path: String,
+) -> Result<Vec<String>, String> {
+    Ok(state.protected_paths.lock().unwrap().patterns(&repo_path))
+}
+
+#[tauri::command]
+async fn set_repo_protected_paths(
+    state: State<'_, AppState>,
+    repo_path: String,
+    patterns: Vec<String>,
+) -> Result<(), String> {
+    state
+        .protected_paths
+        .lock()
+        .unwrap()
+        .set_patterns(repo_path, patterns)
+}
+
+#[tauri::command]
+async fn list_agent_backends(state: State<'_, AppState>) -> Result<Vec<AgentBackendInfo>, String> {
+    Ok(state.agent_backends.list())
+}
+
+#[tauri::command]
+async fn diagnose_environment(
+    state: State<'_, AppState>,
+    claude_binary: Option<String>,
+) -> Result<EnvironmentReport, String> {
+    let claude_binary = claude_binary.unwrap_or_else(|| "claude".to_string());
+
+    let checks = vec![
+        diagnostics::evaluate_git(diagnostics::run_version_command("git")),
+        diagnostics::evaluate_claude(
+            &claude_binary,
+            diagnostics::run_version_command(&claude_binary),
+        ),
+        diagnostics::evaluate_node(diagnostics::run_version_command("node")),
+        diagnostics::evaluate_mcp_server(
+            state
+                .mcp_manager
+                .refresh_runtime()
+                .map(|info| info.server_path),
+        ),
+    ];
+
+    // Pick up a freshly installed or updated CLI on the next launch
+    state.claude_version_gate.clear();
+
+    Ok(EnvironmentReport::new(checks))
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -821,15 +1933,20 @@ async fn remove_worktree(
 
         if let Ok(output) = delete_branch_output {
             if !output.status.success() {
-                eprintln!(
-                    "Warning: Failed to delete branch '{}': {}",
+                tracing::warn!(
                     branch,
-                    String::from_utf8_lossy(&output.stderr)
+                    stderr = %String::from_utf8_lossy(&output.stderr),
+                    "Failed to delete branch"
                 );
             }
         }
     }
 
+    // The worktree's MCP server goes with it
+    if let Err(e) = state.mcp_manager.stop_worktree_server(&worktree_path).await {
+        tracing::warn!(worktree_path, error = %e, "Failed to stop the worktree's MCP server");
+    }
+
     // Also remove from backend state if it exists (for worktrees created via backend)
     let mut worktrees = state.worktrees.lock().unwrap();
     let worktree_to_remove = worktrees
@@ -883,6 +2000,228 @@ async fn get_mcp_server_status(
         .ok_or_else(|| "Server not found".to_string())
 }
 
+#[tauri::command]
+async fn get_mcp_transport(state: State<'_, AppState>) -> Result<McpTransport, String> {
+    Ok(state.mcp_manager.transport())
+}
+
+#[tauri::command]
+async fn set_mcp_transport(
+    state: State<'_, AppState>,
+    transport: McpTransport,
+) -> Result<(), String> {
+    state.mcp_manager.set_transport(transport);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_mcp_server_logs(
+    state: State<'_, AppState>,
+    server_id: String,
+    tail: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+    state
+        .mcp_manager
+        .get_server_logs(&server_id, tail.unwrap_or(DEFAULT_LOG_TAIL))
+}
+
+/// Recent app log entries, optionally only those of one process, worktree
+/// or approval, or at least as severe as `level`.
+#[tauri::command]
+async fn get_logs(
+    state: State<'_, AppState>,
+    tail: Option<usize>,
+    level: Option<LogLevel>,
+    process_id: Option<String>,
+    worktree_id: Option<String>,
+    approval_id: Option<String>,
+) -> Result<Vec<AppLogEntry>, String> {
+    Ok(state.logging.read(&LogQuery {
+        tail: tail.unwrap_or(DEFAULT_APP_LOG_TAIL),
+        level,
+        process_id,
+        worktree_id,
+        approval_id,
+    }))
+}
+
+#[tauri::command]
+async fn get_log_level(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.logging.level())
+}
+
+#[tauri::command]
+async fn set_log_level(state: State<'_, AppState>, level: String) -> Result<(), String> {
+    state.logging.set_level(&level)
+}
+
+#[tauri::command]
+async fn list_mcp_server_statuses(
+    state: State<'_, AppState>,
+) -> Result<Vec<McpServerStatus>, String> {
+    Ok(state.mcp_manager.server_statuses().await)
+}
+
+/// The redactor that output, logs and approval payloads go through.
+pub(crate) fn redactor(app_handle: &AppHandle) -> Arc<Redactor> {
+    app_handle
+        .state::<AppState>()
+        .redactor
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+pub(crate) fn record_redactions(app_handle: &AppHandle, process_id: &str, count: usize) {
+    if count == 0 {
+        return;
+    }
+    if let Some(process) = app_handle
+        .state::<AppState>()
+        .processes
+        .lock()
+        .unwrap()
+        .get_mut(process_id)
+    {
+        process.redactions += count as u64;
+    }
+}
+
+/// Add a run's API cost to the total of its repository.
+fn record_run_cost(app_handle: &AppHandle, repo: &str, cost_usd: f64) {
+    app_handle
+        .state::<AppState>()
+        .run_metrics
+        .lock()
+        .unwrap()
+        .add_cost(repo, cost_usd);
+}
+
+/// Record how long a process ran, by the status it ended in.
+fn record_process_duration(
+    app_handle: &AppHandle,
+    process_id: &str,
+    duration: std::time::Duration,
+) {
+    let state = app_handle.state::<AppState>();
+    let status = state
+        .processes
+        .lock()
+        .unwrap()
+        .get(process_id)
+        .map(|process| process.status.clone())
+        .unwrap_or_else(|| "stopped".to_string());
+    state
+        .run_metrics
+        .lock()
+        .unwrap()
+        .observe_duration(&status, duration.as_secs_f64());
+}
+
+/// Gather what the `/metrics` endpoint reports.
+pub(crate) fn metrics_snapshot(app_handle: &AppHandle) -> MetricsSnapshot {
+    let state = app_handle.state::<AppState>();
+    let mut processes_by_status = HashMap::new();
+    for process in state.processes.lock().unwrap().values() {
+        *processes_by_status
+            .entry(process.status.clone())
+            .or_insert(0) += 1;
+    }
+    let runs = state.run_metrics.lock().unwrap().clone();
+    MetricsSnapshot {
+        processes_by_status: processes_by_status.into_iter().collect(),
+        approvals_pending: state.mcp_manager.approvals.list_pending().len() as u64,
+        approvals: state.mcp_manager.approvals.stats(),
+        mcp_server_restarts: state.mcp_manager.restarts_total(),
+        runs,
+    }
+}
+
+/// Sample CPU, memory, children and ports of every running agent process
+/// and send them as one `process-metrics` event.
+fn sample_process_metrics(app_handle: &AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let live: Vec<String> = state
+        .running_processes
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    let targets: Vec<SampleTarget> = state
+        .processes
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|process| matches!(process.status.as_str(), "running" | "awaiting_approval"))
+        .filter(|process| live.contains(&process.id))
+        .filter_map(|process| {
+            Some(SampleTarget {
+                process_id: process.id.clone(),
+                worktree_id: process.worktree_id.clone(),
+                pid: process.pid?,
+                cgroup: process
+                    .resource_limits
+                    .as_ref()
+                    .and_then(|limits| limits.cgroup.clone()),
+            })
+        })
+        .collect();
+    let metrics = state.process_metrics.lock().unwrap().sample(&targets);
+    if !metrics.is_empty() {
+        let _ = app_handle.emit("process-metrics", &metrics);
+    }
+}
+
+/// Tell running Claude processes in the worktree that tool approvals will
+/// fail while its MCP server is down.
+fn warn_processes_of_mcp_server(app_handle: &AppHandle, status: &McpServerStatus) {
+    let state = app_handle.state::<AppState>();
+    let process_ids: Vec<String> = state
+        .processes
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|p| {
+            p.worktree_id == status.worktree_id
+                && matches!(p.status.as_str(), "running" | "awaiting_approval")
+        })
+        .map(|p| p.id.clone())
+        .collect();
+
+    let content = match status.state {
+        McpServerState::Failed => {
+            "The MCP approval server for this worktree has stopped and could not be restarted. Tool approvals will fail until it is recreated."
+        }
+        _ => "The MCP approval server for this worktree crashed and is restarting. Tool approvals may fail until it is back.",
+    };
+
+    for process_id in process_ids {
+        let output = ProcessOutput {
+            process_id,
+            content: content.to_string(),
+            is_error: true,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let _ = app_handle.emit("claude-output", &output);
+    }
+}
+
+#[tauri::command]
+async fn get_mcp_runtime_info(
+    state: State<'_, AppState>,
+) -> Result<mcp_manager::McpRuntimeInfo, String> {
+    state.mcp_manager.runtime_info()
+}
+
+#[tauri::command]
+async fn set_mcp_server_path_override(
+    state: State<'_, AppState>,
+    path: Option<String>,
+) -> Result<mcp_manager::McpRuntimeInfo, String> {
+    state.mcp_manager.set_server_path_override(path)
+}
+
 #[tauri::command]
 async fn request_tool_approval(
     state: State<'_, AppState>,
@@ -910,6 +2249,35 @@ async fn get_pending_approvals(
     Ok(state.mcp_manager.get_pending_approvals().await)
 }
 
+#[tauri::command]
+async fn list_approvals(state: State<'_, AppState>) -> Result<Vec<ApprovalRecord>, String> {
+    Ok(state.mcp_manager.approvals.list_pending())
+}
+
+#[tauri::command]
+async fn get_approval_history(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<ApprovalRecord>, String> {
+    Ok(state
+        .mcp_manager
+        .approvals
+        .history(limit.unwrap_or(APPROVAL_HISTORY_LIMIT)))
+}
+
+#[tauri::command]
+async fn list_approval_grants(state: State<'_, AppState>) -> Result<Vec<ApprovalGrant>, String> {
+    Ok(state.mcp_manager.approvals.list_grants())
+}
+
+#[tauri::command]
+async fn revoke_approval_grant(
+    state: State<'_, AppState>,
+    grant_id: String,
+) -> Result<ApprovalGrant, String> {
+    state.mcp_manager.approvals.revoke_grant(&grant_id)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -919,16 +2287,81 @@ pub fn run() {
         .setup(|app| {
             let app_handle = app.handle().clone();
 
+            if let Err(e) = app
+                .state::<AppState>()
+                .logging
+                .init(app.path().app_log_dir().ok())
+            {
+                eprintln!("{e}");
+            }
+
+            // Resolve and validate the bundled MCP server once up front
+            let resource_dir = app.path().resource_dir().ok();
+            let _ = app
+                .state::<AppState>()
+                .mcp_manager
+                .initialize_runtime(resource_dir);
+            if let Ok(log_dir) = app.path().app_log_dir() {
+                app.state::<AppState>().mcp_manager.set_log_dir(log_dir);
+            }
+            if let Ok(config_dir) = app.path().app_config_dir() {
+                if let Err(e) = app
+                    .state::<AppState>()
+                    .mcp_manager
+                    .approvals
+                    .load_grants(config_dir.join("approval_grants.json"))
+                {
+                    tracing::warn!("{e}");
+                }
+                if let Err(e) = app
+                    .state::<AppState>()
+                    .protected_paths
+                    .lock()
+                    .unwrap()
+                    .load(config_dir.join("protected_paths.json"))
+                {
+                    tracing::warn!("{e}");
+                }
+                match load_patterns(&config_dir.join(REDACTION_PATTERNS_FILE))
+                    .and_then(Redactor::new)
+                {
+                    Ok(redactor) => {
+                        *app.state::<AppState>().redactor.lock().unwrap() = Arc::new(redactor)
+                    }
+                    Err(e) => tracing::warn!("{e}"),
+                }
+            }
+
+            // Supervise MCP servers, restarting them when they crash
+            let supervisor_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(SUPERVISOR_INTERVAL).await;
+                    let state = supervisor_handle.state::<AppState>();
+                    for status in state.mcp_manager.supervise(&supervisor_handle).await {
+                        if status.state != McpServerState::Running {
+                            warn_processes_of_mcp_server(&supervisor_handle, &status);
+                        }
+                    }
+                }
+            });
+
+            // Publish live metrics of the running agent processes
+            let metrics_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(METRICS_INTERVAL).await;
+                    sample_process_metrics(&metrics_handle);
+                }
+            });
+
             // Clone data we need from state before spawning
-            let pending_http_approvals = {
-                let state = app.state::<AppState>();
-                state.mcp_manager.pending_http_approvals.clone()
-            };
+            let approvals = app.state::<AppState>().mcp_manager.approvals.clone();
 
             tauri::async_runtime::spawn(async move {
                 // Create a new state that includes the app handle
                 let app_state = HttpAppState {
-                    pending_http_approvals,
+                    approvals,
                     app_handle: Some(app_handle),
                 };
 
@@ -938,20 +2371,25 @@ pub fn run() {
                         "/api/approval-request",
                         post(crate::mcp_manager::handle_approval_request),
                     )
+                    .route("/metrics", get(crate::mcp_manager::handle_metrics))
                     .layer(CorsLayer::permissive())
                     .with_state(app_state);
 
-                eprintln!("🌐 RUST: Starting HTTP server on http://localhost:8080");
-
-                let listener = tokio::net::TcpListener::bind("0.0.0.0:8080")
+                let listener = tokio::net::TcpListener::bind(("0.0.0.0", APPROVAL_SERVER_PORT))
                     .await
-                    .expect("Failed to bind to port 8080");
+                    .expect("Failed to bind the approval server port");
 
-                eprintln!("🟢 RUST: HTTP server listening on http://localhost:8080");
+                tracing::info!(
+                    port = APPROVAL_SERVER_PORT,
+                    "Approval HTTP server listening"
+                );
 
-                axum::serve(listener, app)
-                    .await
-                    .expect("HTTP server failed");
+                axum::serve(
+                    listener,
+                    app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+                )
+                .await
+                .expect("HTTP server failed");
             });
 
             Ok(())
@@ -965,6 +2403,20 @@ pub fn run() {
             send_message_to_claude,
             stop_claude_process,
             list_processes,
+            get_process_metrics,
+            get_retry_policy,
+            set_retry_policy,
+            get_resource_limits,
+            set_resource_limits,
+            get_redaction_patterns,
+            set_redaction_patterns,
+            list_launch_profiles,
+            save_launch_profile,
+            delete_launch_profile,
+            set_worktree_launch_profile,
+            set_repo_launch_profile,
+            list_agent_backends,
+            diagnose_environment,
             check_worktree_status,
             remove_worktree,
             // MCP Server commands
@@ -972,9 +2424,28 @@ pub fn run() {
             stop_mcp_server,
             list_mcp_servers,
             get_mcp_server_status,
+            list_mcp_server_statuses,
+            get_mcp_server_logs,
+            get_logs,
+            get_log_level,
+            set_log_level,
+            get_mcp_transport,
+            set_mcp_transport,
+            get_mcp_runtime_info,
+            set_mcp_server_path_override,
+            get_worktree_mcp_servers,
+            set_worktree_mcp_servers,
+            get_repo_mcp_servers,
+            set_repo_mcp_servers,
+            get_repo_protected_paths,
+            set_repo_protected_paths,
             request_tool_approval,
             respond_to_approval,
-            get_pending_approvals
+            get_pending_approvals,
+            list_approvals,
+            get_approval_history,
+            list_approval_grants,
+            revoke_approval_grant
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
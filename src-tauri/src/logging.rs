@@ -0,0 +1,285 @@
+use crate::mcp_logs::LogLevel;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+/// Overrides the starting log level, e.g. `ORCHESTRA_LOG=debug`.
+pub const LOG_LEVEL_ENV: &str = "ORCHESTRA_LOG";
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+/// App logs are written to `orchestra.<date>.jsonl`, one file per day.
+pub const LOG_FILE_PREFIX: &str = "orchestra";
+pub const LOG_FILE_SUFFIX: &str = "jsonl";
+/// Daily files kept before the oldest is deleted.
+pub const MAX_LOG_FILES: usize = 7;
+/// Entries returned by `get_logs` when no tail is given.
+pub const DEFAULT_APP_LOG_TAIL: usize = 500;
+
+/// The app's tracing setup, installed once in `setup`.
+#[derive(Default)]
+pub struct Logging {
+    active: Mutex<Option<ActiveLogging>>,
+}
+
+struct ActiveLogging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    level: String,
+    log_dir: Option<PathBuf>,
+    /// Flushes the file writer when the app exits.
+    _guard: Option<WorkerGuard>,
+}
+
+impl Logging {
+    /// Log to stderr and, when there is a log dir, to rotating JSON files in it.
+    pub fn init(&self, log_dir: Option<PathBuf>) -> Result<(), String> {
+        let level = std::env::var(LOG_LEVEL_ENV)
+            .ok()
+            .filter(|level| parse_filter(level).is_ok())
+            .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string());
+        let (filter, handle) = reload::Layer::new(parse_filter(&level)?);
+
+        let mut guard = None;
+        let file_layer = match &log_dir {
+            Some(dir) => {
+                let appender = RollingFileAppender::builder()
+                    .rotation(Rotation::DAILY)
+                    .filename_prefix(LOG_FILE_PREFIX)
+                    .filename_suffix(LOG_FILE_SUFFIX)
+                    .max_log_files(MAX_LOG_FILES)
+                    .build(dir)
+                    .map_err(|e| format!("Failed to open logs in {}: {e}", dir.display()))?;
+                let (writer, worker_guard) = tracing_appender::non_blocking(appender);
+                guard = Some(worker_guard);
+                Some(json_layer(writer))
+            }
+            None => None,
+        };
+
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer().with_writer(std::io::stderr).with_target(false))
+            .with(file_layer)
+            .try_init()
+            .map_err(|e| format!("Failed to install the logger: {e}"))?;
+
+        *self.active.lock().unwrap() = Some(ActiveLogging {
+            filter: handle,
+            level,
+            log_dir,
+            _guard: guard,
+        });
+        Ok(())
+    }
+
+    pub fn level(&self) -> String {
+        self.active
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|active| active.level.clone())
+            .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string())
+    }
+
+    /// Change what is logged from now on: a level such as `debug`, or
+    /// filter directives such as `info,orchestra_manager_lib::mcp_manager=debug`.
+    pub fn set_level(&self, level: &str) -> Result<(), String> {
+        let filter = parse_filter(level)?;
+        let mut active = self.active.lock().unwrap();
+        let active = active.as_mut().ok_or("Logging is not initialized")?;
+        active
+            .filter
+            .reload(filter)
+            .map_err(|e| format!("Failed to change the log level: {e}"))?;
+        active.level = level.trim().to_string();
+        tracing::info!(level = %active.level, "Log level changed");
+        Ok(())
+    }
+
+    pub fn read(&self, query: &LogQuery) -> Vec<AppLogEntry> {
+        let log_dir = self
+            .active
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|active| active.log_dir.clone());
+        match log_dir {
+            Some(dir) => read_app_logs(&dir, query),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn parse_filter(level: &str) -> Result<EnvFilter, String> {
+    let level = level.trim();
+    if level.is_empty() {
+        return Err("Log level must not be empty".to_string());
+    }
+    EnvFilter::try_new(level).map_err(|e| format!("Invalid log level '{level}': {e}"))
+}
+
+/// One JSON object per event, with the fields of the spans it happened in.
+pub fn json_layer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(false)
+        .with_span_list(true)
+        .with_writer(writer)
+}
+
+/// An entry of the app log, as returned by `get_logs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppLogEntry {
+    pub timestamp: String,
+    pub level: LogLevel,
+    pub target: String,
+    pub message: String,
+    /// Other fields of the event, e.g. `tool` or `error`.
+    #[serde(default)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub process_id: Option<String>,
+    #[serde(default)]
+    pub worktree_id: Option<String>,
+    #[serde(default)]
+    pub approval_id: Option<String>,
+}
+
+/// Ids that are lifted out of event and span fields.
+const CONTEXT_FIELDS: &[&str] = &["process_id", "worktree_id", "approval_id"];
+
+impl AppLogEntry {
+    /// Parse a line written by [`json_layer`].
+    pub fn from_json_line(line: &str) -> Option<Self> {
+        let serde_json::Value::Object(mut event) = serde_json::from_str(line).ok()? else {
+            return None;
+        };
+        let text = |event: &mut serde_json::Map<String, serde_json::Value>, key: &str| {
+            event
+                .remove(key)
+                .and_then(|value| value.as_str().map(str::to_string))
+        };
+
+        let level = match text(&mut event, "level")?.to_lowercase().as_str() {
+            "error" => LogLevel::Error,
+            "warn" => LogLevel::Warn,
+            "info" => LogLevel::Info,
+            _ => LogLevel::Debug,
+        };
+        let mut entry = Self {
+            timestamp: text(&mut event, "timestamp").unwrap_or_default(),
+            level,
+            target: text(&mut event, "target").unwrap_or_default(),
+            message: text(&mut event, "message").unwrap_or_default(),
+            fields: serde_json::Map::new(),
+            process_id: None,
+            worktree_id: None,
+            approval_id: None,
+        };
+
+        // Outer spans first, so the innermost span and then the event win
+        let spans = event.remove("spans");
+        let span_fields = spans
+            .as_ref()
+            .and_then(|spans| spans.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|span| span.as_object());
+        for fields in span_fields.chain(std::iter::once(&event)) {
+            for key in CONTEXT_FIELDS {
+                if let Some(value) = fields.get(*key).and_then(|value| value.as_str()) {
+                    *entry.context_mut(key) = Some(value.to_string());
+                }
+            }
+        }
+        for key in CONTEXT_FIELDS {
+            event.remove(*key);
+        }
+        entry.fields = event;
+        Some(entry)
+    }
+
+    fn context_mut(&mut self, key: &str) -> &mut Option<String> {
+        match key {
+            "process_id" => &mut self.process_id,
+            "worktree_id" => &mut self.worktree_id,
+            _ => &mut self.approval_id,
+        }
+    }
+}
+
+/// Which app log entries `get_logs` returns.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    pub tail: usize,
+    /// Least severe level to include.
+    pub level: Option<LogLevel>,
+    pub process_id: Option<String>,
+    pub worktree_id: Option<String>,
+    pub approval_id: Option<String>,
+}
+
+impl LogQuery {
+    fn matches(&self, entry: &AppLogEntry) -> bool {
+        let severity = |level: LogLevel| level as u8;
+        self.level
+            .is_none_or(|level| severity(entry.level) >= severity(level))
+            && matches_id(&self.process_id, &entry.process_id)
+            && matches_id(&self.worktree_id, &entry.worktree_id)
+            && matches_id(&self.approval_id, &entry.approval_id)
+    }
+}
+
+fn matches_id(wanted: &Option<String>, actual: &Option<String>) -> bool {
+    wanted.is_none() || wanted == actual
+}
+
+/// The last `query.tail` matching entries across the daily files in `log_dir`.
+pub fn read_app_logs(log_dir: &Path, query: &LogQuery) -> Vec<AppLogEntry> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(log_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| {
+                            name.starts_with(&format!("{LOG_FILE_PREFIX}."))
+                                && name.ends_with(&format!(".{LOG_FILE_SUFFIX}"))
+                        })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Dates in the names sort oldest first
+    files.sort();
+
+    let mut entries = Vec::new();
+    for file in files {
+        let Ok(file) = File::open(&file) else {
+            continue;
+        };
+        entries.extend(
+            BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| AppLogEntry::from_json_line(&line))
+                .filter(|entry| query.matches(entry)),
+        );
+    }
+
+    let skip = entries.len().saturating_sub(query.tail);
+    entries.split_off(skip)
+}
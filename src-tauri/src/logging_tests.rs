@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use crate::logging::{json_layer, read_app_logs, AppLogEntry, LogQuery, Logging};
+    use crate::mcp_logs::LogLevel;
+    use std::fs::{self, File};
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn create_log_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("orchestra_app_logs_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn query(tail: usize) -> LogQuery {
+        LogQuery {
+            tail,
+            ..LogQuery::default()
+        }
+    }
+
+    #[test]
+    fn test_spans_round_trip_through_log_files() {
+        let dir = create_log_dir("spans");
+        let file = File::create(dir.join("orchestra.2026-10-18.jsonl")).unwrap();
+        let subscriber = tracing_subscriber::registry().with(json_layer(Mutex::new(file)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("Outside any span");
+            let process = tracing::info_span!("process", process_id = "p1", worktree_id = "w1");
+            let _process = process.enter();
+            tracing::debug!(content = "secret output", "Agent output");
+            tracing::info_span!("approval", approval_id = "a1", tool = "Bash").in_scope(|| {
+                tracing::warn!(error = "boom", "Approval failed");
+            });
+        });
+
+        let all = read_app_logs(&dir, &query(10));
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].message, "Outside any span");
+        assert_eq!(all[0].process_id, None);
+
+        assert_eq!(all[1].level, LogLevel::Debug);
+        assert_eq!(all[1].process_id.as_deref(), Some("p1"));
+        assert_eq!(all[1].worktree_id.as_deref(), Some("w1"));
+        assert_eq!(all[1].fields["content"], "secret output");
+
+        let approval = &all[2];
+        assert_eq!(approval.level, LogLevel::Warn);
+        assert_eq!(approval.message, "Approval failed");
+        assert_eq!(approval.process_id.as_deref(), Some("p1"));
+        assert_eq!(approval.approval_id.as_deref(), Some("a1"));
+        assert_eq!(approval.fields["error"], "boom");
+        assert!(!approval.fields.contains_key("approval_id"));
+
+        let by_process = LogQuery {
+            process_id: Some("p1".to_string()),
+            ..query(10)
+        };
+        assert_eq!(read_app_logs(&dir, &by_process).len(), 2);
+
+        let by_approval = LogQuery {
+            approval_id: Some("a1".to_string()),
+            ..query(10)
+        };
+        assert_eq!(read_app_logs(&dir, &by_approval), vec![approval.clone()]);
+
+        let warnings = LogQuery {
+            level: Some(LogLevel::Info),
+            ..query(10)
+        };
+        let messages: Vec<String> = read_app_logs(&dir, &warnings)
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect();
+        assert_eq!(messages, ["Outside any span", "Approval failed"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_read_app_logs_tails_across_daily_files() {
+        let dir = create_log_dir("tail");
+        let line = |n: u32| {
+            format!(r#"{{"timestamp":"t{n}","level":"INFO","target":"app","message":"m{n}"}}"#)
+        };
+        fs::write(
+            dir.join("orchestra.2026-10-18.jsonl"),
+            format!("{}\n{}\n", line(3), line(4)),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("orchestra.2026-10-17.jsonl"),
+            format!("{}\nnot json\n{}\n", line(1), line(2)),
+        )
+        .unwrap();
+        fs::write(dir.join("other.log"), line(5)).unwrap();
+
+        let messages = |tail| -> Vec<String> {
+            read_app_logs(&dir, &query(tail))
+                .into_iter()
+                .map(|entry| entry.message)
+                .collect()
+        };
+        assert_eq!(messages(3), ["m2", "m3", "m4"]);
+        assert_eq!(messages(10), ["m1", "m2", "m3", "m4"]);
+        assert!(read_app_logs(&dir.join("missing"), &query(10)).is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_event_fields_override_span_context() {
+        let entry = AppLogEntry::from_json_line(
+            r#"{"timestamp":"t","level":"TRACE","target":"app","message":"m",
+                "process_id":"p2","spans":[{"name":"process","process_id":"p1","worktree_id":"w1"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.level, LogLevel::Debug);
+        assert_eq!(entry.process_id.as_deref(), Some("p2"));
+        assert_eq!(entry.worktree_id.as_deref(), Some("w1"));
+        assert!(entry.fields.is_empty());
+
+        assert!(AppLogEntry::from_json_line("[1, 2]").is_none());
+        assert!(AppLogEntry::from_json_line(r#"{"message":"no level"}"#).is_none());
+    }
+
+    #[test]
+    fn test_logging_before_init() {
+        let logging = Logging::default();
+        assert_eq!(logging.level(), "info");
+        assert!(logging.read(&query(10)).is_empty());
+        assert!(logging.set_level("not a [level").is_err());
+        assert!(logging.set_level("debug").is_err());
+    }
+}
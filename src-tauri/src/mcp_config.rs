@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{DirBuilder, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of Orchestra's own approval server in generated MCP configs.
+pub const ORCHESTRA_SERVER_NAME: &str = "orchestra-worktree";
+/// Environment variable carrying the id of the Claude process a server
+/// belongs to, forwarded with every approval request.
+pub const PROCESS_ID_ENV: &str = "ORCHESTRA_PROCESS_ID";
+
+/// A stdio MCP server entry as understood by `claude --mcp-config`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpStdioServer {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// One entry of the `mcpServers` map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum McpServerEntry {
+    Stdio(McpStdioServer),
+    /// A long-running server reached over HTTP/SSE.
+    Sse {
+        url: String,
+    },
+}
+
+impl From<McpStdioServer> for McpServerEntry {
+    fn from(server: McpStdioServer) -> Self {
+        McpServerEntry::Stdio(server)
+    }
+}
+
+/// Values substituted into `{placeholder}`s in extra MCP server definitions.
+pub struct McpTemplateContext<'a> {
+    pub worktree_path: &'a str,
+    pub worktree_id: &'a str,
+    pub repo_path: Option<&'a str>,
+    pub branch: Option<&'a str>,
+}
+
+impl McpTemplateContext<'_> {
+    /// Replace `{worktree_path}`, `{worktree_id}`, `{repo_path}` and `{branch}`.
+    /// Placeholders without a known value are left as-is.
+    pub fn render(&self, value: &str) -> String {
+        let mut rendered = value
+            .replace("{worktree_path}", self.worktree_path)
+            .replace("{worktree_id}", self.worktree_id);
+        if let Some(repo_path) = self.repo_path {
+            rendered = rendered.replace("{repo_path}", repo_path);
+        }
+        if let Some(branch) = self.branch {
+            rendered = rendered.replace("{branch}", branch);
+        }
+        rendered
+    }
+}
+
+/// A user-defined stdio MCP server attached to a repo or worktree. The
+/// command, args and env values are templates, see [`McpTemplateContext`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtraMcpServer {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl ExtraMcpServer {
+    pub fn render(&self, context: &McpTemplateContext) -> McpStdioServer {
+        McpStdioServer {
+            command: context.render(&self.command),
+            args: self.args.iter().map(|arg| context.render(arg)).collect(),
+            env: self
+                .env
+                .iter()
+                .map(|(key, value)| (key.clone(), context.render(value)))
+                .collect(),
+        }
+    }
+}
+
+/// Extra MCP servers configured per repo and per worktree.
+#[derive(Debug, Default)]
+pub struct ExtraMcpServerStore {
+    worktree_servers: HashMap<String, Vec<ExtraMcpServer>>,
+    repo_servers: HashMap<String, Vec<ExtraMcpServer>>,
+}
+
+impl ExtraMcpServerStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn worktree_servers(&self, worktree_id: &str) -> Vec<ExtraMcpServer> {
+        self.worktree_servers
+            .get(worktree_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn repo_servers(&self, repo_path: &str) -> Vec<ExtraMcpServer> {
+        self.repo_servers
+            .get(repo_path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_worktree_servers(
+        &mut self,
+        worktree_id: String,
+        servers: Vec<ExtraMcpServer>,
+    ) -> Result<(), String> {
+        Self::set_servers(&mut self.worktree_servers, worktree_id, servers)
+    }
+
+    pub fn set_repo_servers(
+        &mut self,
+        repo_path: String,
+        servers: Vec<ExtraMcpServer>,
+    ) -> Result<(), String> {
+        Self::set_servers(&mut self.repo_servers, repo_path, servers)
+    }
+
+    fn set_servers(
+        store: &mut HashMap<String, Vec<ExtraMcpServer>>,
+        key: String,
+        servers: Vec<ExtraMcpServer>,
+    ) -> Result<(), String> {
+        let mut names = std::collections::HashSet::new();
+        for server in &servers {
+            if server.name.trim().is_empty() {
+                return Err("MCP server name cannot be empty".to_string());
+            }
+            if server.name == ORCHESTRA_SERVER_NAME {
+                return Err(format!(
+                    "MCP server name '{ORCHESTRA_SERVER_NAME}' is reserved"
+                ));
+            }
+            if server.command.trim().is_empty() {
+                return Err(format!("MCP server '{}' has no command", server.name));
+            }
+            if !names.insert(server.name.as_str()) {
+                return Err(format!("Duplicate MCP server name: {}", server.name));
+            }
+        }
+
+        if servers.is_empty() {
+            store.remove(&key);
+        } else {
+            store.insert(key, servers);
+        }
+        Ok(())
+    }
+
+    /// The extra servers for a run: the repo's servers, with worktree servers
+    /// replacing any of the same name.
+    pub fn resolve(&self, worktree_id: &str, repo_path: Option<&str>) -> Vec<ExtraMcpServer> {
+        let mut servers = repo_path
+            .map(|repo| self.repo_servers(repo))
+            .unwrap_or_default();
+        for server in self.worktree_servers(worktree_id) {
+            servers.retain(|existing| existing.name != server.name);
+            servers.push(server);
+        }
+        servers
+    }
+}
+
+/// Builds the `mcpServers` config handed to Claude for a single run.
+#[derive(Debug, Clone, Default)]
+pub struct McpConfigBuilder {
+    servers: BTreeMap<String, McpServerEntry>,
+}
+
+impl McpConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add Orchestra's approval server for a worktree and Claude process.
+    pub fn orchestra_server(
+        self,
+        server_path: &str,
+        worktree_path: &str,
+        worktree_id: &str,
+        process_id: &str,
+    ) -> Self {
+        self.server(
+            ORCHESTRA_SERVER_NAME,
+            McpStdioServer {
+                command: "node".to_string(),
+                args: vec![server_path.to_string()],
+                env: HashMap::from([
+                    ("WORKTREE_PATH".to_string(), worktree_path.to_string()),
+                    ("WORKTREE_ID".to_string(), worktree_id.to_string()),
+                    (PROCESS_ID_ENV.to_string(), process_id.to_string()),
+                ]),
+            },
+        )
+    }
+
+    /// Point the approval server entry at an already running SSE server. The
+    /// server is shared, so the process id goes in the connection URL.
+    pub fn orchestra_sse_server(self, url: &str, process_id: &str) -> Self {
+        self.server(
+            ORCHESTRA_SERVER_NAME,
+            McpServerEntry::Sse {
+                url: format!("{url}?processId={process_id}"),
+            },
+        )
+    }
+
+    pub fn server(mut self, name: &str, server: impl Into<McpServerEntry>) -> Self {
+        self.servers.insert(name.to_string(), server.into());
+        self
+    }
+
+    /// Add user-defined servers, rendering their templates for this run.
+    pub fn extra_servers(self, servers: &[ExtraMcpServer], context: &McpTemplateContext) -> Self {
+        servers.iter().fold(self, |builder, server| {
+            builder.server(&server.name, server.render(context))
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    pub fn build(&self) -> serde_json::Value {
+        serde_json::json!({ "mcpServers": self.servers })
+    }
+
+    /// Write the config into a fresh private directory under `parent`, owned
+    /// by the returned handle and removed when it is dropped.
+    pub fn write_private(&self, parent: &Path, process_id: &str) -> Result<McpConfigFile, String> {
+        let dir = parent.join(format!("orchestra-mcp-{process_id}"));
+
+        // `create` (not `create_all`) fails if the directory already exists, so
+        // another user cannot pre-create it or swap in a symlink.
+        let mut dir_builder = DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            dir_builder.mode(0o700);
+        }
+        dir_builder.create(&dir).map_err(|e| {
+            format!(
+                "Failed to create MCP config directory {}: {e}",
+                dir.display()
+            )
+        })?;
+
+        // Own the directory from here on so it is cleaned up on any error below
+        let config = McpConfigFile {
+            path: dir.join("mcp_config.json"),
+            dir,
+        };
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&config.path)
+            .map_err(|e| format!("Failed to create MCP config file: {e}"))?;
+        file.write_all(self.build().to_string().as_bytes())
+            .map_err(|e| format!("Failed to write MCP config file: {e}"))?;
+
+        Ok(config)
+    }
+}
+
+/// A per-process MCP config file. Dropping it deletes the file and its directory.
+#[derive(Debug)]
+pub struct McpConfigFile {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl McpConfigFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn path_str(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for McpConfigFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(
+                    dir = %self.dir.display(),
+                    error = %e,
+                    "Failed to remove MCP config directory"
+                );
+            }
+        }
+    }
+}
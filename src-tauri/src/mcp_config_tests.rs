@@ -0,0 +1,233 @@
+#[cfg(test)]
+mod tests {
+    use crate::mcp_config::{
+        ExtraMcpServer, ExtraMcpServerStore, McpConfigBuilder, McpStdioServer, McpTemplateContext,
+        ORCHESTRA_SERVER_NAME, PROCESS_ID_ENV,
+    };
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn create_parent_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "orchestra_mcp_config_{name}_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn create_extra_server(name: &str, command: &str) -> ExtraMcpServer {
+        ExtraMcpServer {
+            name: name.to_string(),
+            command: command.to_string(),
+            args: vec![],
+            env: HashMap::new(),
+        }
+    }
+
+    fn create_test_context() -> McpTemplateContext<'static> {
+        McpTemplateContext {
+            worktree_path: "/repo/.worktrees/feature",
+            worktree_id: "wt-1",
+            repo_path: Some("/repo"),
+            branch: Some("feature/db"),
+        }
+    }
+
+    #[test]
+    fn test_orchestra_server_config() {
+        let config = McpConfigBuilder::new()
+            .orchestra_server(
+                "/app/mcp-server/dist/index.js",
+                "/repo/wt",
+                "wt-1",
+                "proc-1",
+            )
+            .build();
+
+        let server = &config["mcpServers"][ORCHESTRA_SERVER_NAME];
+        assert_eq!(server["command"], "node");
+        assert_eq!(server["args"][0], "/app/mcp-server/dist/index.js");
+        assert_eq!(server["env"]["WORKTREE_PATH"], "/repo/wt");
+        assert_eq!(server["env"]["WORKTREE_ID"], "wt-1");
+        assert_eq!(server["env"][PROCESS_ID_ENV], "proc-1");
+    }
+
+    #[test]
+    fn test_additional_servers_are_merged() {
+        let config = McpConfigBuilder::new()
+            .orchestra_server("/srv/index.js", "/repo/wt", "wt-1", "proc-1")
+            .server(
+                "docs",
+                McpStdioServer {
+                    command: "docs-mcp".to_string(),
+                    args: vec!["--stdio".to_string()],
+                    env: HashMap::new(),
+                },
+            )
+            .build();
+
+        let servers = config["mcpServers"].as_object().unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers["docs"]["command"], "docs-mcp");
+    }
+
+    #[test]
+    fn test_write_private_is_removed_on_drop() {
+        let parent = create_parent_dir("drop");
+        let builder =
+            McpConfigBuilder::new().orchestra_server("/srv/index.js", "/repo/wt", "wt-1", "proc-1");
+
+        let config = builder.write_private(&parent, "proc-1").unwrap();
+        let path = config.path().to_path_buf();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written, builder.build());
+
+        drop(config);
+        assert!(!path.exists());
+        assert!(!path.parent().unwrap().exists());
+
+        let _ = fs::remove_dir_all(&parent);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let parent = create_parent_dir("permissions");
+        let config = McpConfigBuilder::new()
+            .write_private(&parent, "proc-1")
+            .unwrap();
+
+        let file_mode = fs::metadata(config.path()).unwrap().permissions().mode();
+        let dir_mode = fs::metadata(config.path().parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(file_mode & 0o777, 0o600);
+        assert_eq!(dir_mode & 0o777, 0o700);
+
+        drop(config);
+        let _ = fs::remove_dir_all(&parent);
+    }
+
+    #[test]
+    fn test_concurrent_runs_get_separate_files() {
+        let parent = create_parent_dir("concurrent");
+        let builder =
+            McpConfigBuilder::new().orchestra_server("/srv/index.js", "/repo/wt", "wt-1", "proc-1");
+
+        let first = builder.write_private(&parent, "proc-1").unwrap();
+        let second = builder.write_private(&parent, "proc-2").unwrap();
+        assert_ne!(first.path(), second.path());
+
+        // Reusing a process id must not clobber the existing config
+        assert!(builder.write_private(&parent, "proc-1").is_err());
+        assert!(first.path().exists());
+
+        drop(first);
+        drop(second);
+        let _ = fs::remove_dir_all(&parent);
+    }
+
+    #[test]
+    fn test_extra_server_templating() {
+        let server = ExtraMcpServer {
+            name: "db".to_string(),
+            command: "{repo_path}/bin/db-mcp".to_string(),
+            args: vec![
+                "--root".to_string(),
+                "{worktree_path}".to_string(),
+                "--label={worktree_id}@{branch}".to_string(),
+                "{unknown}".to_string(),
+            ],
+            env: HashMap::from([("DB_FILE".to_string(), "{worktree_path}/dev.db".to_string())]),
+        };
+
+        let rendered = server.render(&create_test_context());
+
+        assert_eq!(rendered.command, "/repo/bin/db-mcp");
+        assert_eq!(
+            rendered.args,
+            vec![
+                "--root",
+                "/repo/.worktrees/feature",
+                "--label=wt-1@feature/db",
+                "{unknown}"
+            ]
+        );
+        assert_eq!(rendered.env["DB_FILE"], "/repo/.worktrees/feature/dev.db");
+    }
+
+    #[test]
+    fn test_extra_servers_worktree_overrides_repo() {
+        let mut store = ExtraMcpServerStore::new();
+        store
+            .set_repo_servers(
+                "/repo".to_string(),
+                vec![
+                    create_extra_server("docs", "docs-mcp"),
+                    create_extra_server("db", "db-mcp"),
+                ],
+            )
+            .unwrap();
+        store
+            .set_worktree_servers(
+                "wt-1".to_string(),
+                vec![create_extra_server("db", "db-mcp-dev")],
+            )
+            .unwrap();
+
+        let servers = store.resolve("wt-1", Some("/repo"));
+        assert_eq!(servers.len(), 2);
+        assert_eq!(
+            servers.iter().find(|s| s.name == "db").unwrap().command,
+            "db-mcp-dev"
+        );
+
+        // Other worktrees of the repo only get the repo servers
+        let servers = store.resolve("wt-2", Some("/repo"));
+        assert_eq!(
+            servers.iter().find(|s| s.name == "db").unwrap().command,
+            "db-mcp"
+        );
+        assert!(store.resolve("wt-2", None).is_empty());
+
+        let config = McpConfigBuilder::new()
+            .orchestra_server("/srv/index.js", "/repo/wt", "wt-1", "proc-1")
+            .extra_servers(
+                &store.resolve("wt-1", Some("/repo")),
+                &create_test_context(),
+            )
+            .build();
+        assert_eq!(config["mcpServers"].as_object().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_extra_servers_validation() {
+        let mut store = ExtraMcpServerStore::new();
+
+        let test_cases = vec![
+            vec![create_extra_server("", "cmd")],
+            vec![create_extra_server(ORCHESTRA_SERVER_NAME, "cmd")],
+            vec![create_extra_server("docs", " ")],
+            vec![
+                create_extra_server("docs", "a"),
+                create_extra_server("docs", "b"),
+            ],
+        ];
+
+        for servers in test_cases {
+            assert!(
+                store
+                    .set_worktree_servers("wt-1".to_string(), servers.clone())
+                    .is_err(),
+                "Expected {servers:?} to be rejected"
+            );
+        }
+        assert!(store.worktree_servers("wt-1").is_empty());
+    }
+}
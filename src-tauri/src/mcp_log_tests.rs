@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use crate::mcp_logs::{
+        parse_log_level, read_log_tail, server_log_path, LogEntry, LogLevel, RotatingLog,
+    };
+    use crate::mcp_manager::McpManager;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn create_log_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("orchestra_mcp_logs_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_log_level() {
+        let test_cases = vec![
+            ("❌ MCP: Error in approval polling for abc", LogLevel::Error),
+            ("Failed to run server: boom", LogLevel::Error),
+            (
+                "⏰ MCP: Request abc timed out after 30000ms",
+                LogLevel::Warn,
+            ),
+            ("[WARN] slow response", LogLevel::Warn),
+            ("🔄 MCP: Polling attempt 3 for abc", LogLevel::Debug),
+            ("🚀 MCP Server: Ready to receive tool calls", LogLevel::Info),
+        ];
+
+        for (line, expected) in test_cases {
+            assert_eq!(parse_log_level(line), expected, "Failed for line: {line}");
+        }
+    }
+
+    #[test]
+    fn test_rotating_log_rotates_and_keeps_tail() {
+        let dir = create_log_dir("rotate");
+        let path = server_log_path(&dir, "server-1");
+        let mut log = RotatingLog::new(path.clone(), 400, 2);
+
+        for i in 0..30 {
+            log.append(&LogEntry::new(LogLevel::Info, &format!("line {i}")))
+                .unwrap();
+        }
+
+        assert!(fs::metadata(&path).unwrap().len() <= 400);
+        assert!(dir.join("mcp-server-1.log.1").exists());
+        assert!(dir.join("mcp-server-1.log.2").exists());
+        assert!(!dir.join("mcp-server-1.log.3").exists());
+
+        // The tail spans rotated files and is in order
+        let entries = read_log_tail(&path, 2, 5);
+        let messages: Vec<&str> = entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["line 25", "line 26", "line 27", "line 28", "line 29"]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_manager_reads_server_logs() {
+        let dir = create_log_dir("manager");
+        let manager = McpManager::new();
+        manager.set_log_dir(dir.clone());
+
+        let mut log = RotatingLog::for_server(&dir, "abc-123");
+        log.append(&LogEntry::from_line("❌ MCP: approval failed"))
+            .unwrap();
+        log.append(&LogEntry::from_line("🚀 MCP Server: Ready"))
+            .unwrap();
+
+        let entries = manager.get_server_logs("abc-123", 1).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message, "🚀 MCP Server: Ready");
+
+        let entries = manager.get_server_logs("abc-123", 10).unwrap();
+        assert_eq!(entries[0].level, LogLevel::Error);
+
+        assert!(manager.get_server_logs("missing", 10).is_err());
+        assert!(manager.get_server_logs("../etc/passwd", 10).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Size at which a server log is rotated.
+pub const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Rotated files kept per server (`.1` is the newest).
+pub const MAX_ROTATED_FILES: usize = 3;
+/// Entries returned by `get_mcp_server_logs` when no tail is given.
+pub const DEFAULT_LOG_TAIL: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// Guess the level of an MCP server stderr line. The server logs free-form
+/// text with emoji markers, so this looks at both.
+pub fn parse_log_level(line: &str) -> LogLevel {
+    let lower = line.to_lowercase();
+
+    if line.contains('❌')
+        || line.contains('🚨')
+        || lower.contains("[error]")
+        || lower.contains("level=error")
+        || lower.contains("error:")
+        || lower.contains("failed")
+    {
+        return LogLevel::Error;
+    }
+    if line.contains('⚠')
+        || line.contains('⏰')
+        || lower.contains("[warn")
+        || lower.contains("level=warn")
+        || lower.contains("warning")
+        || lower.contains("timed out")
+    {
+        return LogLevel::Warn;
+    }
+    if line.contains('🔄') || lower.contains("[debug]") || lower.contains("level=debug") {
+        return LogLevel::Debug;
+    }
+    LogLevel::Info
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn new(level: LogLevel, message: &str) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            level,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn from_line(line: &str) -> Self {
+        Self::new(parse_log_level(line), line)
+    }
+}
+
+pub fn server_log_path(log_dir: &Path, server_id: &str) -> PathBuf {
+    log_dir.join(format!("mcp-{server_id}.log"))
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+/// A JSON-lines log file that rotates to `<path>.1`, `<path>.2`, ... once it
+/// grows past `max_bytes`.
+pub struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingLog {
+    pub fn new(path: PathBuf, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            path,
+            max_bytes,
+            max_files,
+            file: None,
+            size: 0,
+        }
+    }
+
+    pub fn for_server(log_dir: &Path, server_id: &str) -> Self {
+        Self::new(
+            server_log_path(log_dir, server_id),
+            MAX_LOG_BYTES,
+            MAX_ROTATED_FILES,
+        )
+    }
+
+    pub fn append(&mut self, entry: &LogEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry).map_err(io::Error::other)?;
+        line.push('\n');
+
+        if self.file.is_none() {
+            self.open()?;
+        }
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let file = self.file.as_mut().expect("log file is open");
+        file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+
+        self.open()
+    }
+}
+
+/// The last `tail` entries of a log, reading into rotated files as needed.
+/// Lines that are not valid entries are returned as info messages.
+pub fn read_log_tail(path: &Path, max_files: usize, tail: usize) -> Vec<LogEntry> {
+    let mut files: Vec<PathBuf> = (1..=max_files)
+        .rev()
+        .map(|index| rotated_path(path, index))
+        .collect();
+    files.push(path.to_path_buf());
+
+    let mut entries = Vec::new();
+    for file in files {
+        let Ok(file) = File::open(&file) else {
+            continue;
+        };
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(
+                serde_json::from_str(&line)
+                    .unwrap_or_else(|_| LogEntry::new(LogLevel::Info, &line)),
+            );
+        }
+    }
+
+    let skip = entries.len().saturating_sub(tail);
+    entries.split_off(skip)
+}
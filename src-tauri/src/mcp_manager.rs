@@ -1,27 +1,79 @@
-use axum::{extract::State, http::StatusCode, response::Json, routing::post, Router};
+use crate::approval_broker::{ApprovalBroker, ApprovalRecord, APPROVAL_TIMEOUT};
+use crate::approval_context::file_change_context;
+use crate::approval_grants::GrantRequest;
+use crate::claude_errors::RetryPolicy;
+use crate::command_risk::tool_risk;
+use crate::diagnostics::{parse_version, run_version_command, MIN_NODE_VERSION};
+use crate::mcp_logs::{
+    read_log_tail, server_log_path, LogEntry, LogLevel, RotatingLog, MAX_ROTATED_FILES,
+};
+use crate::mcp_supervisor::{
+    default_restart_policy, McpServerHealth, McpServerState, McpServerStatus,
+};
+use crate::port_allocator::PortAllocator;
+use crate::protected_paths::{denial_message, protected_targets};
+use crate::read_only::read_only_denial;
+use crate::redaction::{LineRedactor, Redactor};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
+    routing::{get, post},
+    Router,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tauri::{AppHandle, Emitter};
-use tokio::sync::{oneshot, Mutex};
+use tokio::sync::Mutex;
 use tower_http::cors::CorsLayer;
 use uuid::Uuid;
 
+/// Port of the local HTTP server that receives approval requests.
+pub const APPROVAL_SERVER_PORT: u16 = 8080;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServerConfig {
     pub server_id: String,
     pub worktree_id: String,
     pub worktree_path: String,
     pub server_path: String,
+    /// Set when the server runs in SSE mode on this local port.
     pub port: Option<u16>,
 }
 
+impl McpServerConfig {
+    pub fn sse_url(&self) -> Option<String> {
+        self.port.map(|port| format!("http://127.0.0.1:{port}/sse"))
+    }
+}
+
+/// How McpManager runs worktree MCP servers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpTransport {
+    /// Each Claude invocation spawns its own server over stdio.
+    #[default]
+    Stdio,
+    /// One long-running HTTP/SSE server per worktree, shared by all invocations.
+    Sse,
+}
+
+/// How long to wait for a freshly started SSE server to accept connections.
+const SSE_STARTUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApprovalRequest {
     pub tool_name: String,
     pub input: serde_json::Value,
     pub worktree_id: String,
+    #[serde(default)]
+    pub process_id: Option<String>,
     pub timestamp: u64,
 }
 
@@ -37,6 +89,9 @@ pub struct ApprovalResponse {
     pub message: Option<String>,
     #[serde(rename = "updatedInput")]
     pub updated_input: Option<serde_json::Value>,
+    /// Also allow matching requests later, see [`GrantRequest`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grant: Option<GrantRequest>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,97 +103,325 @@ pub struct HttpApprovalRequest {
     pub input: serde_json::Value,
     #[serde(rename = "worktreeId")]
     pub worktree_id: String,
+    /// Sent by MCP servers that know which Claude process they serve.
+    #[serde(rename = "processId", default)]
+    pub process_id: Option<String>,
     pub timestamp: u64,
 }
 
-// Structure to hold pending HTTP approval with response channel
-pub struct PendingHttpApproval {
-    pub request: HttpApprovalRequest,
-    pub response_tx: oneshot::Sender<ApprovalResponse>,
-}
-
 // State for the HTTP server
 #[derive(Clone)]
 pub struct HttpAppState {
-    pub pending_http_approvals: Arc<Mutex<HashMap<String, PendingHttpApproval>>>,
+    pub approvals: Arc<ApprovalBroker>,
     pub app_handle: Option<AppHandle>,
 }
 
+/// Prometheus scrape endpoint. The labels include repository paths, so only
+/// scrapers on this machine are answered.
+pub async fn handle_metrics(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    State(state): State<HttpAppState>,
+) -> impl IntoResponse {
+    if !peer.ip().is_loopback() {
+        return (
+            StatusCode::FORBIDDEN,
+            "Metrics are only served on localhost",
+        )
+            .into_response();
+    }
+    let Some(app_handle) = &state.app_handle else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Orchestra is starting").into_response();
+    };
+    let body = crate::metrics::render(&crate::metrics_snapshot(app_handle));
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+/// Mask secrets in an approval's input before it is shown or kept in the
+/// history, and count them against the requesting process. The unmasked
+/// input stays on the record for grants and edits.
+fn redact_approval(app_handle: Option<&AppHandle>, record: &mut ApprovalRecord) -> Arc<Redactor> {
+    let redactor = app_handle.map(crate::redactor).unwrap_or_default();
+    let original = record.input.clone();
+    let redactions = redactor.redact_json(&mut record.input);
+    if redactions > 0 {
+        record.unredacted_input = Some(original);
+    }
+    if let (Some(app_handle), Some(process_id)) = (app_handle, &record.process_id) {
+        crate::record_redactions(app_handle, process_id, redactions);
+    }
+    redactor
+}
+
+/// A caller's claim on a worktree's `starting` slot. The slot is removed
+/// when the last caller waiting on it is done, however `ensure_server` returns.
+struct StartingSlot<'a> {
+    starting: &'a std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    worktree_id: String,
+    slot: Arc<Mutex<()>>,
+}
+
+impl<'a> StartingSlot<'a> {
+    fn claim(
+        starting: &'a std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+        worktree_id: &str,
+    ) -> Self {
+        let slot = starting
+            .lock()
+            .unwrap()
+            .entry(worktree_id.to_string())
+            .or_default()
+            .clone();
+        Self {
+            starting,
+            worktree_id: worktree_id.to_string(),
+            slot,
+        }
+    }
+}
+
+impl Drop for StartingSlot<'_> {
+    fn drop(&mut self) {
+        let mut starting = self.starting.lock().unwrap();
+        // The map and this claim hold the only references when nobody waits
+        if Arc::strong_count(&self.slot) <= 2
+            && starting
+                .get(&self.worktree_id)
+                .is_some_and(|slot| Arc::ptr_eq(slot, &self.slot))
+        {
+            starting.remove(&self.worktree_id);
+        }
+    }
+}
+
+/// Cancels an approval if the HTTP request is dropped (the MCP server went
+/// away) before it was answered, and puts the requesting process back to
+/// `running` once it has no other approvals open.
+struct PendingApprovalGuard {
+    approvals: Arc<ApprovalBroker>,
+    approval_id: String,
+    process_id: Option<String>,
+    app_handle: Option<AppHandle>,
+}
+
+impl Drop for PendingApprovalGuard {
+    fn drop(&mut self) {
+        if self.approvals.cancel(&self.approval_id).is_some() {
+            tracing::info!(
+                approval_id = %self.approval_id,
+                "Approval cancelled, the requester disconnected"
+            );
+        }
+        if let (Some(process_id), Some(app_handle)) = (&self.process_id, &self.app_handle) {
+            if !self.approvals.has_pending_for_process(process_id) {
+                crate::set_process_awaiting_approval(app_handle, process_id, false);
+            }
+        }
+    }
+}
+
 // HTTP handler for approval requests
+#[tracing::instrument(
+    name = "approval",
+    skip_all,
+    fields(
+        approval_id = %request.request_id,
+        tool = %request.tool_name,
+        worktree_id = %request.worktree_id,
+        process_id = request.process_id.as_deref(),
+    )
+)]
 pub async fn handle_approval_request(
     State(state): State<HttpAppState>,
     Json(request): Json<HttpApprovalRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    eprintln!(
-        "🔵 RUST HTTP: Received approval request for tool: {}",
-        request.tool_name
+    tracing::info!("Received approval request");
+
+    // Where the request comes from, for diffs, risk, protected paths and grants
+    let (worktree_path, repo_path, protected_patterns, read_only) = match &state.app_handle {
+        Some(app_handle) => {
+            let read_only = crate::process_is_read_only(
+                app_handle,
+                request.process_id.as_deref(),
+                &request.worktree_id,
+            );
+            let worktree_path = crate::worktree_path_for(app_handle, &request.worktree_id).await;
+            let repo_path =
+                crate::repo_path_for(app_handle, &request.worktree_id, worktree_path.as_deref());
+            let patterns = crate::protected_patterns_for(app_handle, repo_path.as_deref());
+            (worktree_path, repo_path, patterns, read_only)
+        }
+        None => (None, None, Vec::new(), false),
+    };
+    let risk = tool_risk(&request.tool_name, &request.input, worktree_path.as_deref());
+    let roots: Vec<&str> = worktree_path
+        .iter()
+        .chain(repo_path.iter())
+        .map(String::as_str)
+        .collect();
+    let protected = protected_targets(
+        &request.tool_name,
+        &request.input,
+        &protected_patterns,
+        &roots,
     );
-    eprintln!("🔵 RUST HTTP: Request ID: {}", request.request_id);
-    eprintln!("🔵 RUST HTTP: Worktree ID: {}", request.worktree_id);
-
-    // Create a oneshot channel to wait for user response
-    let (response_tx, response_rx) = oneshot::channel();
+    let mut record = ApprovalRecord::new(
+        request.request_id.clone(),
+        request.tool_name.clone(),
+        request.input.clone(),
+        request.worktree_id.clone(),
+        request.timestamp,
+    )
+    .with_process(request.process_id.clone())
+    .with_repo(repo_path);
+    // The agent still gets the real input; the UI and history do not
+    let redactor = redact_approval(state.app_handle.as_ref(), &mut record);
+    let shown_input = record.input.clone();
+    tracing::debug!(input = %shown_input, "Approval request input");
+
+    // Read-only runs and protected paths are denied before grants or the user are consulted
+    let read_only_message = read_only
+        .then(|| read_only_denial(&request.tool_name, &request.input))
+        .flatten();
+    if read_only_message.is_some() || !protected.is_empty() {
+        let message = read_only_message.unwrap_or_else(|| denial_message(&protected));
+        tracing::info!(%message, "Approval denied by policy");
+        state.approvals.record_denied(record, message.clone());
+        if let Some(app_handle) = &state.app_handle {
+            let _ = app_handle.emit(
+                "tool-approval-denied",
+                serde_json::json!({
+                    "approval_id": request.request_id,
+                    "tool_name": request.tool_name,
+                    "worktree_id": request.worktree_id,
+                    "process_id": request.process_id,
+                    "read_only": read_only,
+                    "protected_paths": protected,
+                    "message": message,
+                }),
+            );
+        }
+        return Ok(Json(serde_json::json!({
+            "behavior": "deny",
+            "message": message
+        })));
+    }
 
-    // Store the pending approval
+    // Requests covered by an earlier "always allow" never reach the dialog
+    if let Some(grant) = state
+        .approvals
+        .find_grant(&record, risk.as_ref().map(|risk| risk.level))
     {
-        let mut pending = state.pending_http_approvals.lock().await;
-        pending.insert(
-            request.request_id.clone(),
-            PendingHttpApproval {
-                request: request.clone(),
-                response_tx,
-            },
-        );
-        eprintln!(
-            "🔵 RUST HTTP: Stored pending approval, total count: {}",
-            pending.len()
-        );
+        tracing::info!(grant_id = %grant.grant_id, "Approval allowed by grant");
+        state.approvals.record_granted(record, &grant);
+        if let Some(app_handle) = &state.app_handle {
+            let _ = app_handle.emit(
+                "tool-approval-granted",
+                serde_json::json!({
+                    "approval_id": request.request_id,
+                    "grant_id": grant.grant_id,
+                    "tool_name": request.tool_name,
+                    "worktree_id": request.worktree_id,
+                    "process_id": request.process_id,
+                }),
+            );
+        }
+        return Ok(Json(serde_json::json!({
+            "behavior": "allow",
+            "message": null,
+            "updatedInput": request.input
+        })));
     }
 
+    // Register with the broker and wait on the returned channel for the user
+    let response_rx = state.approvals.submit(record).map_err(|e| {
+        tracing::warn!(error = %e, "Failed to submit approval");
+        StatusCode::CONFLICT
+    })?;
+    let _guard = PendingApprovalGuard {
+        approvals: state.approvals.clone(),
+        approval_id: request.request_id.clone(),
+        process_id: request.process_id.clone(),
+        app_handle: state.app_handle.clone(),
+    };
+    tracing::debug!(
+        pending = state.approvals.list_pending().len(),
+        "Waiting for an answer"
+    );
+
     // Emit event to UI for approval dialog
     if let Some(app_handle) = &state.app_handle {
+        if let Some(process_id) = &request.process_id {
+            crate::set_process_awaiting_approval(app_handle, process_id, true);
+        }
+
+        // Show file edits as a diff against the worktree instead of raw JSON
+        let file_change = match worktree_path {
+            Some(worktree_path) => {
+                let tool_name = request.tool_name.clone();
+                let input = request.input.clone();
+                tokio::task::spawn_blocking(move || {
+                    file_change_context(&tool_name, &input, &worktree_path)
+                })
+                .await
+                .ok()
+                .flatten()
+            }
+            None => None,
+        };
+        let mut file_change = serde_json::to_value(file_change).unwrap_or_default();
+        let redactions = redactor.redact_json(&mut file_change);
+        if let Some(process_id) = &request.process_id {
+            crate::record_redactions(app_handle, process_id, redactions);
+        }
+
         let event_payload = serde_json::json!({
             "approval_id": request.request_id,
             "request": {
                 "toolName": request.tool_name,
-                "input": request.input,
+                "input": shown_input,
                 "worktreeId": request.worktree_id,
+                "processId": request.process_id,
                 "timestamp": request.timestamp
-            }
+            },
+            "file_change": file_change,
+            "risk": risk,
         });
 
-        eprintln!("📤 RUST HTTP: Emitting tool-approval-request event");
+        tracing::debug!("Emitting tool-approval-request");
         let _ = app_handle.emit("tool-approval-request", event_payload);
     }
 
     // Wait for user response (this blocks the HTTP request until user responds)
-    match response_rx.await {
-        Ok(response) => {
-            eprintln!("✅ RUST HTTP: User responded with: {response:?}");
-
-            // Serialize the response to check what we're sending
-            let _json_response = match serde_json::to_string(&response) {
-                Ok(json_str) => {
-                    eprintln!("📤 RUST HTTP: Sending JSON response: {json_str}");
-                    json_str
-                }
-                Err(e) => {
-                    eprintln!("❌ RUST HTTP: Failed to serialize response: {e}");
-                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
-                }
-            };
-
-            eprintln!("🔵 RUST HTTP: About to return HTTP 200 response");
-
+    match tokio::time::timeout(APPROVAL_TIMEOUT, response_rx).await {
+        Err(_) => {
+            tracing::info!("Approval expired without an answer");
+            state.approvals.expire(&request.request_id);
+            if let Some(app_handle) = &state.app_handle {
+                let _ = app_handle.emit(
+                    "tool-approval-expired",
+                    serde_json::json!({
+                        "approval_id": request.request_id,
+                        "process_id": request.process_id,
+                    }),
+                );
+            }
+            Ok(Json(serde_json::json!({
+                "behavior": "deny",
+                "message": "The approval request expired before the user answered"
+            })))
+        }
+        Ok(Ok(response)) => {
             // Convert behavior back to lowercase for MCP protocol compliance
             let mcp_behavior = match response.behavior {
                 ApprovalBehavior::Allow => "allow",
                 ApprovalBehavior::Deny => "deny",
             };
 
-            eprintln!(
-                "🔵 RUST HTTP: Converting behavior '{:?}' to MCP format '{}'",
-                response.behavior, mcp_behavior
+            tracing::info!(behavior = mcp_behavior, "User answered approval");
+            tracing::debug!(
+                message = ?response.message,
+                edited = response.updated_input.is_some(),
+                "Approval response"
             );
             Ok(Json(serde_json::json!({
                 "behavior": mcp_behavior,
@@ -146,16 +429,133 @@ pub async fn handle_approval_request(
                 "updatedInput": response.updated_input
             })))
         }
-        Err(_) => {
-            eprintln!("❌ RUST HTTP: Failed to receive user response - oneshot channel closed");
+        Ok(Err(_)) => {
+            tracing::error!("Approval channel closed before the user answered");
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
+/// Location of the MCP server inside the app's resource directory.
+pub const MCP_SERVER_RESOURCE: &str = "mcp-server/dist/index.js";
+/// Environment variable that overrides the MCP server location.
+pub const MCP_SERVER_PATH_ENV: &str = "ORCHESTRA_MCP_SERVER_PATH";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServerSource {
+    Override,
+    Bundled,
+    Development,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpRuntimeInfo {
+    pub server_path: String,
+    pub source: McpServerSource,
+    pub node_version: String,
+}
+
+/// Find the MCP server: an explicit override, then the bundled resource, then
+/// (in debug builds only) the `mcp-server` checkout next to the working directory.
+pub fn resolve_mcp_server_path(
+    override_path: Option<&str>,
+    resource_dir: Option<&Path>,
+) -> Result<(PathBuf, McpServerSource), String> {
+    if let Some(path) = override_path {
+        let path = PathBuf::from(path);
+        if path.is_file() {
+            return Ok((path, McpServerSource::Override));
+        }
+        return Err(format!(
+            "MCP server override path does not exist: {}",
+            path.display()
+        ));
+    }
+
+    let mut tried = Vec::new();
+
+    if let Some(resource_dir) = resource_dir {
+        let bundled = resource_dir.join(MCP_SERVER_RESOURCE);
+        if bundled.is_file() {
+            return Ok((bundled, McpServerSource::Bundled));
+        }
+        tried.push(bundled);
+    }
+
+    if cfg!(debug_assertions) {
+        if let Ok(current_dir) = std::env::current_dir() {
+            let candidates = [
+                current_dir.join("mcp-server").join("dist").join("index.js"),
+                current_dir
+                    .parent()
+                    .unwrap_or(&current_dir)
+                    .join("mcp-server")
+                    .join("dist")
+                    .join("index.js"),
+            ];
+            for candidate in candidates {
+                if candidate.is_file() {
+                    return Ok((candidate, McpServerSource::Development));
+                }
+                tried.push(candidate);
+            }
+        }
+    }
+
+    Err(format!(
+        "MCP server not found. Tried paths: {:?}\nRun 'npm run build' in the mcp-server directory or set {MCP_SERVER_PATH_ENV}.",
+        tried.iter().map(|p| p.display().to_string()).collect::<Vec<_>>()
+    ))
+}
+
+/// Check that `node` can run the resolved server.
+pub fn validate_mcp_runtime(
+    server_path: PathBuf,
+    source: McpServerSource,
+) -> Result<McpRuntimeInfo, String> {
+    let output = run_version_command("node")
+        .map_err(|e| format!("Node.js is required to run the MCP server: {e}"))?;
+    let node_version = parse_version(&output)
+        .ok_or_else(|| format!("Could not parse node version from: {}", output.trim()))?;
+
+    if node_version < MIN_NODE_VERSION {
+        return Err(format!(
+            "Node.js {node_version} is too old to run the MCP server (minimum {MIN_NODE_VERSION})"
+        ));
+    }
+
+    Ok(McpRuntimeInfo {
+        server_path: server_path.to_string_lossy().to_string(),
+        source,
+        node_version: node_version.to_string(),
+    })
+}
+
+/// Wait until something accepts connections on a local port.
+pub async fn wait_for_port(port: u16, timeout: std::time::Duration) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!(
+                "MCP server did not start listening on port {port} within {timeout:?}"
+            ));
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
 pub struct McpServer {
     pub config: McpServerConfig,
     pub process: Option<Child>,
+    pub health: McpServerHealth,
+    log: Option<Arc<std::sync::Mutex<RotatingLog>>>,
 }
 
 impl McpServer {
@@ -163,17 +563,71 @@ impl McpServer {
         Self {
             config,
             process: None,
+            health: McpServerHealth::default(),
+            log: None,
+        }
+    }
+
+    /// Persist this server's stderr (and supervisor notes) to `log`.
+    pub fn with_log(mut self, log: RotatingLog) -> Self {
+        self.log = Some(Arc::new(std::sync::Mutex::new(log)));
+        self
+    }
+
+    pub fn log(&self, level: LogLevel, message: &str) {
+        if let Some(log) = &self.log {
+            if let Err(e) = log.lock().unwrap().append(&LogEntry::new(level, message)) {
+                tracing::warn!(server_id = %self.config.server_id, error = %e, "Failed to write MCP server log");
+            }
+        }
+    }
+
+    pub fn status(&self, message: Option<String>) -> McpServerStatus {
+        McpServerStatus {
+            server_id: self.config.server_id.clone(),
+            worktree_id: self.config.worktree_id.clone(),
+            state: self.health.state(),
+            restart_count: self.health.restart_count(),
+            message,
         }
     }
 
+    /// Check whether the process has exited, without logging on every poll.
+    /// Returns a description of how it ended.
+    fn poll_exit(&mut self) -> Option<String> {
+        let Some(process) = &mut self.process else {
+            return Some("process handle lost".to_string());
+        };
+        let exit = match process.try_wait() {
+            Ok(None) => return None,
+            Ok(Some(status)) => status.to_string(),
+            Err(e) => format!("status unavailable: {e}"),
+        };
+        self.process = None;
+        Some(exit)
+    }
+
+    /// The span that this server's logs belong to.
+    fn span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "worktree",
+            worktree_id = %self.config.worktree_id,
+            server_id = %self.config.server_id
+        )
+    }
+
     pub fn start(&mut self, app_handle: AppHandle) -> Result<(), String> {
         if self.process.is_some() {
             return Err("MCP server is already running".to_string());
         }
 
-        eprintln!("Starting MCP server with path: {}", self.config.server_path);
-        eprintln!("Worktree path: {}", self.config.worktree_path);
-        eprintln!("Worktree ID: {}", self.config.worktree_id);
+        let span = self.span();
+        let _span = span.enter();
+        tracing::info!(
+            server_path = %self.config.server_path,
+            worktree_path = %self.config.worktree_path,
+            "Starting MCP server"
+        );
 
         let mut cmd = Command::new("node");
         cmd.arg(&self.config.server_path)
@@ -182,13 +636,17 @@ impl McpServer {
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
+        if let Some(port) = self.config.port {
+            cmd.env("MCP_TRANSPORT", "sse")
+                .env("MCP_PORT", port.to_string());
+        }
 
         let mut child = cmd.spawn().map_err(|e| {
-            eprintln!("Failed to spawn MCP server process: {e}");
+            tracing::error!(error = %e, "Failed to spawn MCP server process");
             format!("Failed to start MCP server: {e}")
         })?;
 
-        eprintln!("MCP server started successfully with PID: {:?}", child.id());
+        tracing::info!(pid = child.id(), "MCP server started");
 
         // Emit a test event to verify event system is working
         let _ = app_handle.emit(
@@ -203,15 +661,27 @@ impl McpServer {
         // Capture stderr for debugging MCP server logs (not for approval processing)
         if let Some(stderr) = child.stderr.take() {
             let app_handle_clone = app_handle.clone();
+            let log = self.log.clone();
+            let span = span.clone();
             std::thread::spawn(move || {
                 use std::io::{BufRead, BufReader};
+                let _span = span.enter();
                 let reader = BufReader::new(stderr);
-                eprintln!("🔥 MCP: Starting stderr monitoring for debug logs");
+                let mut line_redactor = LineRedactor::default();
 
                 for line in reader.lines() {
                     match line {
                         Ok(line) => {
-                            eprintln!("📝 MCP STDERR: {line}");
+                            // Tool inputs the server echoes are persisted in its log
+                            let redactor = crate::redactor(&app_handle_clone);
+                            let Some((line, _)) = line_redactor.redact(&redactor, &line) else {
+                                continue;
+                            };
+                            tracing::debug!(%line, "MCP server stderr");
+
+                            if let Some(log) = &log {
+                                let _ = log.lock().unwrap().append(&LogEntry::from_line(&line));
+                            }
 
                             // Emit as debug event
                             let _ = app_handle_clone.emit(
@@ -223,20 +693,26 @@ impl McpServer {
                             );
                         }
                         Err(e) => {
-                            eprintln!("❌ MCP: Error reading stderr: {e}");
+                            tracing::warn!(error = %e, "Failed to read MCP server stderr");
                             break;
                         }
                     }
                 }
-                eprintln!("🔥 MCP: Stderr monitoring ended");
+                tracing::debug!("MCP server stderr closed");
             });
         }
 
+        self.log(
+            LogLevel::Info,
+            &format!("MCP server started with PID {}", child.id()),
+        );
         self.process = Some(child);
+        self.health.on_started(Instant::now());
         Ok(())
     }
 
     pub fn stop(&mut self) -> Result<(), String> {
+        self.health.on_stopped();
         if let Some(mut process) = self.process.take() {
             process
                 .kill()
@@ -254,36 +730,24 @@ impl McpServer {
             match process.try_wait() {
                 Ok(Some(status)) => {
                     // Process has exited
-                    eprintln!(
-                        "MCP server process {} has exited with status: {:?}",
-                        self.config.server_id, status
-                    );
+                    tracing::info!(parent: &self.span(), ?status, "MCP server exited");
                     self.process = None;
                     false
                 }
                 Ok(None) => {
                     // Process is still running
-                    eprintln!(
-                        "MCP server process {} is still running",
-                        self.config.server_id
-                    );
+                    tracing::trace!(parent: &self.span(), "MCP server is running");
                     true
                 }
                 Err(e) => {
                     // Error checking process status
-                    eprintln!(
-                        "Error checking MCP server process {} status: {}",
-                        self.config.server_id, e
-                    );
+                    tracing::warn!(parent: &self.span(), error = %e, "Failed to check MCP server status");
                     self.process = None;
                     false
                 }
             }
         } else {
-            eprintln!(
-                "MCP server process {} has no process handle",
-                self.config.server_id
-            );
+            tracing::debug!(parent: &self.span(), "MCP server has no process handle");
             false
         }
     }
@@ -291,20 +755,36 @@ impl McpServer {
 
 pub struct McpManager {
     servers: Arc<Mutex<HashMap<String, McpServer>>>,
-    // Legacy approval system (kept for backward compatibility)
-    pending_approvals: Arc<Mutex<HashMap<String, ApprovalRequest>>>,
-    // New HTTP approval system
-    pub pending_http_approvals: Arc<Mutex<HashMap<String, PendingHttpApproval>>>,
+    /// Held by the caller starting a worktree's server, by worktree id.
+    starting: std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    pub approvals: Arc<ApprovalBroker>,
     app_handle: Option<AppHandle>,
+    resource_dir: std::sync::Mutex<Option<PathBuf>>,
+    server_path_override: std::sync::Mutex<Option<String>>,
+    runtime: std::sync::Mutex<Option<Result<McpRuntimeInfo, String>>>,
+    restart_policy: RetryPolicy,
+    log_dir: std::sync::Mutex<Option<PathBuf>>,
+    transport: std::sync::Mutex<McpTransport>,
+    ports: std::sync::Mutex<PortAllocator>,
+    /// MCP servers restarted by the supervisor since startup.
+    restarts: AtomicU64,
 }
 
 impl McpManager {
     pub fn new() -> Self {
         Self {
             servers: Arc::new(Mutex::new(HashMap::new())),
-            pending_approvals: Arc::new(Mutex::new(HashMap::new())),
-            pending_http_approvals: Arc::new(Mutex::new(HashMap::new())),
+            starting: std::sync::Mutex::new(HashMap::new()),
+            approvals: Arc::new(ApprovalBroker::default()),
             app_handle: None,
+            resource_dir: std::sync::Mutex::new(None),
+            server_path_override: std::sync::Mutex::new(None),
+            runtime: std::sync::Mutex::new(None),
+            restart_policy: default_restart_policy(),
+            log_dir: std::sync::Mutex::new(None),
+            transport: std::sync::Mutex::new(McpTransport::default()),
+            ports: std::sync::Mutex::new(PortAllocator::default()),
+            restarts: AtomicU64::new(0),
         }
     }
 
@@ -314,56 +794,159 @@ impl McpManager {
 
     pub async fn start_http_server(&self) -> Result<(), String> {
         let app_state = HttpAppState {
-            pending_http_approvals: self.pending_http_approvals.clone(),
+            approvals: self.approvals.clone(),
             app_handle: self.app_handle.clone(),
         };
 
         let app = Router::new()
             .route("/api/approval-request", post(handle_approval_request))
+            .route("/metrics", get(handle_metrics))
             .layer(CorsLayer::permissive())
             .with_state(app_state);
 
-        eprintln!("🌐 RUST: Starting HTTP server on http://localhost:8080");
-
         tokio::spawn(async move {
-            let listener = tokio::net::TcpListener::bind("0.0.0.0:8080")
+            let listener = tokio::net::TcpListener::bind(("0.0.0.0", APPROVAL_SERVER_PORT))
                 .await
-                .expect("Failed to bind to port 8080");
+                .expect("Failed to bind the approval server port");
 
-            eprintln!("🟢 RUST: HTTP server listening on http://localhost:8080");
+            tracing::info!(
+                port = APPROVAL_SERVER_PORT,
+                "Approval HTTP server listening"
+            );
 
-            axum::serve(listener, app)
-                .await
-                .expect("HTTP server failed");
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .expect("HTTP server failed");
         });
 
         Ok(())
     }
 
+    /// Start an MCP server for a worktree. A worktree has at most one server,
+    /// so an existing server's id is returned instead of starting another.
     pub async fn create_server(
         &self,
         worktree_id: String,
         worktree_path: String,
         app_handle: AppHandle,
     ) -> Result<String, String> {
+        self.ensure_server(worktree_id, worktree_path, app_handle)
+            .await
+            .map(|config| config.server_id)
+    }
+
+    /// The worktree's MCP server, started lazily on first use and reused after.
+    pub async fn ensure_server(
+        &self,
+        worktree_id: String,
+        worktree_path: String,
+        app_handle: AppHandle,
+    ) -> Result<McpServerConfig, String> {
+        // Reserve the worktree so concurrent callers cannot both start a
+        // server for it, without holding `servers` while this one starts
+        let slot = StartingSlot::claim(&self.starting, &worktree_id);
+        let _reserved = slot.slot.lock().await;
+
+        {
+            let mut servers = self.servers.lock().await;
+            let existing_id = servers
+                .values()
+                .find(|server| server.config.worktree_id == worktree_id)
+                .map(|server| server.config.server_id.clone());
+            if let Some(server_id) = existing_id {
+                let server = servers.get_mut(&server_id).unwrap();
+                match server.health.state() {
+                    McpServerState::Running | McpServerState::Restarting => {
+                        return Ok(server.config.clone());
+                    }
+                    // Replace a server the supervisor gave up on
+                    McpServerState::Failed | McpServerState::Stopped => {
+                        let _ = server.stop();
+                        self.release_port(server.config.port);
+                        servers.remove(&server_id);
+                    }
+                }
+            }
+        }
+
         let server_id = Uuid::new_v4().to_string();
         let server_path = self.get_mcp_server_path()?;
+        let port = match self.transport() {
+            McpTransport::Stdio => None,
+            McpTransport::Sse => Some(self.ports.lock().unwrap().allocate()?),
+        };
 
         let config = McpServerConfig {
             server_id: server_id.clone(),
-            worktree_id: worktree_id.clone(),
+            worktree_id,
             worktree_path,
             server_path,
-            port: None,
+            port,
         };
 
-        let mut server = McpServer::new(config);
-        server.start(app_handle)?;
+        let mut server = McpServer::new(config.clone())
+            .with_log(RotatingLog::for_server(&self.log_dir(), &config.server_id));
+        if let Err(e) = server.start(app_handle) {
+            self.release_port(port);
+            return Err(e);
+        }
+
+        if let Some(port) = port {
+            if let Err(e) = wait_for_port(port, SSE_STARTUP_TIMEOUT).await {
+                let _ = server.stop();
+                self.release_port(Some(port));
+                return Err(e);
+            }
+        }
+        self.servers.lock().await.insert(server_id, server);
 
+        Ok(config)
+    }
+
+    pub async fn server_for_worktree(&self, worktree_id: &str) -> Option<McpServerConfig> {
+        let servers = self.servers.lock().await;
+        servers
+            .values()
+            .find(|server| server.config.worktree_id == worktree_id)
+            .map(|server| server.config.clone())
+    }
+
+    /// Stop the MCP server of a removed worktree. Matched by path because
+    /// worktrees created by the frontend are not tracked by id in the backend.
+    pub async fn stop_worktree_server(&self, worktree_path: &str) -> Result<(), String> {
         let mut servers = self.servers.lock().await;
-        servers.insert(server_id.clone(), server);
+        let server_ids: Vec<String> = servers
+            .values()
+            .filter(|server| server.config.worktree_path == worktree_path)
+            .map(|server| server.config.server_id.clone())
+            .collect();
+
+        for server_id in server_ids {
+            if let Some(mut server) = servers.remove(&server_id) {
+                self.release_port(server.config.port);
+                server.stop()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn transport(&self) -> McpTransport {
+        *self.transport.lock().unwrap()
+    }
+
+    /// Choose the transport for servers started from now on. Running servers
+    /// keep theirs until they are recreated.
+    pub fn set_transport(&self, transport: McpTransport) {
+        *self.transport.lock().unwrap() = transport;
+    }
 
-        Ok(server_id)
+    fn release_port(&self, port: Option<u16>) {
+        if let Some(port) = port {
+            self.ports.lock().unwrap().release(port);
+        }
     }
 
     pub async fn stop_server(&self, server_id: &str) -> Result<(), String> {
@@ -371,6 +954,7 @@ impl McpManager {
 
         if let Some(server) = servers.get_mut(server_id) {
             server.stop()?;
+            self.release_port(server.config.port);
             servers.remove(server_id);
             Ok(())
         } else {
@@ -391,126 +975,225 @@ impl McpManager {
         servers.get_mut(server_id).map(|server| server.is_running())
     }
 
+    /// Record an approval request made through the `request_tool_approval`
+    /// command. Nobody blocks on it; the answer is kept in the broker.
     pub async fn request_approval(&self, request: ApprovalRequest) -> Result<String, String> {
         let approval_id = Uuid::new_v4().to_string();
-
-        let mut pending = self.pending_approvals.lock().await;
-        pending.insert(approval_id.clone(), request);
-
+        let mut record = ApprovalRecord::new(
+            approval_id.clone(),
+            request.tool_name,
+            request.input,
+            request.worktree_id,
+            request.timestamp,
+        )
+        .with_process(request.process_id);
+        redact_approval(self.app_handle.as_ref(), &mut record);
+        self.approvals.register(record)?;
         Ok(approval_id)
     }
 
+    #[tracing::instrument(name = "approval", skip_all, fields(approval_id = %approval_id))]
     pub async fn respond_to_approval(
         &self,
         approval_id: String,
         response: ApprovalResponse,
     ) -> Result<(), String> {
-        eprintln!("🔵 RUST: respond_to_approval called for ID: {approval_id}");
+        tracing::debug!(
+            behavior = ?response.behavior,
+            edited = response.updated_input.is_some(),
+            "Responding to approval"
+        );
+        self.approvals
+            .respond(&approval_id, response)
+            .map(|_| ())
+            .inspect_err(|e| tracing::warn!(error = %e, "Failed to respond to approval"))
+    }
 
-        // Try HTTP approval first (new system)
-        if let Ok(()) = self
-            .respond_to_http_approval(approval_id.clone(), response.clone())
-            .await
+    /// Every approval still waiting for an answer, whichever way it arrived.
+    pub async fn get_pending_approvals(&self) -> Vec<(String, ApprovalRequest)> {
+        self.approvals
+            .list_pending()
+            .into_iter()
+            .map(|record| {
+                (
+                    record.approval_id,
+                    ApprovalRequest {
+                        tool_name: record.tool_name,
+                        input: record.input,
+                        worktree_id: record.worktree_id,
+                        process_id: record.process_id,
+                        timestamp: record.timestamp,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Resolve and validate the MCP server once at startup. `resource_dir` is
+    /// the app's Tauri resource directory, where bundled builds ship the server.
+    pub fn initialize_runtime(
+        &self,
+        resource_dir: Option<PathBuf>,
+    ) -> Result<McpRuntimeInfo, String> {
+        *self.resource_dir.lock().unwrap() = resource_dir;
         {
-            return Ok(());
+            let mut override_path = self.server_path_override.lock().unwrap();
+            if override_path.is_none() {
+                *override_path = std::env::var(MCP_SERVER_PATH_ENV)
+                    .ok()
+                    .filter(|p| !p.trim().is_empty());
+            }
         }
+        self.refresh_runtime()
+    }
 
-        // Fallback to legacy system for tests and backward compatibility
-        eprintln!("🔴 RUST: HTTP approval not found, trying legacy system for ID: {approval_id}");
-        let mut pending = self.pending_approvals.lock().await;
+    /// Re-run resolution and validation, replacing the cached result.
+    pub fn refresh_runtime(&self) -> Result<McpRuntimeInfo, String> {
+        let override_path = self.server_path_override.lock().unwrap().clone();
+        let resource_dir = self.resource_dir.lock().unwrap().clone();
+
+        let result = resolve_mcp_server_path(override_path.as_deref(), resource_dir.as_deref())
+            .and_then(|(path, source)| validate_mcp_runtime(path, source));
+
+        match &result {
+            Ok(info) => tracing::info!(
+                source = ?info.source,
+                server_path = %info.server_path,
+                node_version = %info.node_version,
+                "Using MCP server"
+            ),
+            Err(e) => tracing::warn!(error = %e, "MCP server unavailable"),
+        }
 
-        if let Some(_approval_request) = pending.remove(&approval_id) {
-            eprintln!("🟢 RUST: Found pending approval in legacy system for ID: {approval_id}");
-            eprintln!("🔵 RUST: Legacy response: {response:?}");
-            Ok(())
-        } else {
-            eprintln!("🔴 RUST: Approval request not found in either system for ID: {approval_id}");
-            Err(format!("Approval request not found: {approval_id}"))
+        *self.runtime.lock().unwrap() = Some(result.clone());
+        result
+    }
+
+    /// Use a specific MCP server build instead of the bundled one (for development).
+    pub fn set_server_path_override(&self, path: Option<String>) -> Result<McpRuntimeInfo, String> {
+        *self.server_path_override.lock().unwrap() = path.filter(|p| !p.trim().is_empty());
+        self.refresh_runtime()
+    }
+
+    pub fn runtime_info(&self) -> Result<McpRuntimeInfo, String> {
+        let cached = self.runtime.lock().unwrap().clone();
+        match cached {
+            Some(result) => result,
+            None => self.refresh_runtime(),
         }
     }
 
-    pub async fn respond_to_http_approval(
-        &self,
-        approval_id: String,
-        response: ApprovalResponse,
-    ) -> Result<(), String> {
-        eprintln!("🔵 RUST HTTP: respond_to_http_approval called for ID: {approval_id}");
+    pub fn get_mcp_server_path(&self) -> Result<String, String> {
+        self.runtime_info().map(|info| info.server_path)
+    }
 
-        let mut pending = self.pending_http_approvals.lock().await;
+    pub fn set_log_dir(&self, log_dir: PathBuf) {
+        *self.log_dir.lock().unwrap() = Some(log_dir);
+    }
 
-        if let Some(pending_approval) = pending.remove(&approval_id) {
-            eprintln!("🟢 RUST HTTP: Found pending HTTP approval for ID: {approval_id}");
-            eprintln!("🔵 RUST HTTP: Response: {response:?}");
+    /// Where server logs are written: the app log directory, or a temp
+    /// directory before one is known (e.g. in tests).
+    pub fn log_dir(&self) -> PathBuf {
+        self.log_dir
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("orchestra-logs"))
+    }
 
-            // Send response through oneshot channel (this unblocks the HTTP request)
-            match pending_approval.response_tx.send(response) {
-                Ok(()) => {
-                    eprintln!("✅ RUST HTTP: Successfully sent response to HTTP handler");
-                    Ok(())
-                }
-                Err(_) => {
-                    eprintln!("❌ RUST HTTP: Failed to send response - receiver dropped");
-                    Err("Failed to send response to HTTP handler".to_string())
-                }
-            }
-        } else {
-            eprintln!("🔴 RUST HTTP: HTTP approval request not found for ID: {approval_id}");
-            eprintln!(
-                "🔴 RUST HTTP: Available HTTP approval IDs: {:?}",
-                pending.keys().collect::<Vec<_>>()
-            );
-            Err(format!("HTTP approval request not found: {approval_id}"))
+    /// The last `tail` log entries of a server, including servers that have
+    /// since been stopped.
+    pub fn get_server_logs(&self, server_id: &str, tail: usize) -> Result<Vec<LogEntry>, String> {
+        if server_id.is_empty()
+            || !server_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(format!("Invalid MCP server id: {server_id}"));
         }
+
+        let path = server_log_path(&self.log_dir(), server_id);
+        if !path.exists() {
+            return Err(format!("No logs found for MCP server: {server_id}"));
+        }
+        Ok(read_log_tail(&path, MAX_ROTATED_FILES, tail))
     }
 
-    pub async fn get_pending_approvals(&self) -> Vec<(String, ApprovalRequest)> {
-        let pending = self.pending_approvals.lock().await;
-        pending
-            .iter()
-            .map(|(id, req)| (id.clone(), req.clone()))
-            .collect()
+    pub async fn server_statuses(&self) -> Vec<McpServerStatus> {
+        let servers = self.servers.lock().await;
+        servers.values().map(|server| server.status(None)).collect()
+    }
+
+    pub fn restarts_total(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
     }
 
-    fn get_mcp_server_path(&self) -> Result<String, String> {
-        // Try multiple possible paths for the MCP server
-        let current_dir =
-            std::env::current_dir().map_err(|e| format!("Failed to get current directory: {e}"))?;
-
-        let possible_paths = vec![
-            current_dir.join("mcp-server").join("dist").join("index.js"),
-            current_dir
-                .join("..")
-                .join("mcp-server")
-                .join("dist")
-                .join("index.js"),
-            current_dir
-                .parent()
-                .unwrap_or(&current_dir)
-                .join("mcp-server")
-                .join("dist")
-                .join("index.js"),
-        ];
-
-        for path in &possible_paths {
-            eprintln!("Checking MCP server path: {}", path.display());
-            if path.exists() {
-                eprintln!("Found MCP server at: {}", path.display());
-                return Ok(path.to_string_lossy().to_string());
+    /// One supervisor pass: notice crashed servers, restart those whose
+    /// backoff has elapsed and emit `mcp-server-status` for every change.
+    pub async fn supervise(&self, app_handle: &AppHandle) -> Vec<McpServerStatus> {
+        let now = Instant::now();
+        let mut changes = Vec::new();
+
+        let mut servers = self.servers.lock().await;
+        for server in servers.values_mut() {
+            match server.health.state() {
+                McpServerState::Running => {
+                    if let Some(exit) = server.poll_exit() {
+                        let message = match server.health.on_exit(now, &self.restart_policy) {
+                            McpServerState::Failed => format!(
+                                "MCP server exited ({exit}) and reached the restart limit of {}",
+                                self.restart_policy.max_retries
+                            ),
+                            _ => format!(
+                                "MCP server exited ({exit}), restarting in {}ms",
+                                server
+                                    .health
+                                    .restart_delay(now)
+                                    .unwrap_or_default()
+                                    .as_millis()
+                            ),
+                        };
+                        tracing::warn!(parent: &server.span(), "{message}");
+                        server.log(LogLevel::Error, &message);
+                        changes.push(server.status(Some(message)));
+                    }
+                }
+                McpServerState::Restarting if server.health.restart_due(now) => {
+                    let message = match server.start(app_handle.clone()) {
+                        Ok(()) => {
+                            self.restarts.fetch_add(1, Ordering::Relaxed);
+                            format!(
+                                "MCP server restarted (attempt {})",
+                                server.health.restart_count()
+                            )
+                        }
+                        Err(e) => {
+                            server.health.on_exit(now, &self.restart_policy);
+                            format!("MCP server restart failed: {e}")
+                        }
+                    };
+                    tracing::warn!(parent: &server.span(), "{message}");
+                    server.log(LogLevel::Warn, &message);
+                    changes.push(server.status(Some(message)));
+                }
+                _ => {}
             }
         }
+        drop(servers);
 
-        Err(format!(
-            "MCP server not found. Tried paths: {:?}\nRun 'npm run build' in the mcp-server directory.",
-            possible_paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>()
-        ))
+        for status in &changes {
+            let _ = app_handle.emit("mcp-server-status", status);
+        }
+        changes
     }
 
+    /// Forget servers the supervisor has given up on.
     pub async fn cleanup_dead_servers(&self) {
         let mut servers = self.servers.lock().await;
         let dead_servers: Vec<String> = servers
             .iter_mut()
             .filter_map(|(id, server)| {
-                if !server.is_running() {
+                if server.health.state() == McpServerState::Failed {
                     Some(id.clone())
                 } else {
                     None
@@ -519,7 +1202,9 @@ impl McpManager {
             .collect();
 
         for server_id in dead_servers {
-            servers.remove(&server_id);
+            if let Some(server) = servers.remove(&server_id) {
+                self.release_port(server.config.port);
+            }
         }
     }
 }
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use crate::mcp_manager::{
+        resolve_mcp_server_path, McpManager, McpServerSource, MCP_SERVER_RESOURCE,
+    };
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn create_resource_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "orchestra_mcp_runtime_{name}_{}",
+            std::process::id()
+        ));
+        let server = dir.join(MCP_SERVER_RESOURCE);
+        fs::create_dir_all(server.parent().unwrap()).unwrap();
+        fs::write(&server, "// bundled server").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_resolve_prefers_bundled_resource() {
+        let dir = create_resource_dir("bundled");
+
+        let (path, source) = resolve_mcp_server_path(None, Some(&dir)).unwrap();
+        assert_eq!(source, McpServerSource::Bundled);
+        assert_eq!(path, dir.join(MCP_SERVER_RESOURCE));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_resolve_override_takes_precedence() {
+        let dir = create_resource_dir("override");
+        let override_path = dir.join("custom.js");
+        fs::write(&override_path, "// dev server").unwrap();
+
+        let (path, source) =
+            resolve_mcp_server_path(Some(override_path.to_str().unwrap()), Some(&dir)).unwrap();
+        assert_eq!(source, McpServerSource::Override);
+        assert_eq!(path, override_path);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_resolve_missing_override_is_an_error() {
+        let dir = create_resource_dir("missing_override");
+
+        // A bad override is reported rather than silently falling back
+        let result = resolve_mcp_server_path(Some("/nonexistent/mcp/index.js"), Some(&dir));
+        assert!(result.unwrap_err().contains("/nonexistent/mcp/index.js"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_manager_caches_runtime_result() {
+        let manager = McpManager::new();
+
+        let result =
+            manager.set_server_path_override(Some("/nonexistent/mcp/index.js".to_string()));
+        assert!(result.is_err());
+
+        // The cached failure is returned without re-resolving
+        assert_eq!(manager.runtime_info().unwrap_err(), result.unwrap_err());
+        assert!(manager.get_mcp_server_path().is_err());
+    }
+}
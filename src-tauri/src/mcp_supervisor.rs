@@ -0,0 +1,110 @@
+use crate::claude_errors::RetryPolicy;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How often the supervisor checks on MCP server processes.
+pub const SUPERVISOR_INTERVAL: Duration = Duration::from_secs(2);
+/// A server that stays up this long gets its restart budget back.
+pub const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Backoff used when restarting crashed MCP servers.
+pub fn default_restart_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 5,
+        initial_delay_ms: 500,
+        max_delay_ms: 30_000,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServerState {
+    Running,
+    Restarting,
+    Failed,
+    Stopped,
+}
+
+/// Payload of the `mcp-server-status` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerStatus {
+    pub server_id: String,
+    pub worktree_id: String,
+    pub state: McpServerState,
+    pub restart_count: u32,
+    pub message: Option<String>,
+}
+
+/// Restart bookkeeping for one supervised MCP server.
+#[derive(Debug, Clone)]
+pub struct McpServerHealth {
+    state: McpServerState,
+    restart_count: u32,
+    running_since: Option<Instant>,
+    next_restart_at: Option<Instant>,
+}
+
+impl Default for McpServerHealth {
+    fn default() -> Self {
+        Self {
+            state: McpServerState::Stopped,
+            restart_count: 0,
+            running_since: None,
+            next_restart_at: None,
+        }
+    }
+}
+
+impl McpServerHealth {
+    pub fn state(&self) -> McpServerState {
+        self.state
+    }
+
+    pub fn restart_count(&self) -> u32 {
+        self.restart_count
+    }
+
+    /// Delay until the scheduled restart, if one is pending.
+    pub fn restart_delay(&self, now: Instant) -> Option<Duration> {
+        self.next_restart_at
+            .map(|at| at.saturating_duration_since(now))
+    }
+
+    pub fn on_started(&mut self, now: Instant) {
+        self.state = McpServerState::Running;
+        self.running_since = Some(now);
+        self.next_restart_at = None;
+    }
+
+    /// The server exited (or failed to restart). Schedules a restart with
+    /// backoff, or marks the server failed once the restart budget is spent.
+    pub fn on_exit(&mut self, now: Instant, policy: &RetryPolicy) -> McpServerState {
+        if self
+            .running_since
+            .is_some_and(|since| now.duration_since(since) >= STABLE_AFTER)
+        {
+            self.restart_count = 0;
+        }
+        self.running_since = None;
+
+        if self.restart_count >= policy.max_retries {
+            self.state = McpServerState::Failed;
+            self.next_restart_at = None;
+        } else {
+            self.restart_count += 1;
+            self.state = McpServerState::Restarting;
+            self.next_restart_at = Some(now + policy.delay_for_attempt(self.restart_count));
+        }
+        self.state
+    }
+
+    pub fn restart_due(&self, now: Instant) -> bool {
+        self.state == McpServerState::Restarting && self.next_restart_at.is_some_and(|at| now >= at)
+    }
+
+    pub fn on_stopped(&mut self) {
+        self.state = McpServerState::Stopped;
+        self.running_since = None;
+        self.next_restart_at = None;
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use crate::claude_errors::RetryPolicy;
+    use crate::mcp_supervisor::{
+        default_restart_policy, McpServerHealth, McpServerState, McpServerStatus, STABLE_AFTER,
+    };
+    use std::time::{Duration, Instant};
+
+    fn create_test_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            initial_delay_ms: 100,
+            max_delay_ms: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_default_restart_policy_is_capped() {
+        let policy = default_restart_policy();
+        assert!(policy.max_retries > 0);
+        assert!(policy.delay_for_attempt(20) <= Duration::from_millis(policy.max_delay_ms));
+    }
+
+    #[test]
+    fn test_exit_schedules_restart_with_backoff() {
+        let policy = create_test_policy();
+        let now = Instant::now();
+        let mut health = McpServerHealth::default();
+        health.on_started(now);
+
+        assert_eq!(health.on_exit(now, &policy), McpServerState::Restarting);
+        assert_eq!(health.restart_count(), 1);
+        assert_eq!(health.restart_delay(now), Some(Duration::from_millis(100)));
+        assert!(!health.restart_due(now));
+        assert!(health.restart_due(now + Duration::from_millis(100)));
+
+        // A failed restart attempt backs off further
+        assert_eq!(health.on_exit(now, &policy), McpServerState::Restarting);
+        assert_eq!(health.restart_delay(now), Some(Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn test_restart_cap_marks_server_failed() {
+        let policy = create_test_policy();
+        let now = Instant::now();
+        let mut health = McpServerHealth::default();
+        health.on_started(now);
+
+        health.on_exit(now, &policy);
+        health.on_started(now);
+        health.on_exit(now, &policy);
+        health.on_started(now);
+
+        assert_eq!(health.on_exit(now, &policy), McpServerState::Failed);
+        assert_eq!(health.restart_count(), 2);
+        assert!(!health.restart_due(now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_stable_server_gets_restart_budget_back() {
+        let policy = create_test_policy();
+        let start = Instant::now();
+        let mut health = McpServerHealth::default();
+        health.on_started(start);
+        health.on_exit(start, &policy);
+        health.on_started(start);
+        health.on_exit(start, &policy);
+        health.on_started(start);
+
+        let later = start + STABLE_AFTER;
+        assert_eq!(health.on_exit(later, &policy), McpServerState::Restarting);
+        assert_eq!(health.restart_count(), 1);
+    }
+
+    #[test]
+    fn test_stopped_server_is_not_restarted() {
+        let now = Instant::now();
+        let mut health = McpServerHealth::default();
+        health.on_started(now);
+        health.on_stopped();
+
+        assert_eq!(health.state(), McpServerState::Stopped);
+        assert!(!health.restart_due(now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        let status = McpServerStatus {
+            server_id: "server-1".to_string(),
+            worktree_id: "wt-1".to_string(),
+            state: McpServerState::Restarting,
+            restart_count: 1,
+            message: None,
+        };
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["state"], "restarting");
+        assert_eq!(json["restart_count"], 1);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Bucket bounds, in seconds, for how long a person takes to answer an approval.
+pub const APPROVAL_LATENCY_BUCKETS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+/// Bucket bounds, in seconds, for how long an agent process runs.
+pub const PROCESS_DURATION_BUCKETS: &[f64] =
+    &[10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+/// Process statuses always exported, so their series exist before first use.
+pub const PROCESS_STATUSES: &[&str] = &[
+    "running",
+    "awaiting_approval",
+    "idle",
+    "completed",
+    "stopped",
+    "error",
+];
+
+/// A Prometheus histogram with fixed bucket bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bucket, not cumulative; the last is above every bound.
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// Lifetime approval outcomes, kept by the approval broker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalStats {
+    /// Resolved requests by outcome: approved, denied, expired or cancelled.
+    pub resolved: BTreeMap<&'static str, u64>,
+    /// Time from request to answer, for requests that waited on a person.
+    pub latency: Histogram,
+}
+
+impl Default for ApprovalStats {
+    fn default() -> Self {
+        Self {
+            resolved: ["approved", "denied", "expired", "cancelled"]
+                .into_iter()
+                .map(|outcome| (outcome, 0))
+                .collect(),
+            latency: Histogram::new(APPROVAL_LATENCY_BUCKETS),
+        }
+    }
+}
+
+/// Totals of finished agent runs, kept in `AppState`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunMetrics {
+    /// API cost reported by the agent, in USD, by repository path.
+    pub cost_usd_by_repo: BTreeMap<String, f64>,
+    /// Process run time by the status the process ended in.
+    pub durations: BTreeMap<String, Histogram>,
+}
+
+impl RunMetrics {
+    pub fn add_cost(&mut self, repo: &str, cost_usd: f64) {
+        *self.cost_usd_by_repo.entry(repo.to_string()).or_default() += cost_usd;
+    }
+
+    pub fn observe_duration(&mut self, status: &str, seconds: f64) {
+        self.durations
+            .entry(status.to_string())
+            .or_insert_with(|| Histogram::new(PROCESS_DURATION_BUCKETS))
+            .observe(seconds);
+    }
+}
+
+/// Everything `/metrics` reports, gathered from the app state.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub processes_by_status: BTreeMap<String, u64>,
+    pub approvals_pending: u64,
+    pub approvals: ApprovalStats,
+    pub mcp_server_restarts: u64,
+    pub runs: RunMetrics,
+}
+
+/// Render a snapshot in the Prometheus text exposition format.
+pub fn render(snapshot: &MetricsSnapshot) -> String {
+    let mut text = MetricsText::default();
+
+    text.family("orchestra_processes", "gauge", "Agent processes by status.");
+    let mut statuses: BTreeMap<&str, u64> =
+        PROCESS_STATUSES.iter().map(|status| (*status, 0)).collect();
+    for (status, count) in &snapshot.processes_by_status {
+        *statuses.entry(status).or_default() += count;
+    }
+    for (status, count) in statuses {
+        text.sample("orchestra_processes", &[("status", status)], count as f64);
+    }
+
+    text.family(
+        "orchestra_approvals_pending",
+        "gauge",
+        "Tool approval requests waiting for an answer.",
+    );
+    text.sample(
+        "orchestra_approvals_pending",
+        &[],
+        snapshot.approvals_pending as f64,
+    );
+
+    text.family(
+        "orchestra_approvals_total",
+        "counter",
+        "Resolved tool approval requests by outcome.",
+    );
+    for (outcome, count) in &snapshot.approvals.resolved {
+        text.sample(
+            "orchestra_approvals_total",
+            &[("outcome", outcome)],
+            *count as f64,
+        );
+    }
+
+    text.family(
+        "orchestra_approval_latency_seconds",
+        "histogram",
+        "Time from a tool approval request to its answer.",
+    );
+    text.histogram(
+        "orchestra_approval_latency_seconds",
+        &[],
+        &snapshot.approvals.latency,
+    );
+
+    text.family(
+        "orchestra_mcp_server_restarts_total",
+        "counter",
+        "MCP server restarts after a crash.",
+    );
+    text.sample(
+        "orchestra_mcp_server_restarts_total",
+        &[],
+        snapshot.mcp_server_restarts as f64,
+    );
+
+    text.family(
+        "orchestra_cost_usd_total",
+        "counter",
+        "API cost reported by agent runs, by repository.",
+    );
+    for (repo, cost) in &snapshot.runs.cost_usd_by_repo {
+        text.sample("orchestra_cost_usd_total", &[("repo", repo)], *cost);
+    }
+
+    text.family(
+        "orchestra_process_duration_seconds",
+        "histogram",
+        "Run time of finished agent processes, by final status.",
+    );
+    for (status, histogram) in &snapshot.runs.durations {
+        text.histogram(
+            "orchestra_process_duration_seconds",
+            &[("status", status)],
+            histogram,
+        );
+    }
+
+    text.text
+}
+
+#[derive(Default)]
+struct MetricsText {
+    text: String,
+}
+
+impl MetricsText {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.text, "# HELP {name} {help}");
+        let _ = writeln!(self.text, "# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+            .collect();
+        let _ = match labels.is_empty() {
+            true => writeln!(self.text, "{name} {value}"),
+            false => writeln!(self.text, "{name}{{{}}} {value}", labels.join(",")),
+        };
+    }
+
+    fn histogram(&mut self, name: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+        let bucket = format!("{name}_bucket");
+        let mut cumulative = 0;
+        for (index, count) in histogram.counts.iter().enumerate() {
+            cumulative += count;
+            let bound = histogram
+                .bounds
+                .get(index)
+                .map(|bound| bound.to_string())
+                .unwrap_or_else(|| "+Inf".to_string());
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &bound));
+            self.sample(&bucket, &bucket_labels, cumulative as f64);
+        }
+        self.sample(&format!("{name}_sum"), labels, histogram.sum);
+        self.sample(&format!("{name}_count"), labels, histogram.count as f64);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
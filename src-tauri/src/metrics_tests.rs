@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use crate::approval_broker::{ApprovalBroker, ApprovalRecord};
+    use crate::mcp_manager::{ApprovalBehavior, ApprovalResponse};
+    use crate::metrics::{render, MetricsSnapshot, RunMetrics};
+
+    fn record(approval_id: &str, timestamp: u64) -> ApprovalRecord {
+        ApprovalRecord::new(
+            approval_id.to_string(),
+            "Bash".to_string(),
+            serde_json::json!({"command": "ls"}),
+            "wt-1".to_string(),
+            timestamp,
+        )
+    }
+
+    fn response(behavior: ApprovalBehavior) -> ApprovalResponse {
+        ApprovalResponse {
+            behavior,
+            message: None,
+            updated_input: None,
+            grant: None,
+        }
+    }
+
+    #[test]
+    fn test_histogram_renders_cumulative_buckets() {
+        let mut runs = RunMetrics::default();
+        for seconds in [5.0, 45.0, 45.0, 7200.0] {
+            runs.observe_duration("completed", seconds);
+        }
+        let text = render(&MetricsSnapshot {
+            runs,
+            ..Default::default()
+        });
+
+        assert!(text.contains(
+            "orchestra_process_duration_seconds_bucket{status=\"completed\",le=\"10\"} 1\n"
+        ));
+        assert!(text.contains(
+            "orchestra_process_duration_seconds_bucket{status=\"completed\",le=\"60\"} 3\n"
+        ));
+        assert!(text.contains(
+            "orchestra_process_duration_seconds_bucket{status=\"completed\",le=\"3600\"} 3\n"
+        ));
+        assert!(text.contains(
+            "orchestra_process_duration_seconds_bucket{status=\"completed\",le=\"+Inf\"} 4\n"
+        ));
+        assert!(
+            text.contains("orchestra_process_duration_seconds_sum{status=\"completed\"} 7295\n")
+        );
+        assert!(text.contains("orchestra_process_duration_seconds_count{status=\"completed\"} 4\n"));
+    }
+
+    #[test]
+    fn test_render_exports_every_family() {
+        let mut snapshot = MetricsSnapshot::default();
+        snapshot
+            .processes_by_status
+            .insert("running".to_string(), 2);
+        snapshot.approvals_pending = 1;
+        snapshot.mcp_server_restarts = 3;
+        snapshot.runs.add_cost("/repos/app", 0.25);
+        snapshot.runs.add_cost("/repos/app", 0.5);
+        snapshot.runs.add_cost("/repos/\"quoted\"", 1.0);
+        let text = render(&snapshot);
+
+        for family in [
+            "orchestra_processes gauge",
+            "orchestra_approvals_pending gauge",
+            "orchestra_approvals_total counter",
+            "orchestra_approval_latency_seconds histogram",
+            "orchestra_mcp_server_restarts_total counter",
+            "orchestra_cost_usd_total counter",
+            "orchestra_process_duration_seconds histogram",
+        ] {
+            assert!(text.contains(&format!("# TYPE {family}\n")), "{family}");
+        }
+        assert!(text.contains("orchestra_processes{status=\"running\"} 2\n"));
+        // Statuses without processes are still exported
+        assert!(text.contains("orchestra_processes{status=\"awaiting_approval\"} 0\n"));
+        assert!(text.contains("orchestra_approvals_pending 1\n"));
+        assert!(text.contains("orchestra_approvals_total{outcome=\"denied\"} 0\n"));
+        assert!(text.contains("orchestra_approval_latency_seconds_count 0\n"));
+        assert!(text.contains("orchestra_mcp_server_restarts_total 3\n"));
+        assert!(text.contains("orchestra_cost_usd_total{repo=\"/repos/app\"} 0.75\n"));
+        assert!(text.contains("orchestra_cost_usd_total{repo=\"/repos/\\\"quoted\\\"\"} 1\n"));
+    }
+
+    #[test]
+    fn test_broker_counts_outcomes_and_latency() {
+        let broker = ApprovalBroker::default();
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+
+        // Answered after waiting 20 seconds
+        let _receiver = broker.submit(record("a1", now - 20_000)).unwrap();
+        broker
+            .respond("a1", response(ApprovalBehavior::Allow))
+            .unwrap();
+        let _receiver = broker.submit(record("a2", now)).unwrap();
+        broker
+            .respond("a2", response(ApprovalBehavior::Deny))
+            .unwrap();
+        let _receiver = broker.submit(record("a3", now)).unwrap();
+        broker.expire("a3");
+        // Decided by policy without waiting on anyone
+        broker.record_denied(record("a4", now), "protected".to_string());
+
+        let stats = broker.stats();
+        assert_eq!(stats.resolved["approved"], 1);
+        assert_eq!(stats.resolved["denied"], 2);
+        assert_eq!(stats.resolved["expired"], 1);
+        assert_eq!(stats.resolved["cancelled"], 0);
+        assert_eq!(stats.latency.count(), 3);
+
+        let text = render(&MetricsSnapshot {
+            approvals: stats,
+            ..Default::default()
+        });
+        assert!(text.contains("orchestra_approval_latency_seconds_bucket{le=\"15\"} 2\n"));
+        assert!(text.contains("orchestra_approval_latency_seconds_bucket{le=\"30\"} 3\n"));
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+use std::net::TcpListener;
+use std::ops::RangeInclusive;
+
+/// Ports handed out to MCP servers running in SSE mode.
+pub const MCP_PORT_RANGE: RangeInclusive<u16> = 38100..=38199;
+
+/// Hands out local ports from a fixed range, skipping ports that are already
+/// allocated or that another process is listening on.
+#[derive(Debug)]
+pub struct PortAllocator {
+    range: RangeInclusive<u16>,
+    allocated: HashSet<u16>,
+}
+
+impl PortAllocator {
+    pub fn new(range: RangeInclusive<u16>) -> Self {
+        Self {
+            range,
+            allocated: HashSet::new(),
+        }
+    }
+
+    pub fn allocate(&mut self) -> Result<u16, String> {
+        let port = self
+            .range
+            .clone()
+            .find(|port| !self.allocated.contains(port) && port_is_free(*port))
+            .ok_or_else(|| {
+                format!(
+                    "No free port available in {}-{}",
+                    self.range.start(),
+                    self.range.end()
+                )
+            })?;
+        self.allocated.insert(port);
+        Ok(port)
+    }
+
+    pub fn release(&mut self, port: u16) {
+        self.allocated.remove(&port);
+    }
+}
+
+impl Default for PortAllocator {
+    fn default() -> Self {
+        Self::new(MCP_PORT_RANGE)
+    }
+}
+
+pub fn port_is_free(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
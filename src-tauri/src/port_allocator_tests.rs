@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use crate::mcp_config::{McpConfigBuilder, ORCHESTRA_SERVER_NAME};
+    use crate::mcp_manager::{McpManager, McpServerConfig, McpTransport};
+    use crate::port_allocator::{port_is_free, PortAllocator};
+    use std::net::TcpListener;
+
+    fn find_free_range(len: u16) -> (u16, u16) {
+        // Let the OS pick a free port and use the range after it
+        let port = TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let start = port.min(u16::MAX - len);
+        (start, start + len - 1)
+    }
+
+    #[test]
+    fn test_allocations_do_not_collide() {
+        let (start, end) = find_free_range(10);
+        let mut allocator = PortAllocator::new(start..=end);
+
+        let first = allocator.allocate().unwrap();
+        let second = allocator.allocate().unwrap();
+        assert_ne!(first, second);
+
+        // Released ports can be handed out again
+        allocator.release(second);
+        assert_eq!(allocator.allocate().unwrap(), second);
+    }
+
+    #[test]
+    fn test_ports_in_use_are_skipped() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let busy = listener.local_addr().unwrap().port();
+        assert!(!port_is_free(busy));
+
+        let mut allocator = PortAllocator::new(busy..=busy);
+        assert!(allocator.allocate().is_err());
+    }
+
+    #[test]
+    fn test_sse_config_entry() {
+        let config = McpServerConfig {
+            server_id: "server-1".to_string(),
+            worktree_id: "wt-1".to_string(),
+            worktree_path: "/tmp/wt".to_string(),
+            server_path: "/srv/index.js".to_string(),
+            port: Some(38100),
+        };
+        let url = config.sse_url().unwrap();
+        assert_eq!(url, "http://127.0.0.1:38100/sse");
+
+        let mcp_config = McpConfigBuilder::new()
+            .orchestra_sse_server(&url, "proc-1")
+            .build();
+        let entry = &mcp_config["mcpServers"][ORCHESTRA_SERVER_NAME];
+        assert_eq!(entry["type"], "sse");
+        assert_eq!(entry["url"], format!("{url}?processId=proc-1"));
+
+        let stdio_config = McpConfigBuilder::new()
+            .orchestra_server("/srv/index.js", "/tmp/wt", "wt-1", "proc-1")
+            .build();
+        assert_eq!(
+            stdio_config["mcpServers"][ORCHESTRA_SERVER_NAME]["type"],
+            "stdio"
+        );
+    }
+
+    #[test]
+    fn test_manager_transport_setting() {
+        let manager = McpManager::new();
+        assert_eq!(manager.transport(), McpTransport::Stdio);
+
+        manager.set_transport(McpTransport::Sse);
+        assert_eq!(manager.transport(), McpTransport::Sse);
+        assert_eq!(serde_json::to_value(McpTransport::Sse).unwrap(), "sse");
+    }
+}
@@ -0,0 +1,203 @@
+use crate::resource_limits::{is_alive, read_stat, resident_kb, tree_pids, CLOCK_TICKS_PER_SECOND};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How often running agent processes are sampled.
+pub const METRICS_INTERVAL: Duration = Duration::from_secs(2);
+/// Samples kept per process for sparklines: two minutes at the default interval.
+pub const METRICS_HISTORY_LIMIT: usize = 60;
+
+/// One sample of an agent process tree, sent in `process-metrics` events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessMetrics {
+    pub process_id: String,
+    pub worktree_id: String,
+    pub timestamp: String,
+    /// CPU used since the previous sample; 100 is one full core.
+    pub cpu_percent: f64,
+    pub rss_mb: f64,
+    /// Processes in the tree besides the agent itself.
+    pub child_processes: u64,
+    /// TCP ports the tree is listening on.
+    pub open_ports: Vec<u16>,
+}
+
+/// A running agent process to sample.
+#[derive(Debug, Clone)]
+pub struct SampleTarget {
+    pub process_id: String,
+    pub worktree_id: String,
+    pub pid: u32,
+    /// The run's cgroup, which also holds processes that left the tree.
+    pub cgroup: Option<String>,
+}
+
+/// CPU percentage from the ticks used over `elapsed`. A tree whose processes
+/// exited can report fewer ticks than before, which counts as idle.
+pub fn cpu_percent(ticks_before: u64, ticks_after: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    let seconds = ticks_after.saturating_sub(ticks_before) as f64 / CLOCK_TICKS_PER_SECOND;
+    seconds / elapsed.as_secs_f64() * 100.0
+}
+
+/// Keeps the previous CPU reading and the recent samples of each process.
+#[derive(Default)]
+pub struct MetricsSampler {
+    /// When each process was last sampled, its pid then and its CPU ticks.
+    previous: HashMap<String, (Instant, u32, u64)>,
+    history: HashMap<String, VecDeque<ProcessMetrics>>,
+}
+
+impl MetricsSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sample every target and forget processes that are no longer running.
+    /// An exited agent's pid may already belong to another process, so its
+    /// history is dropped rather than extended.
+    pub fn sample(&mut self, targets: &[SampleTarget]) -> Vec<ProcessMetrics> {
+        let targets: Vec<&SampleTarget> = targets
+            .iter()
+            .filter(|target| is_alive(target.pid))
+            .collect();
+        let running = |process_id: &String| {
+            targets
+                .iter()
+                .any(|target| &target.process_id == process_id)
+        };
+        self.previous.retain(|process_id, _| running(process_id));
+        self.history.retain(|process_id, _| running(process_id));
+
+        let now = Instant::now();
+        let mut samples = Vec::new();
+        for target in targets {
+            let pids = tree_pids(target.pid, target.cgroup.as_deref());
+            if pids.is_empty() {
+                continue;
+            }
+
+            let mut ticks = 0;
+            let mut rss_kb = 0;
+            for &pid in &pids {
+                if let Some(stat) = read_stat(pid) {
+                    ticks += stat.cpu_ticks;
+                    rss_kb += resident_kb(pid);
+                }
+            }
+            // A retry starts a new process, so its ticks start from zero again
+            let cpu = match self.previous.get(&target.process_id) {
+                Some(&(at, pid, before)) if pid == target.pid => {
+                    cpu_percent(before, ticks, now.duration_since(at))
+                }
+                _ => 0.0,
+            };
+            self.previous
+                .insert(target.process_id.clone(), (now, target.pid, ticks));
+
+            let metrics = ProcessMetrics {
+                process_id: target.process_id.clone(),
+                worktree_id: target.worktree_id.clone(),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                cpu_percent: cpu,
+                rss_mb: rss_kb as f64 / 1024.0,
+                child_processes: pids.len().saturating_sub(1) as u64,
+                open_ports: listening_ports(&pids),
+            };
+            self.record(metrics.clone());
+            samples.push(metrics);
+        }
+        samples
+    }
+
+    pub fn record(&mut self, metrics: ProcessMetrics) {
+        let history = self.history.entry(metrics.process_id.clone()).or_default();
+        history.push_back(metrics);
+        while history.len() > METRICS_HISTORY_LIMIT {
+            history.pop_front();
+        }
+    }
+
+    /// Recent samples of a process, oldest first.
+    pub fn history(&self, process_id: &str) -> Vec<ProcessMetrics> {
+        self.history
+            .get(process_id)
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Socket inodes and local ports of the listening sockets in a
+/// `/proc/net/tcp` or `/proc/net/tcp6` table.
+pub fn parse_listening_sockets(table: &str) -> Vec<(u64, u16)> {
+    const LISTEN: &str = "0A";
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(3) != Some(&LISTEN) {
+                return None;
+            }
+            let (_, port) = fields.get(1)?.rsplit_once(':')?;
+            let port = u16::from_str_radix(port, 16).ok()?;
+            let inode = fields.get(9)?.parse().ok()?;
+            Some((inode, port))
+        })
+        .collect()
+}
+
+/// Ports that the processes in `pids` are listening on. A sandboxed agent
+/// has a network namespace of its own, so every namespace in the tree is read.
+fn listening_ports(pids: &[u32]) -> Vec<u16> {
+    let mut namespaces = Vec::new();
+    let mut sockets = Vec::new();
+    for pid in pids {
+        let Ok(namespace) = std::fs::read_link(format!("/proc/{pid}/ns/net")) else {
+            continue;
+        };
+        if namespaces.contains(&namespace) {
+            continue;
+        }
+        namespaces.push(namespace);
+        for table in ["tcp", "tcp6"] {
+            if let Ok(table) = std::fs::read_to_string(format!("/proc/{pid}/net/{table}")) {
+                sockets.extend(parse_listening_sockets(&table));
+            }
+        }
+    }
+    if sockets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ports = Vec::new();
+    for pid in pids {
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{pid}/fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|target| target.strip_prefix("socket:["))
+                .and_then(|inode| inode.strip_suffix(']'))
+                .and_then(|inode| inode.parse::<u64>().ok());
+            if let Some(inode) = inode {
+                ports.extend(
+                    sockets
+                        .iter()
+                        .filter(|(socket, _)| *socket == inode)
+                        .map(|(_, port)| *port),
+                );
+            }
+        }
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    ports
+}
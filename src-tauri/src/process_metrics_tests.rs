@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use crate::process_metrics::{
+        cpu_percent, parse_listening_sockets, MetricsSampler, ProcessMetrics, SampleTarget,
+        METRICS_HISTORY_LIMIT,
+    };
+    use std::net::TcpListener;
+    use std::process::Command;
+    use std::time::Duration;
+
+    fn target(process_id: &str, pid: u32) -> SampleTarget {
+        SampleTarget {
+            process_id: process_id.to_string(),
+            worktree_id: "wt-1".to_string(),
+            pid,
+            cgroup: None,
+        }
+    }
+
+    #[test]
+    fn test_cpu_percent() {
+        // 150 ticks over 1.5s is one full core
+        assert_eq!(cpu_percent(100, 250, Duration::from_millis(1500)), 100.0);
+        assert_eq!(cpu_percent(0, 50, Duration::from_secs(2)), 25.0);
+        assert_eq!(cpu_percent(250, 100, Duration::from_secs(1)), 0.0);
+        assert_eq!(cpu_percent(0, 100, Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn test_parse_listening_sockets() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41235 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:9C40 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 41240 1 0000000000000000 20 4 30 10 -1
+   2: 00000000:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41300 1 0000000000000000 100 0 0 10 0";
+        assert_eq!(
+            parse_listening_sockets(table),
+            [(41235, 8080), (41300, 3000)]
+        );
+        assert!(parse_listening_sockets("").is_empty());
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut sampler = MetricsSampler::new();
+        for index in 0..METRICS_HISTORY_LIMIT + 5 {
+            sampler.record(ProcessMetrics {
+                process_id: "p1".to_string(),
+                worktree_id: "wt-1".to_string(),
+                timestamp: index.to_string(),
+                cpu_percent: 0.0,
+                rss_mb: 0.0,
+                child_processes: 0,
+                open_ports: Vec::new(),
+            });
+        }
+        let history = sampler.history("p1");
+        assert_eq!(history.len(), METRICS_HISTORY_LIMIT);
+        assert_eq!(history[0].timestamp, "5");
+        assert!(sampler.history("p2").is_empty());
+    }
+
+    #[test]
+    fn test_sample_process_tree() {
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 5 & sleep 5 & wait"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let mut sampler = MetricsSampler::new();
+        let samples = sampler.sample(&[target("p1", child.id()), target("gone", u32::MAX)]);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].process_id, "p1");
+        assert_eq!(samples[0].child_processes, 2);
+        assert_eq!(samples[0].cpu_percent, 0.0);
+        assert!(samples[0].rss_mb > 0.0);
+        assert!(samples[0].open_ports.is_empty());
+
+        sampler.sample(&[target("p1", child.id())]);
+        assert_eq!(sampler.history("p1").len(), 2);
+
+        // Processes that stopped running are forgotten
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(sampler.sample(&[]).is_empty());
+        assert!(sampler.history("p1").is_empty());
+    }
+
+    #[test]
+    fn test_exited_processes_are_not_sampled() {
+        let mut child = Command::new("sh").args(["-c", "sleep 5"]).spawn().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let mut sampler = MetricsSampler::new();
+        assert_eq!(sampler.sample(&[target("p1", child.id())]).len(), 1);
+
+        // Still listed as running, but exited and not yet reaped
+        child.kill().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(sampler.sample(&[target("p1", child.id())]).is_empty());
+        assert!(sampler.history("p1").is_empty());
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_sample_reports_listening_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut sampler = MetricsSampler::new();
+        let samples = sampler.sample(&[target("self", std::process::id())]);
+        assert!(samples[0].open_ports.contains(&port), "{samples:?}");
+    }
+}
@@ -0,0 +1,355 @@
+use crate::approval_context::normalize;
+use crate::command_risk::command_words;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Globs of files agents may not change, configured per repo.
+///
+/// Patterns follow `.gitignore` rules: a pattern without a `/` matches a file
+/// or directory name at any depth, one with a `/` is matched from the repo
+/// root, `*` and `?` stay within a path segment, `**` spans segments, and a
+/// pattern naming a directory covers everything inside it.
+/// Saved to `path` once loaded, so the patterns outlive the app.
+#[derive(Debug, Default)]
+pub struct ProtectedPathStore {
+    repos: HashMap<String, Vec<String>>,
+    path: Option<PathBuf>,
+}
+
+impl ProtectedPathStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load saved patterns from `path` and save future changes there.
+    pub fn load(&mut self, path: PathBuf) -> Result<(), String> {
+        self.repos = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+        self.path = Some(path);
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        let repos: BTreeMap<&String, &Vec<String>> = self.repos.iter().collect();
+        let content = serde_json::to_string_pretty(&repos).map_err(|e| e.to_string())?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to save protected paths to {}: {e}", path.display()))
+    }
+
+    pub fn patterns(&self, repo_path: &str) -> Vec<String> {
+        self.repos.get(repo_path).cloned().unwrap_or_default()
+    }
+
+    pub fn set_patterns(&mut self, repo_path: String, patterns: Vec<String>) -> Result<(), String> {
+        let mut cleaned: Vec<String> = Vec::new();
+        for pattern in patterns {
+            let pattern = pattern.trim();
+            if pattern.is_empty() {
+                return Err("Protected path pattern cannot be empty".to_string());
+            }
+            if pattern.split('/').any(|segment| segment == "..") {
+                return Err(format!(
+                    "Protected path pattern cannot leave the repo: {pattern}"
+                ));
+            }
+            if !cleaned.iter().any(|existing| existing == pattern) {
+                cleaned.push(pattern.to_string());
+            }
+        }
+
+        let previous = match cleaned.is_empty() {
+            true => self.repos.remove(&repo_path),
+            false => self.repos.insert(repo_path.clone(), cleaned),
+        };
+        // Keep what is in effect in line with what is saved
+        if let Err(e) = self.save() {
+            match previous {
+                Some(previous) => self.repos.insert(repo_path, previous),
+                None => self.repos.remove(&repo_path),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// A repo-relative path and the protected pattern it matched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectedPathMatch {
+    pub path: String,
+    pub pattern: String,
+}
+
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    let path_segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    let pattern = pattern.trim().trim_end_matches('/');
+
+    // A bare name matches at any depth
+    if !pattern.contains('/') {
+        return path_segments
+            .iter()
+            .any(|segment| segment_matches(pattern, segment));
+    }
+
+    let pattern_segments: Vec<&str> = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    // Matching a directory protects everything inside it
+    (1..=path_segments.len()).any(|len| segments_match(&pattern_segments, &path_segments[..len]))
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => path.split_first().is_some_and(|(segment, path_rest)| {
+            segment_matches(first, segment) && segments_match(rest, path_rest)
+        }),
+    }
+}
+
+/// `*` and `?` wildcard match of a single path segment.
+fn segment_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was and how much text it has taken so far
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The first pattern matching a repo-relative path.
+pub fn protected_match(patterns: &[String], path: &str) -> Option<ProtectedPathMatch> {
+    patterns
+        .iter()
+        .find(|pattern| glob_matches(pattern, path))
+        .map(|pattern| ProtectedPathMatch {
+            path: path.to_string(),
+            pattern: pattern.clone(),
+        })
+}
+
+/// Protected paths a tool request would touch. `roots` are the checkouts
+/// the paths may be under, worktree first; relative paths are resolved
+/// against the worktree, where Claude runs.
+///
+/// Every path-like word of a `Bash` command is checked, so commands that
+/// only read a protected file are denied too.
+pub fn protected_targets(
+    tool_name: &str,
+    input: &serde_json::Value,
+    patterns: &[String],
+    roots: &[&str],
+) -> Vec<ProtectedPathMatch> {
+    let Some(cwd) = roots.first() else {
+        return Vec::new();
+    };
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+
+    let field = |name: &str| {
+        input
+            .get(name)
+            .and_then(|value| value.as_str())
+            .map(|value| vec![value.to_string()])
+            .unwrap_or_default()
+    };
+    let candidates = match tool_name {
+        "Write" | "Edit" | "MultiEdit" => field("file_path"),
+        "NotebookEdit" => field("notebook_path"),
+        "Bash" => field("command")
+            .iter()
+            .flat_map(|command| command_words(command))
+            .filter_map(|word| {
+                // `--output=path` style options
+                let word = match word.split_once('=') {
+                    Some((name, value)) if name.starts_with('-') => value.to_string(),
+                    _ if word.starts_with('-') => return None,
+                    _ => word,
+                };
+                (!word.is_empty()).then_some(word)
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut matches: Vec<ProtectedPathMatch> = Vec::new();
+    for candidate in candidates {
+        let path = normalize(&Path::new(cwd).join(&candidate));
+        let relative = roots.iter().find_map(|root| {
+            path.strip_prefix(normalize(Path::new(root)))
+                .ok()
+                .map(|relative| relative.to_string_lossy().to_string())
+        });
+        if let Some(found) = relative.and_then(|relative| protected_match(patterns, &relative)) {
+            if !matches.contains(&found) {
+                matches.push(found);
+            }
+        }
+    }
+    matches
+}
+
+/// The message Claude gets when a request is denied for touching protected paths.
+pub fn denial_message(matches: &[ProtectedPathMatch]) -> String {
+    let paths: Vec<String> = matches
+        .iter()
+        .map(|found| format!("{} (matches '{}')", found.path, found.pattern))
+        .collect();
+    format!(
+        "Denied automatically: this repo protects {} from changes by agents. Do not retry; ask the user to make this change instead.",
+        paths.join(", ")
+    )
+}
+
+/// The state of a worktree when a run starts, so that files it already had
+/// changed are not blamed on the agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorktreeSnapshot {
+    head: Option<String>,
+    /// Changed files and a hash of their content (`None` when deleted).
+    changed: HashMap<String, Option<u64>>,
+}
+
+impl WorktreeSnapshot {
+    pub fn capture(worktree_path: &str) -> Self {
+        let head = git_output(worktree_path, &["rev-parse", "HEAD"])
+            .ok()
+            .map(|output| output.trim().to_string())
+            .filter(|head| !head.is_empty());
+        let changed = changed_files(worktree_path, head.as_deref())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|path| {
+                let hash = content_hash(worktree_path, &path);
+                (path, hash)
+            })
+            .collect();
+        Self { head, changed }
+    }
+
+    /// Protected files changed since the snapshot, committed or not.
+    pub fn protected_changes(
+        &self,
+        worktree_path: &str,
+        patterns: &[String],
+    ) -> Result<Vec<ProtectedPathMatch>, String> {
+        if patterns.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(changed_files(worktree_path, self.head.as_deref())?
+            .into_iter()
+            .filter(|path| {
+                self.changed
+                    .get(path)
+                    .is_none_or(|hash| *hash != content_hash(worktree_path, path))
+            })
+            .filter_map(|path| protected_match(patterns, &path))
+            .collect())
+    }
+
+    /// Files whose content differs between `before` and this snapshot,
+    /// including files that were already changed in both.
+    pub fn content_changes_since(&self, before: &WorktreeSnapshot) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .changed
+            .iter()
+            .filter(|(path, hash)| before.changed.get(*path) != Some(*hash))
+            .map(|(path, _)| path.clone())
+            .chain(
+                before
+                    .changed
+                    .keys()
+                    .filter(|path| !self.changed.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+/// Files that differ from `since` (or are untracked), relative to the worktree.
+fn changed_files(worktree_path: &str, since: Option<&str>) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    if let Some(since) = since {
+        let diff = git_output(
+            worktree_path,
+            &["diff", "--name-only", "--no-renames", "-z", since],
+        )?;
+        files.extend(diff.split('\0').map(str::to_string));
+    }
+    let untracked = git_output(
+        worktree_path,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )?;
+    files.extend(untracked.split('\0').map(str::to_string));
+
+    files.retain(|file| !file.is_empty());
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn content_hash(worktree_path: &str, path: &str) -> Option<u64> {
+    let content = std::fs::read(Path::new(worktree_path).join(path)).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+pub(crate) fn git_output(worktree_path: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(worktree_path)
+        .output()
+        .map_err(|e| format!("Failed to run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
@@ -0,0 +1,110 @@
+use crate::command_risk::command_is_read_only;
+use crate::protected_paths::{git_output, WorktreeSnapshot};
+
+/// Tools a read-only run may use without asking.
+pub const READ_ONLY_TOOLS: &[&str] = &[
+    "Read",
+    "Glob",
+    "Grep",
+    "LS",
+    "NotebookRead",
+    "WebFetch",
+    "WebSearch",
+    "TodoWrite",
+];
+/// Tools that change files, never available to a read-only run.
+pub const WRITE_TOOLS: &[&str] = &["Write", "Edit", "MultiEdit", "NotebookEdit"];
+
+/// Why a read-only run may not use a tool this way, or `None` if it may.
+pub fn read_only_denial(tool_name: &str, input: &serde_json::Value) -> Option<String> {
+    if WRITE_TOOLS.contains(&tool_name) {
+        return Some(format!(
+            "Denied automatically: this is a read-only run, so {tool_name} is not available. Report what should change instead of changing it."
+        ));
+    }
+    if tool_name == "Bash" {
+        let command = input.get("command").and_then(|c| c.as_str()).unwrap_or("");
+        if !command_is_read_only(command) {
+            return Some(
+                "Denied automatically: this is a read-only run and this command may change files. Only commands that read, such as ls, cat, grep or git log, are allowed."
+                    .to_string(),
+            );
+        }
+    }
+    None
+}
+
+/// `git status --porcelain`, `HEAD` and the content of changed files in a
+/// worktree, compared before and after a read-only run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorktreeStatus {
+    head: Option<String>,
+    porcelain: Vec<String>,
+    snapshot: WorktreeSnapshot,
+}
+
+impl WorktreeStatus {
+    pub fn capture(worktree_path: &str) -> Result<Self, String> {
+        let head = git_output(worktree_path, &["rev-parse", "HEAD"])
+            .ok()
+            .map(|output| output.trim().to_string());
+        let porcelain = git_output(
+            worktree_path,
+            &["status", "--porcelain", "--untracked-files=all"],
+        )?
+        .lines()
+        .map(str::to_string)
+        .collect();
+        let snapshot = WorktreeSnapshot::capture(worktree_path);
+        Ok(Self {
+            head,
+            porcelain,
+            snapshot,
+        })
+    }
+
+    /// What differs in `after`: status lines that are new, a moved `HEAD`, and
+    /// files that were already changed but whose content changed again.
+    pub fn changes_since(&self, after: &WorktreeStatus) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.head != after.head {
+            let short = |head: &Option<String>| {
+                head.as_deref()
+                    .map(|head| head.chars().take(8).collect())
+                    .unwrap_or_else(|| "none".to_string())
+            };
+            changes.push(format!(
+                "HEAD moved from {} to {}",
+                short(&self.head),
+                short(&after.head)
+            ));
+        }
+        changes.extend(
+            after
+                .porcelain
+                .iter()
+                .filter(|line| !self.porcelain.contains(line))
+                .cloned(),
+        );
+        changes.extend(
+            self.porcelain
+                .iter()
+                .filter(|line| !after.porcelain.contains(line))
+                .map(|line| format!("{line} (reverted)")),
+        );
+        let reported: Vec<&str> = changes
+            .iter()
+            .filter_map(|line| line.get(3..))
+            .map(|path| path.trim_end_matches(" (reverted)"))
+            .collect();
+        let modified_again: Vec<String> = after
+            .snapshot
+            .content_changes_since(&self.snapshot)
+            .into_iter()
+            .filter(|path| !reported.contains(&path.as_str()))
+            .map(|path| format!("{path} (modified again)"))
+            .collect();
+        changes.extend(modified_again);
+        changes
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use crate::command_risk::command_is_read_only;
+    use crate::read_only::{read_only_denial, WorktreeStatus};
+    use serde_json::json;
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_read_only_commands() {
+        for command in [
+            "ls -la src",
+            "cat Cargo.toml | grep version",
+            "rg 'fn main' --type rust 2>/dev/null",
+            "git log --oneline -5 && git status",
+            "git -C ../repo diff HEAD~1",
+            "git branch -a",
+            "find . -name '*.rs' | wc -l",
+            "sed -n '1,20p' src/lib.rs",
+            "bash -c 'cat README.md | head'",
+            "echo $(git rev-parse HEAD)",
+            "timeout 10 grep -r TODO src",
+        ] {
+            assert!(command_is_read_only(command), "{command}");
+        }
+    }
+
+    #[test]
+    fn test_mutating_commands() {
+        for command in [
+            "rm -rf target",
+            "echo hi > notes.txt",
+            "cat a >> b",
+            "sed -i 's/a/b/' src/lib.rs",
+            "sort -o out.txt in.txt",
+            "find . -name '*.tmp' -delete",
+            "find . | xargs rm",
+            "git commit -am wip",
+            "git branch feature",
+            "git stash",
+            "npm install",
+            "cargo build",
+            "bash script.sh",
+            "ls $(touch x)",
+            "ls && touch x",
+            "./configure",
+        ] {
+            assert!(!command_is_read_only(command), "{command}");
+        }
+    }
+
+    #[test]
+    fn test_read_only_denials() {
+        assert!(read_only_denial("Edit", &json!({ "file_path": "/a" }))
+            .unwrap()
+            .contains("read-only run"));
+        assert!(read_only_denial("Bash", &json!({ "command": "git push" })).is_some());
+        assert!(read_only_denial("Bash", &json!({ "command": "git diff" })).is_none());
+        assert!(read_only_denial("Read", &json!({ "file_path": "/a" })).is_none());
+    }
+
+    #[test]
+    fn test_worktree_status_changes() {
+        let dir = std::env::temp_dir().join(format!("read-only-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        git(&dir, &["init", "-q"]);
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        git(&dir, &["add", "-A"]);
+        git(&dir, &["commit", "-q", "-m", "init"]);
+        fs::write(dir.join("dirty.txt"), "before the run").unwrap();
+        let worktree = dir.to_string_lossy().to_string();
+
+        let before = WorktreeStatus::capture(&worktree).unwrap();
+        assert!(before
+            .changes_since(&WorktreeStatus::capture(&worktree).unwrap())
+            .is_empty());
+
+        fs::write(dir.join("a.txt"), "changed").unwrap();
+        let changes = before.changes_since(&WorktreeStatus::capture(&worktree).unwrap());
+        assert_eq!(changes, vec![" M a.txt"]);
+
+        fs::write(dir.join("dirty.txt"), "changed during the run").unwrap();
+        let changes = before.changes_since(&WorktreeStatus::capture(&worktree).unwrap());
+        assert_eq!(changes, vec![" M a.txt", "dirty.txt (modified again)"]);
+
+        git(&dir, &["commit", "-q", "-am", "agent commit"]);
+        let changes = before.changes_since(&WorktreeStatus::capture(&worktree).unwrap());
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].starts_with("HEAD moved from "));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_worktree_status_sees_already_modified_file_change_again() {
+        let dir = std::env::temp_dir().join(format!("read-only-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        git(&dir, &["init", "-q"]);
+        fs::write(dir.join("foo.rs"), "fn main() {}").unwrap();
+        git(&dir, &["add", "-A"]);
+        git(&dir, &["commit", "-q", "-m", "init"]);
+        fs::write(dir.join("foo.rs"), "fn main() { edited }").unwrap();
+        let worktree = dir.to_string_lossy().to_string();
+
+        let before = WorktreeStatus::capture(&worktree).unwrap();
+        fs::write(dir.join("foo.rs"), "fn main() { edited again }").unwrap();
+        let changes = before.changes_since(&WorktreeStatus::capture(&worktree).unwrap());
+        assert_eq!(changes, vec!["foo.rs (modified again)"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
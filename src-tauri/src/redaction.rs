@@ -0,0 +1,324 @@
+use regex::Regex;
+use std::path::Path;
+
+/// Where the custom patterns are saved, in the app config dir.
+pub const REDACTION_PATTERNS_FILE: &str = "redaction_patterns.json";
+
+/// Built-in secret formats, by the name that replaces them. A `secret` group,
+/// when present, is the only part masked, so `API_KEY=` stays readable.
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        "private_key",
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----(?:[A-Za-z0-9+/=\s:,]|-[A-Za-z]|\\[nr])*(?:-----END [A-Z ]*PRIVATE KEY-----)?",
+    ),
+    (
+        "jwt",
+        r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]+",
+    ),
+    ("anthropic_api_key", r"\bsk-ant-[A-Za-z0-9_-]{20,}"),
+    ("openai_api_key", r"\bsk-(?:proj-)?[A-Za-z0-9_-]{20,}"),
+    (
+        "github_token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+    ),
+    ("slack_token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}"),
+    ("google_api_key", r"\bAIza[0-9A-Za-z_-]{35}"),
+    ("stripe_key", r"\b[rs]k_live_[0-9A-Za-z]{24,}"),
+    (
+        "aws_access_key_id",
+        r"\b(?:AKIA|ASIA|AGPA|AIDA|AROA|ANPA|ANVA|AIPA)[A-Z0-9]{16}\b",
+    ),
+    (
+        "bearer_token",
+        r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9._~+/=-]{16,})",
+    ),
+    (
+        "url_credentials",
+        r#"(?i)\b[a-z][a-z0-9+.-]*://[^\s:/@"'\\]+:(?P<secret>[^\s@/"'\\]+)@"#,
+    ),
+    // `KEY=value` and `"key": "value"` assignments, also inside escaped JSON
+    (
+        "credential",
+        r#"(?i)[a-z0-9_.-]*(?:secret|token|passw(?:or)?d|api_?key|access_?key|private_?key)[a-z0-9_]*\\?["']?\s*[=:]\s*\\?["']?(?P<secret>[^\s"'\\,;&]{8,})"#,
+    ),
+];
+
+/// The `[REDACTED:<rule>]` markers that replace secrets.
+const MARKER: &str = r"\[REDACTED:[a-z_]+\]";
+
+/// Object keys whose string values are masked whole in JSON payloads.
+const SECRET_KEY: &str =
+    r"(?i)(?:secret|token|passw(?:or)?d|api_?key|access_?key|private_?key|credential)";
+
+/// Masks secrets in agent output, logs and approval payloads.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    rules: Vec<(String, Regex)>,
+    secret_key: Regex,
+    /// User-defined patterns, as given.
+    custom: Vec<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(Vec::new()).expect("built-in redaction rules are valid")
+    }
+}
+
+impl Redactor {
+    /// The built-in rules plus `custom` regexes.
+    pub fn new(custom: Vec<String>) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for (name, pattern) in BUILTIN_RULES {
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid rule {name}: {e}"))?;
+            rules.push((name.to_string(), regex));
+        }
+        for pattern in &custom {
+            let regex = Regex::new(pattern)
+                .map_err(|e| format!("Invalid redaction pattern '{pattern}': {e}"))?;
+            rules.push(("custom".to_string(), regex));
+        }
+        Ok(Self {
+            rules,
+            secret_key: Regex::new(SECRET_KEY).expect("secret key pattern is valid"),
+            custom,
+        })
+    }
+
+    pub fn custom_patterns(&self) -> &[String] {
+        &self.custom
+    }
+
+    /// `text` with every secret replaced by `[REDACTED:<rule>]`, and how many were.
+    pub fn redact(&self, text: &str) -> (String, usize) {
+        let mut text = text.to_string();
+        let mut count = 0;
+        for (name, regex) in &self.rules {
+            if !regex.is_match(&text) {
+                continue;
+            }
+            let marker = format!("[REDACTED:{name}]");
+            let mut redacted = String::with_capacity(text.len());
+            let mut last = 0;
+            for captures in regex.captures_iter(&text) {
+                let found = captures
+                    .name("secret")
+                    .unwrap_or_else(|| captures.get(0).expect("a match has a whole group"));
+                if found.is_empty() || (name == "credential" && !plausible_secret(found.as_str())) {
+                    continue;
+                }
+                redacted.push_str(&text[last..found.start()]);
+                redacted.push_str(&marker);
+                last = found.end();
+                count += 1;
+            }
+            redacted.push_str(&text[last..]);
+            text = redacted;
+        }
+        (text, count)
+    }
+
+    /// Redact every string in a JSON value in place, and mask string values
+    /// of keys such as `password` or `api_key` whole.
+    pub fn redact_json(&self, value: &mut serde_json::Value) -> usize {
+        match value {
+            serde_json::Value::String(text) => {
+                let (redacted, count) = self.redact(text);
+                if count > 0 {
+                    *text = redacted;
+                }
+                count
+            }
+            serde_json::Value::Array(items) => {
+                items.iter_mut().map(|item| self.redact_json(item)).sum()
+            }
+            serde_json::Value::Object(fields) => fields
+                .iter_mut()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(text)
+                        if self.secret_key.is_match(key) && plausible_secret(text) =>
+                    {
+                        *text = "[REDACTED:credential]".to_string();
+                        1
+                    }
+                    _ => self.redact_json(value),
+                })
+                .sum(),
+            _ => 0,
+        }
+    }
+}
+
+/// Custom patterns saved by [`save_patterns`], none before the first save.
+pub fn load_patterns(path: &Path) -> Result<Vec<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {e}", path.display())),
+    }
+}
+
+pub fn save_patterns(path: &Path, patterns: &[String]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let content = serde_json::to_string_pretty(patterns).map_err(|e| e.to_string())?;
+    std::fs::write(path, content).map_err(|e| {
+        format!(
+            "Failed to save redaction patterns to {}: {e}",
+            path.display()
+        )
+    })
+}
+
+/// Put the secrets back into `edited`, a user's edit of `shown`, which is
+/// `original` with its secrets masked. Markers kept as shown get their secret
+/// back and text without markers is taken as is; an edit that changes, moves
+/// or adds a marker is refused.
+pub fn restore_json(
+    edited: &serde_json::Value,
+    shown: &serde_json::Value,
+    original: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    if edited == shown {
+        return Ok(original.clone());
+    }
+    let marker = Regex::new(MARKER).expect("marker pattern is valid");
+    let unmasked = |value: &Value| match marker.is_match(&value.to_string()) {
+        true => Err(EDITED_MARKER.to_string()),
+        false => Ok(value.clone()),
+    };
+    match (edited, shown, original) {
+        (Value::Object(edited), Value::Object(shown), Value::Object(original)) => edited
+            .iter()
+            .map(|(key, value)| {
+                let restored = match (shown.get(key), original.get(key)) {
+                    (Some(shown), Some(original)) => restore_json(value, shown, original)?,
+                    _ => unmasked(value)?,
+                };
+                Ok((key.clone(), restored))
+            })
+            .collect::<Result<serde_json::Map<_, _>, String>>()
+            .map(Value::Object),
+        (Value::Array(edited), Value::Array(shown), Value::Array(original)) => edited
+            .iter()
+            .enumerate()
+            .map(
+                |(index, value)| match (shown.get(index), original.get(index)) {
+                    (Some(shown), Some(original)) => restore_json(value, shown, original),
+                    _ => unmasked(value),
+                },
+            )
+            .collect::<Result<Vec<_>, String>>()
+            .map(Value::Array),
+        (Value::String(edited), Value::String(shown), Value::String(original)) => {
+            let markers = |text: &str| -> Vec<String> {
+                marker
+                    .find_iter(text)
+                    .map(|found| found.as_str().to_string())
+                    .collect()
+            };
+            // Text with the secrets taken out needs nothing put back
+            let kept = markers(edited);
+            if kept.is_empty() {
+                return Ok(Value::String(edited.clone()));
+            }
+            if kept != markers(shown) {
+                return Err(EDITED_MARKER.to_string());
+            }
+            let mut secrets = masked_spans(&marker, shown, original)
+                .ok_or(EDITED_MARKER)?
+                .into_iter();
+            let restored = marker.replace_all(edited, |_: &regex::Captures| {
+                secrets.next().unwrap_or_default()
+            });
+            Ok(Value::String(restored.into_owned()))
+        }
+        _ => unmasked(edited),
+    }
+}
+
+const EDITED_MARKER: &str =
+    "Edits cannot change, move or add [REDACTED:…] markers. Keep them as shown or remove them.";
+
+/// The text each marker in `shown` replaced in `original`, in order.
+fn masked_spans(marker: &Regex, shown: &str, original: &str) -> Option<Vec<String>> {
+    let pieces: Vec<&str> = marker.split(shown).collect();
+    let (first, rest) = pieces.split_first()?;
+    let mut position = original.strip_prefix(first).map(|_| first.len())?;
+    let mut spans = Vec::new();
+    for (index, piece) in rest.iter().enumerate() {
+        let remaining = &original[position..];
+        let end = if index + 1 == rest.len() {
+            remaining.strip_suffix(piece)?.len()
+        } else if piece.is_empty() {
+            // Adjacent markers: there is no telling where one secret ends
+            return None;
+        } else {
+            remaining.find(piece)?
+        };
+        spans.push(remaining[..end].to_string());
+        position += end + piece.len();
+    }
+    Some(spans)
+}
+
+/// Counts, booleans and placeholders next to a secret-sounding key, such as
+/// `"output_tokens": 12345678`, are not secrets.
+fn plausible_secret(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty()
+        && !value.bytes().all(|byte| byte.is_ascii_digit())
+        && !["true", "false", "null", "none", "undefined"].contains(&value.to_lowercase().as_str())
+        && !value.starts_with("[REDACTED")
+        && !value.starts_with("${")
+}
+
+/// Redacts one output stream line by line. A private key printed over several
+/// lines has its body swallowed until the `END` line.
+#[derive(Debug, Default)]
+pub struct LineRedactor {
+    in_private_key: bool,
+}
+
+impl LineRedactor {
+    /// The line to show, or `None` when it belongs to a private key.
+    pub fn redact(&mut self, redactor: &Redactor, line: &str) -> Option<(String, usize)> {
+        let mut line = line;
+        if self.in_private_key {
+            if let Some(end) = line.find("PRIVATE KEY-----") {
+                self.in_private_key = false;
+                line = &line[end + "PRIVATE KEY-----".len()..];
+                if line.trim().is_empty() {
+                    return None;
+                }
+            } else if is_key_body(line) {
+                return None;
+            } else {
+                self.in_private_key = false;
+            }
+        }
+
+        let (redacted, count) = redactor.redact(line);
+        // A key whose block ran to the end of the line continues on the next
+        self.in_private_key = line.rfind("-----BEGIN ").is_some_and(|begin| {
+            let rest = &line[begin..];
+            rest.contains("PRIVATE KEY-----") && !rest.contains("-----END ")
+        });
+        Some((redacted, count))
+    }
+}
+
+/// Base64 lines and PEM headers such as `Proc-Type: 4,ENCRYPTED`.
+fn is_key_body(line: &str) -> bool {
+    let line = line.trim();
+    line.bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || b"+/=".contains(&byte))
+        || line.split_once(": ").is_some_and(|(name, _)| {
+            name.contains('-') && name.bytes().all(|b| b.is_ascii_alphabetic() || b == b'-')
+        })
+}
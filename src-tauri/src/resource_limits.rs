@@ -0,0 +1,534 @@
+use crate::agent_backends::AgentLaunchSpec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Sets rlimits on the command it runs; part of util-linux.
+pub const PRLIMIT_PROGRAM: &str = "prlimit";
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// Leaf cgroup Orchestra moves itself into, so its own cgroup can pass
+/// controllers down to the agents' cgroups.
+const APP_CGROUP: &str = "orchestra-app";
+/// Tries, 50ms apart, to remove a killed cgroup while its processes exit.
+const CGROUP_REMOVE_TRIES: u32 = 20;
+/// `EBUSY`, returned by `rmdir` on a cgroup that still has processes.
+const EBUSY: i32 = 16;
+/// Linux reports CPU times in `/proc/<pid>/stat` in these ticks.
+pub(crate) const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+const CPU_PERIOD_US: u64 = 100_000;
+const MIN_MEMORY_MB: u64 = 64;
+
+/// Limits applied to each agent process tree. `None` leaves a resource unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Share of one core, so 200 allows two full cores. Needs cgroup v2.
+    #[serde(default)]
+    pub cpu_percent: Option<u32>,
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    #[serde(default)]
+    pub open_files: Option<u64>,
+    /// Processes and threads in the tree. Without cgroup v2 this falls back to
+    /// `RLIMIT_NPROC`, which counts every process of the user.
+    #[serde(default)]
+    pub max_processes: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cpu_percent == Some(0) {
+            return Err("CPU limit must be at least 1%".to_string());
+        }
+        if self.memory_mb.is_some_and(|mb| mb < MIN_MEMORY_MB) {
+            return Err(format!("Memory limit must be at least {MIN_MEMORY_MB} MB"));
+        }
+        if self.open_files == Some(0) {
+            return Err("Open file limit must be at least 1".to_string());
+        }
+        if self.max_processes == Some(0) {
+            return Err("Process limit must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// `cgroup.subtree_control` names of the controllers these limits need.
+    fn cgroup_controllers(&self) -> Vec<&'static str> {
+        [
+            ("cpu", self.cpu_percent.is_some()),
+            ("memory", self.memory_mb.is_some()),
+            ("pids", self.max_processes.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, wanted)| *wanted)
+        .map(|(controller, _)| controller)
+        .collect()
+    }
+
+    /// cgroup interface files and the values that enforce these limits.
+    fn cgroup_files(&self) -> Vec<(&'static str, String)> {
+        let mut files = Vec::new();
+        if let Some(percent) = self.cpu_percent {
+            let quota = CPU_PERIOD_US * u64::from(percent) / 100;
+            files.push(("cpu.max", format!("{quota} {CPU_PERIOD_US}")));
+        }
+        if let Some(mb) = self.memory_mb {
+            files.push(("memory.max", (mb * 1024 * 1024).to_string()));
+            // Fail allocations instead of pushing the whole tree into swap
+            files.push(("memory.swap.max", "0".to_string()));
+        }
+        if let Some(max) = self.max_processes {
+            files.push(("pids.max", max.to_string()));
+        }
+        files
+    }
+}
+
+/// How a process's limits were enforced, shown in `list_processes`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedLimits {
+    pub limits: ResourceLimits,
+    /// cgroup the process tree runs in; `None` when only rlimits apply.
+    #[serde(default)]
+    pub cgroup: Option<String>,
+    /// Limits that could not be enforced on this system, and why.
+    #[serde(default)]
+    pub unenforced: Vec<String>,
+}
+
+/// The limits for one agent process, kept until its last retry has exited.
+pub struct ProcessLimits {
+    applied: AppliedLimits,
+    rlimit_args: Vec<String>,
+    cgroup: Option<ProcessCgroup>,
+}
+
+impl ProcessLimits {
+    /// Put the limits in a cgroup of their own when cgroup v2 is usable, and
+    /// use rlimits for the rest.
+    pub fn prepare(process_id: &str, limits: &ResourceLimits) -> Result<Self, String> {
+        if !cfg!(target_os = "linux") {
+            return Err("Resource limits are only supported on Linux".to_string());
+        }
+        limits.validate()?;
+
+        let mut unenforced = Vec::new();
+        let cgroup = match limits.cgroup_controllers().is_empty() {
+            true => None,
+            false => match ProcessCgroup::create(process_id, limits) {
+                Ok(cgroup) => Some(cgroup),
+                Err(e) => {
+                    tracing::warn!(
+                        process_id,
+                        error = %e,
+                        "No cgroup for the process, falling back to rlimits for memory and processes"
+                    );
+                    if limits.cpu_percent.is_some() {
+                        unenforced.push(format!("CPU limit needs cgroup v2 ({e})"));
+                    }
+                    None
+                }
+            },
+        };
+
+        let mut rlimit_args = Vec::new();
+        if let Some(open_files) = limits.open_files {
+            rlimit_args.push(format!("--nofile={open_files}"));
+        }
+        if cgroup.is_none() {
+            if let Some(mb) = limits.memory_mb {
+                rlimit_args.push(format!("--data={}", mb * 1024 * 1024));
+            }
+            if let Some(max) = limits.max_processes {
+                rlimit_args.push(format!("--nproc={max}"));
+            }
+        }
+        if !rlimit_args.is_empty() {
+            Command::new(PRLIMIT_PROGRAM)
+                .arg("--version")
+                .output()
+                .map_err(|e| {
+                    format!("Resource limits need '{PRLIMIT_PROGRAM}' installed and in PATH ({e})")
+                })?;
+        }
+
+        Ok(Self {
+            applied: AppliedLimits {
+                limits: limits.clone(),
+                cgroup: cgroup
+                    .as_ref()
+                    .map(|cgroup| cgroup.path.to_string_lossy().to_string()),
+                unenforced,
+            },
+            rlimit_args,
+            cgroup,
+        })
+    }
+
+    pub fn applied(&self) -> &AppliedLimits {
+        &self.applied
+    }
+
+    /// The same launch, started in the cgroup and under the rlimits.
+    pub fn wrap_launch(&self, spec: &AgentLaunchSpec) -> AgentLaunchSpec {
+        let mut command = vec![spec.program.clone()];
+        command.extend(spec.args.iter().cloned());
+
+        if let Some(cgroup) = &self.cgroup {
+            // Join the cgroup before the agent starts, so everything it spawns is counted
+            let procs = cgroup.path.join("cgroup.procs");
+            command.splice(
+                0..0,
+                [
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "echo $$ > \"$0\" && exec \"$@\"".to_string(),
+                    procs.to_string_lossy().to_string(),
+                ],
+            );
+        }
+        if !self.rlimit_args.is_empty() {
+            let mut prlimit = vec![PRLIMIT_PROGRAM.to_string()];
+            prlimit.extend(self.rlimit_args.iter().cloned());
+            prlimit.push("--".to_string());
+            command.splice(0..0, prlimit);
+        }
+
+        AgentLaunchSpec {
+            program: command.remove(0),
+            args: command,
+            current_dir: spec.current_dir.clone(),
+            envs: spec.envs.clone(),
+        }
+    }
+}
+
+/// A cgroup v2 directory for one agent process tree, removed when dropped.
+struct ProcessCgroup {
+    path: PathBuf,
+}
+
+impl ProcessCgroup {
+    fn create(process_id: &str, limits: &ResourceLimits) -> Result<Self, String> {
+        let parent = own_cgroup()?;
+        // Reorganising a cgroup shared with a shell or a desktop session would
+        // move processes that are not Orchestra's
+        ensure_owned(&parent)?;
+        let subtree_control = parent.join("cgroup.subtree_control");
+        let enabled = std::fs::read_to_string(&subtree_control).unwrap_or_default();
+        let missing: Vec<&str> = limits
+            .cgroup_controllers()
+            .into_iter()
+            .filter(|controller| !enabled.split_whitespace().any(|name| name == *controller))
+            .collect();
+        if !missing.is_empty() {
+            // A cgroup cannot both hold processes and pass controllers to
+            // children, so Orchestra moves out of its own first
+            move_into_leaf(&parent)?;
+            for controller in missing {
+                std::fs::write(&subtree_control, format!("+{controller}")).map_err(|e| {
+                    format!(
+                        "cannot enable the {controller} controller in {}: {e}",
+                        parent.display()
+                    )
+                })?;
+            }
+        }
+
+        let cgroup = Self {
+            path: parent.join(format!("orchestra-{process_id}")),
+        };
+        std::fs::create_dir(&cgroup.path)
+            .map_err(|e| format!("cannot create {}: {e}", cgroup.path.display()))?;
+        for (file, value) in limits.cgroup_files() {
+            let path = cgroup.path.join(file);
+            // Kernels without swap accounting have no memory.swap.max
+            if file == "memory.swap.max" && !path.exists() {
+                continue;
+            }
+            std::fs::write(&path, &value)
+                .map_err(|e| format!("cannot write {value} to {}: {e}", path.display()))?;
+        }
+        Ok(cgroup)
+    }
+}
+
+impl Drop for ProcessCgroup {
+    fn drop(&mut self) {
+        // Kill whatever the agent left running, which would keep the cgroup busy
+        let kill = self.path.join("cgroup.kill");
+        if let Err(e) = std::fs::write(&kill, "1") {
+            tracing::warn!(
+                cgroup = %self.path.display(),
+                error = %e,
+                "Cannot kill the cgroup's processes (needs Linux 5.14), removing it as is"
+            );
+        }
+
+        let mut tries = 0;
+        loop {
+            match std::fs::remove_dir(&self.path) {
+                Ok(()) => break,
+                // Busy until the killed processes have exited
+                Err(e) if e.raw_os_error() == Some(EBUSY) && tries < CGROUP_REMOVE_TRIES => {
+                    tries += 1;
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                Err(e) => {
+                    tracing::warn!(cgroup = %self.path.display(), error = %e, "Failed to remove cgroup");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// The cgroup agent cgroups are created in: this process's, or the parent of
+/// the leaf it moved itself into.
+fn own_cgroup() -> Result<PathBuf, String> {
+    let root = Path::new(CGROUP_ROOT);
+    if !root.join("cgroup.controllers").exists() {
+        return Err("cgroup v2 is not mounted".to_string());
+    }
+    let membership = std::fs::read_to_string("/proc/self/cgroup")
+        .map_err(|e| format!("cannot read /proc/self/cgroup: {e}"))?;
+    let relative = membership
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or("not in a cgroup v2 hierarchy")?;
+    let cgroup = root.join(relative.trim_start_matches('/'));
+    match cgroup.file_name().is_some_and(|name| name == APP_CGROUP) {
+        true => Ok(cgroup.parent().unwrap_or(root).to_path_buf()),
+        false => Ok(cgroup),
+    }
+}
+
+/// Fails unless `cgroup` is delegated to Orchestra's user, as systemd does
+/// for `Delegate=yes` units, and holds nothing but Orchestra and the
+/// processes it started.
+fn ensure_owned(cgroup: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let user = std::fs::metadata("/proc/self")
+            .map_err(|e| format!("cannot read /proc/self: {e}"))?
+            .uid();
+        let owner = std::fs::metadata(cgroup)
+            .map_err(|e| format!("cannot read {}: {e}", cgroup.display()))?
+            .uid();
+        if owner != user {
+            return Err(format!(
+                "{} is not delegated to Orchestra's user",
+                cgroup.display()
+            ));
+        }
+    }
+
+    let orchestra = std::process::id();
+    let foreign = cgroup_pids(cgroup)
+        .into_iter()
+        .chain(cgroup_pids(&cgroup.join(APP_CGROUP)))
+        .find(|&pid| !is_descendant(pid, orchestra));
+    match foreign {
+        Some(pid) => Err(format!(
+            "{} is shared with process {pid}, which Orchestra did not start",
+            cgroup.display()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The processes directly in `cgroup`, none when it does not exist.
+fn cgroup_pids(cgroup: &Path) -> Vec<u32> {
+    std::fs::read_to_string(cgroup.join("cgroup.procs"))
+        .map(|procs| {
+            procs
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether `pid` is `ancestor` or was started by it, directly or not.
+pub(crate) fn is_descendant(pid: u32, ancestor: u32) -> bool {
+    let mut pid = pid;
+    // Bounded in case a pid is reused into a cycle while walking
+    for _ in 0..64 {
+        if pid == ancestor {
+            return true;
+        }
+        match read_stat(pid) {
+            Some(stat) if stat.parent > 0 => pid = stat.parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Move Orchestra's processes, the only ones in `cgroup` once
+/// [`ensure_owned`] passed, into its [`APP_CGROUP`] leaf.
+fn move_into_leaf(cgroup: &Path) -> Result<(), String> {
+    let leaf = cgroup.join(APP_CGROUP);
+    if let Err(e) = std::fs::create_dir(&leaf) {
+        if e.kind() != std::io::ErrorKind::AlreadyExists {
+            return Err(format!("cannot create {}: {e}", leaf.display()));
+        }
+    }
+
+    // Anything that joined since the ownership check stays where it is
+    let orchestra = std::process::id();
+    let pids: Vec<u32> = cgroup_pids(cgroup)
+        .into_iter()
+        .filter(|&pid| is_descendant(pid, orchestra))
+        .collect();
+    if !pids.is_empty() {
+        tracing::info!(
+            from = %cgroup.display(),
+            to = %leaf.display(),
+            processes = pids.len(),
+            "Moving Orchestra into a leaf cgroup"
+        );
+    }
+    for pid in pids {
+        // A process that exited meanwhile has nothing left to move
+        if let Err(e) = std::fs::write(leaf.join("cgroup.procs"), pid.to_string()) {
+            if is_alive(pid) {
+                return Err(format!(
+                    "cannot move process {pid} into {}: {e}",
+                    leaf.display()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What an agent process tree is using right now, shown in `list_processes`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// CPU time used so far by the processes still running.
+    pub cpu_seconds: f64,
+    pub memory_mb: f64,
+    pub open_files: u64,
+    /// Processes and threads, counted the way `pids.max` counts them.
+    pub processes: u64,
+}
+
+impl ResourceUsage {
+    /// Add up the processes in `cgroup`, or the process `pid` and its
+    /// descendants when there is none.
+    pub fn sample(pid: u32, cgroup: Option<&str>) -> Option<Self> {
+        let pids = tree_pids(pid, cgroup);
+        if pids.is_empty() {
+            return None;
+        }
+
+        let mut usage = Self::default();
+        for pid in pids {
+            // Exited processes waiting to be reaped use nothing
+            let Some(stat) = read_stat(pid).filter(|stat| !matches!(stat.state, 'Z' | 'X')) else {
+                continue;
+            };
+            usage.processes += stat.threads;
+            usage.cpu_seconds += stat.cpu_ticks as f64 / CLOCK_TICKS_PER_SECOND;
+            usage.memory_mb += resident_kb(pid) as f64 / 1024.0;
+            usage.open_files += std::fs::read_dir(format!("/proc/{pid}/fd"))
+                .map(|entries| entries.count() as u64)
+                .unwrap_or(0);
+        }
+        Some(usage)
+    }
+}
+
+/// The fields of `/proc/<pid>/stat` that usage is built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcStat {
+    /// `R`, `S`, `Z` and so on.
+    pub state: char,
+    pub parent: u32,
+    /// User plus system time, in clock ticks.
+    pub cpu_ticks: u64,
+    pub threads: u64,
+}
+
+/// Parse `/proc/<pid>/stat`. The command name is in parentheses and may
+/// itself contain spaces and parentheses, so fields are counted from the last `)`.
+pub fn parse_stat(stat: &str) -> Option<ProcStat> {
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    // fields[0] is the state, field 4 of the full line
+    let field = |number: usize| fields.get(number - 3)?.parse::<u64>().ok();
+    Some(ProcStat {
+        state: fields.first()?.chars().next()?,
+        parent: field(4)? as u32,
+        cpu_ticks: field(14)? + field(15)?,
+        threads: field(20)?,
+    })
+}
+
+pub(crate) fn read_stat(pid: u32) -> Option<ProcStat> {
+    parse_stat(&std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?)
+}
+
+/// Whether `pid` is running, as opposed to gone or exited and not yet reaped.
+pub(crate) fn is_alive(pid: u32) -> bool {
+    read_stat(pid).is_some_and(|stat| !matches!(stat.state, 'Z' | 'X'))
+}
+
+pub(crate) fn resident_kb(pid: u32) -> u64 {
+    std::fs::read_to_string(format!("/proc/{pid}/status"))
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmRSS:"))
+                .and_then(|value| value.split_whitespace().next()?.parse().ok())
+        })
+        .unwrap_or(0)
+}
+
+/// The processes of an agent run: those in its cgroup, or `pid` and its
+/// descendants when it has none.
+pub(crate) fn tree_pids(pid: u32, cgroup: Option<&str>) -> Vec<u32> {
+    match cgroup {
+        Some(cgroup) => cgroup_pids(Path::new(cgroup)),
+        None => process_tree(pid),
+    }
+}
+
+/// `pid` and every process descended from it.
+fn process_tree(pid: u32) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let Some(child) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            else {
+                continue;
+            };
+            if let Some(stat) = read_stat(child) {
+                children.entry(stat.parent).or_default().push(child);
+            }
+        }
+    }
+
+    if !is_alive(pid) {
+        return Vec::new();
+    }
+    let mut tree = vec![pid];
+    let mut index = 0;
+    while index < tree.len() {
+        if let Some(descendants) = children.get(&tree[index]) {
+            tree.extend(descendants);
+        }
+        index += 1;
+    }
+    tree
+}
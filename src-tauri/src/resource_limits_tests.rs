@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod tests {
+    use crate::agent_backends::AgentLaunchSpec;
+    use crate::resource_limits::{
+        is_descendant, parse_stat, ProcessLimits, ResourceLimits, ResourceUsage,
+    };
+    use std::process::Command;
+    use std::time::Duration;
+
+    fn shell_spec(script: &str) -> AgentLaunchSpec {
+        AgentLaunchSpec {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            current_dir: ".".to_string(),
+            envs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_limits() {
+        assert!(ResourceLimits::default().is_empty());
+        assert!(ResourceLimits::default().validate().is_ok());
+
+        let limits = ResourceLimits {
+            cpu_percent: Some(150),
+            memory_mb: Some(2048),
+            open_files: Some(1024),
+            max_processes: Some(256),
+        };
+        assert!(!limits.is_empty());
+        assert!(limits.validate().is_ok());
+
+        for invalid in [
+            ResourceLimits {
+                cpu_percent: Some(0),
+                ..Default::default()
+            },
+            ResourceLimits {
+                memory_mb: Some(16),
+                ..Default::default()
+            },
+            ResourceLimits {
+                open_files: Some(0),
+                ..Default::default()
+            },
+            ResourceLimits {
+                max_processes: Some(0),
+                ..Default::default()
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "4242 (node (worker) x) S 4200 4242 4200 0 -1 4194560 100 0 0 0 \
+                    250 50 0 0 20 0 11 0 12345 1000000 5000";
+        let stat = parse_stat(stat).unwrap();
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.parent, 4200);
+        assert_eq!(stat.cpu_ticks, 300);
+        assert_eq!(stat.threads, 11);
+
+        assert!(parse_stat("4242 (truncated").is_none());
+        assert!(parse_stat("").is_none());
+    }
+
+    #[test]
+    fn test_usage_covers_the_process_tree() {
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 5 & sleep 5 & wait"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let usage = ResourceUsage::sample(child.id(), None).unwrap();
+        assert_eq!(usage.processes, 3);
+        assert!(usage.memory_mb > 0.0);
+        assert!(usage.open_files > 0);
+
+        // Exited but not yet reaped counts as gone
+        child.kill().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(ResourceUsage::sample(child.id(), None).is_none());
+        child.wait().unwrap();
+        assert!(ResourceUsage::sample(child.id(), None).is_none());
+    }
+
+    #[test]
+    fn test_is_descendant() {
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 5 & wait"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        let orchestra = std::process::id();
+
+        assert!(is_descendant(orchestra, orchestra));
+        assert!(is_descendant(child.id(), orchestra));
+        // The shell that started this process is not one of its own
+        let parent = std::fs::read_to_string("/proc/self/stat")
+            .ok()
+            .and_then(|stat| parse_stat(&stat))
+            .unwrap()
+            .parent;
+        assert!(!is_descendant(parent, orchestra));
+        assert!(!is_descendant(1, orchestra));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_open_file_limit_applies_to_the_agent() {
+        let limits = ResourceLimits {
+            open_files: Some(200),
+            ..Default::default()
+        };
+        let process_limits = ProcessLimits::prepare("resource-test-nofile", &limits).unwrap();
+        assert!(process_limits.applied().cgroup.is_none());
+        assert!(process_limits.applied().unenforced.is_empty());
+
+        let wrapped = process_limits.wrap_launch(&shell_spec("ulimit -n"));
+        assert_eq!(wrapped.program, "prlimit");
+        assert_eq!(
+            wrapped.args,
+            ["--nofile=200", "--", "sh", "-c", "ulimit -n"]
+        );
+
+        let output = wrapped.spawn().unwrap().wait_with_output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "200");
+    }
+
+    #[test]
+    fn test_limits_fall_back_to_rlimits_without_cgroup() {
+        let limits = ResourceLimits {
+            cpu_percent: Some(50),
+            memory_mb: Some(512),
+            open_files: None,
+            max_processes: Some(4096),
+        };
+        let process_limits = ProcessLimits::prepare("resource-test-fallback", &limits).unwrap();
+        let applied = process_limits.applied().clone();
+        let wrapped = process_limits.wrap_launch(&shell_spec("true"));
+
+        match &applied.cgroup {
+            // cgroup v2 is delegated here: the agent joins it before it starts
+            Some(cgroup) => {
+                assert!(applied.unenforced.is_empty());
+                assert_eq!(wrapped.program, "sh");
+                assert_eq!(wrapped.args[2], format!("{cgroup}/cgroup.procs"));
+            }
+            None => {
+                assert_eq!(applied.unenforced.len(), 1);
+                assert!(applied.unenforced[0].starts_with("CPU limit"));
+                assert_eq!(wrapped.program, "prlimit");
+                assert_eq!(
+                    wrapped.args,
+                    ["--data=536870912", "--nproc=4096", "--", "sh", "-c", "true"]
+                );
+            }
+        }
+    }
+}
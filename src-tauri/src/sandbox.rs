@@ -0,0 +1,467 @@
+use crate::agent_backends::AgentLaunchSpec;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+#[cfg(unix)]
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UnixListener, UnixStream},
+};
+
+/// bubblewrap, which builds the sandbox from Linux namespaces.
+pub const SANDBOX_PROGRAM: &str = "bwrap";
+/// Connects TCP ports inside a network-restricted sandbox to Orchestra's sockets.
+pub const BRIDGE_PROGRAM: &str = "socat";
+/// Hosts a network-restricted sandbox can always reach.
+pub const DEFAULT_ALLOWED_HOSTS: &[&str] = &["api.anthropic.com"];
+/// The only port an allowed host is reachable on unless its entry names one.
+pub const HTTPS_PORT: u16 = 443;
+/// Where the allowlisting proxy listens inside the sandbox's own network.
+pub const SANDBOX_PROXY_PORT: u16 = 3128;
+/// Checks, 100ms apart, for the bridge listeners before the agent starts anyway.
+const BRIDGE_STARTUP_CHECKS: u32 = 50;
+/// Longest proxy request head read before giving up on a client.
+const MAX_PROXY_HEAD_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxNetwork {
+    /// Same network access as Orchestra.
+    #[default]
+    Full,
+    /// Only the allowed hosts (over HTTPS) and Orchestra's local servers.
+    Restricted,
+}
+
+/// Run an agent with the worktree writable and the rest of the system read-only.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SandboxOptions {
+    #[serde(default)]
+    pub network: SandboxNetwork,
+    /// Hosts a restricted sandbox may reach besides [`DEFAULT_ALLOWED_HOSTS`].
+    /// `*.example.com` allows every subdomain; `host:8443` allows a port
+    /// other than 443.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+}
+
+impl SandboxOptions {
+    pub fn allowed_hosts(&self) -> Vec<String> {
+        DEFAULT_ALLOWED_HOSTS
+            .iter()
+            .map(|host| host.to_string())
+            .chain(
+                self.allowed_hosts
+                    .iter()
+                    .map(|host| host.trim().to_lowercase()),
+            )
+            .filter(|host| !host.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxViolationKind {
+    Filesystem,
+    Network,
+}
+
+/// Something the sandbox stopped an agent from doing, sent as `sandbox-violation`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxViolation {
+    pub kind: SandboxViolationKind,
+    pub detail: String,
+}
+
+impl SandboxViolation {
+    /// A write blocked by the read-only mounts, if the output line reports one.
+    pub fn from_output_line(line: &str) -> Option<Self> {
+        let lower = line.to_lowercase();
+        (lower.contains("read-only file system") || lower.contains("erofs")).then(|| Self {
+            kind: SandboxViolationKind::Filesystem,
+            detail: format!(
+                "Blocked a write outside the worktree: {}",
+                line.trim().chars().take(300).collect::<String>()
+            ),
+        })
+    }
+}
+
+/// Fail early with a clear message when the sandbox cannot be built here.
+pub fn check_available(options: &SandboxOptions) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("Sandboxed runs are only supported on Linux".to_string());
+    }
+    let mut programs = vec![(SANDBOX_PROGRAM, "--version")];
+    if options.network == SandboxNetwork::Restricted {
+        programs.push((BRIDGE_PROGRAM, "-V"));
+    }
+    for (program, version_flag) in programs {
+        Command::new(program)
+            .arg(version_flag)
+            .output()
+            .map_err(|e| format!("Sandboxed runs need '{program}' installed and in PATH ({e})"))?;
+    }
+    Ok(())
+}
+
+/// What the sandbox mounts besides the read-only root.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SandboxMounts {
+    /// Writable paths; the worktree first.
+    pub writable: Vec<String>,
+    /// Files under `/tmp` the agent must still read, such as its MCP config.
+    pub read_only: Vec<String>,
+}
+
+impl SandboxMounts {
+    /// The worktree, the git dir it commits to and Claude's own config.
+    pub fn for_worktree(worktree_path: &str) -> Self {
+        let mut writable = vec![worktree_path.to_string()];
+
+        let common_dir = Command::new("git")
+            .args(["rev-parse", "--git-common-dir"])
+            .current_dir(worktree_path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+        if let Some(common_dir) = common_dir {
+            let path =
+                crate::approval_context::normalize(&Path::new(worktree_path).join(common_dir));
+            if !path.starts_with(worktree_path) {
+                writable.push(path.to_string_lossy().to_string());
+            }
+        }
+
+        if let Some(home) = std::env::var_os("HOME") {
+            let home = PathBuf::from(home);
+            writable.push(home.join(".claude").to_string_lossy().to_string());
+            writable.push(home.join(".claude.json").to_string_lossy().to_string());
+        }
+
+        Self {
+            writable,
+            read_only: Vec::new(),
+        }
+    }
+}
+
+/// The same launch run inside bubblewrap. Binds are applied in order, so
+/// the writable paths are mounted over the read-only root.
+pub fn wrap_launch(
+    spec: &AgentLaunchSpec,
+    mounts: &SandboxMounts,
+    bridge: Option<&NetworkBridge>,
+) -> AgentLaunchSpec {
+    let mut args: Vec<String> = [
+        "--die-with-parent",
+        "--unshare-pid",
+        "--unshare-ipc",
+        "--unshare-uts",
+        "--ro-bind",
+        "/",
+        "/",
+        "--dev",
+        "/dev",
+        "--proc",
+        "/proc",
+        "--tmpfs",
+        "/tmp",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+
+    let mut bind = |flag: &str, path: &str| {
+        args.extend([flag.to_string(), path.to_string(), path.to_string()]);
+    };
+    for path in &mounts.read_only {
+        bind("--ro-bind-try", path);
+    }
+    for path in &mounts.writable {
+        bind("--bind-try", path);
+    }
+    if let Some(bridge) = bridge {
+        bind("--bind", &bridge.dir.to_string_lossy());
+    }
+
+    if bridge.is_some() {
+        args.push("--unshare-net".to_string());
+    }
+    args.extend(["--chdir".to_string(), spec.current_dir.clone()]);
+    args.push("--".to_string());
+
+    let mut envs = spec.envs.clone();
+    match bridge {
+        Some(bridge) => {
+            // Start the port bridges, wait for them, then replace the shell with the agent
+            args.extend([
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("{}exec \"$@\"", bridge.startup_script()),
+                "sh".to_string(),
+                spec.program.clone(),
+            ]);
+            let proxy = format!("http://127.0.0.1:{SANDBOX_PROXY_PORT}");
+            for name in ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"] {
+                envs.push((name.to_string(), proxy.clone()));
+            }
+            for name in ["NO_PROXY", "no_proxy"] {
+                envs.push((name.to_string(), "localhost,127.0.0.1,::1".to_string()));
+            }
+        }
+        None => args.push(spec.program.clone()),
+    }
+    args.extend(spec.args.iter().cloned());
+
+    AgentLaunchSpec {
+        program: SANDBOX_PROGRAM.to_string(),
+        args,
+        current_dir: spec.current_dir.clone(),
+        envs,
+    }
+}
+
+/// Whether `host:port` is one of `allowed`, where `*.example.com` covers
+/// subdomains and an entry without a port allows only [`HTTPS_PORT`].
+pub fn host_allowed(host: &str, port: u16, allowed: &[String]) -> bool {
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+    allowed.iter().any(|entry| {
+        let (pattern, allowed_port) = split_port(entry);
+        let host_matches = match pattern.strip_prefix("*.") {
+            Some(domain) => host.ends_with(&format!(".{domain}")),
+            None => host == pattern,
+        };
+        host_matches && port == allowed_port
+    })
+}
+
+/// An allowlist entry's host and port: `host:8443`, `[::1]:8443`, or just a
+/// host for [`HTTPS_PORT`].
+fn split_port(entry: &str) -> (&str, u16) {
+    let (host, port) = match entry.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((host, rest)) => (host, rest.strip_prefix(':')),
+            None => (bracketed, None),
+        },
+        None => match entry.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (entry, None),
+        },
+    };
+    match port.map(str::parse) {
+        Some(Ok(port)) => (host, port),
+        // A malformed port allows nothing rather than every port
+        Some(Err(_)) => (host, 0),
+        None => (host, HTTPS_PORT),
+    }
+}
+
+/// The `host` and `port` of a `CONNECT host:port HTTP/1.1` request line.
+pub fn parse_connect(request_line: &str) -> Option<(String, u16)> {
+    let mut parts = request_line.split_whitespace();
+    if !parts.next()?.eq_ignore_ascii_case("CONNECT") {
+        return None;
+    }
+    let (host, port) = parts.next()?.rsplit_once(':')?;
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+/// Orchestra's side of a network-restricted sandbox: unix sockets, bound
+/// into the sandbox, that forward Orchestra's local ports and proxy HTTPS to
+/// the allowed hosts. Everything stops when the bridge is dropped.
+pub struct NetworkBridge {
+    dir: PathBuf,
+    ports: Vec<u16>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl NetworkBridge {
+    #[cfg(not(unix))]
+    pub fn start(
+        _process_id: &str,
+        _local_ports: &[u16],
+        _allowed_hosts: Vec<String>,
+        _on_violation: Arc<dyn Fn(SandboxViolation) + Send + Sync>,
+    ) -> Result<Self, String> {
+        Err("Network-restricted sandboxes are only supported on Linux".to_string())
+    }
+
+    /// Must be called from within the tokio runtime.
+    #[cfg(unix)]
+    pub fn start(
+        process_id: &str,
+        local_ports: &[u16],
+        allowed_hosts: Vec<String>,
+        on_violation: Arc<dyn Fn(SandboxViolation) + Send + Sync>,
+    ) -> Result<Self, String> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        // Private to this user, and `create` fails rather than reuse a
+        // directory someone else made
+        let dir = std::env::temp_dir().join(format!("orchestra-sandbox-{process_id}"));
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        let mut bridge = Self {
+            dir,
+            ports: Vec::new(),
+            tasks: Vec::new(),
+        };
+
+        for &port in local_ports {
+            if bridge.ports.contains(&port) {
+                continue;
+            }
+            let listener = bridge.bind(&format!("port-{port}.sock"))?;
+            bridge.tasks.push(tokio::spawn(async move {
+                while let Ok((mut client, _)) = listener.accept().await {
+                    tokio::spawn(async move {
+                        if let Ok(mut server) = TcpStream::connect(("127.0.0.1", port)).await {
+                            let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                        }
+                    });
+                }
+            }));
+            bridge.ports.push(port);
+        }
+
+        let listener = bridge.bind("proxy.sock")?;
+        let allowed_hosts = Arc::new(allowed_hosts);
+        bridge.tasks.push(tokio::spawn(async move {
+            while let Ok((client, _)) = listener.accept().await {
+                let allowed_hosts = allowed_hosts.clone();
+                let on_violation = on_violation.clone();
+                tokio::spawn(async move {
+                    if let Some(violation) = proxy_client(client, &allowed_hosts).await {
+                        on_violation(violation);
+                    }
+                });
+            }
+        }));
+
+        Ok(bridge)
+    }
+
+    #[cfg(unix)]
+    fn bind(&self, name: &str) -> Result<UnixListener, String> {
+        let path = self.dir.join(name);
+        let _ = std::fs::remove_file(&path);
+        UnixListener::bind(&path)
+            .map_err(|e| format!("Failed to listen on {}: {e}", path.display()))
+    }
+
+    pub fn socket_path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Shell commands, run inside the sandbox, that listen on the forwarded
+    /// ports and the proxy port and connect them to this bridge's sockets,
+    /// then wait until every port is listening.
+    fn startup_script(&self) -> String {
+        let listen = |port: u16, socket: &str| {
+            format!(
+                "{BRIDGE_PROGRAM} TCP6-LISTEN:{port},ipv6only=0,reuseaddr,fork UNIX-CONNECT:'{}' & ",
+                self.socket_path(socket).display()
+            )
+        };
+        // The sandbox has its own network, so /proc/net/tcp6 lists only these
+        // listeners (state 0A), by port in hex
+        let wait = |port: u16| {
+            format!(
+                "until grep -q ':{port:04X} [0-9A-F]*:0000 0A' /proc/net/tcp6 2>/dev/null || [ $n -ge {BRIDGE_STARTUP_CHECKS} ]; do sleep 0.1; n=$((n+1)); done; "
+            )
+        };
+        let mut script: String = self
+            .ports
+            .iter()
+            .map(|&port| listen(port, &format!("port-{port}.sock")))
+            .collect();
+        script.push_str(&listen(SANDBOX_PROXY_PORT, "proxy.sock"));
+        script.push_str("n=0; ");
+        for &port in self.ports.iter().chain([SANDBOX_PROXY_PORT].iter()) {
+            script.push_str(&wait(port));
+        }
+        script
+    }
+}
+
+impl Drop for NetworkBridge {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Serve one proxy client: tunnel `CONNECT` requests to allowed hosts and
+/// refuse everything else. Returns the violation when a request is refused.
+#[cfg(unix)]
+async fn proxy_client(
+    mut client: UnixStream,
+    allowed_hosts: &[String],
+) -> Option<SandboxViolation> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = client.read(&mut buffer).await.ok()?;
+        if read == 0 || head.len() > MAX_PROXY_HEAD_BYTES {
+            return None;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    let head = String::from_utf8_lossy(&head).to_string();
+    let request_line = head.lines().next().unwrap_or("");
+
+    let refusal = match parse_connect(request_line) {
+        Some((host, port)) if host_allowed(&host, port, allowed_hosts) => {
+            match TcpStream::connect((host.as_str(), port)).await {
+                Ok(mut server) => {
+                    client
+                        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                        .await
+                        .ok()?;
+                    let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                    return None;
+                }
+                Err(_) => {
+                    let _ = client
+                        .write_all(b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n")
+                        .await;
+                    return None;
+                }
+            }
+        }
+        Some((host, port)) => {
+            format!("Blocked a connection to {host}:{port}, which is not an allowed host and port")
+        }
+        None => format!(
+            "Blocked a request that was not an HTTPS tunnel: {}",
+            request_line.chars().take(200).collect::<String>()
+        ),
+    };
+
+    let body = format!("Orchestra sandbox: {refusal}\n");
+    let _ = client
+        .write_all(
+            format!(
+                "HTTP/1.1 403 Forbidden\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await;
+    Some(SandboxViolation {
+        kind: SandboxViolationKind::Network,
+        detail: refusal,
+    })
+}
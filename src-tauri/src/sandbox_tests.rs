@@ -0,0 +1,258 @@
+#[cfg(test)]
+mod tests {
+    use crate::agent_backends::AgentLaunchSpec;
+    use crate::sandbox::{
+        host_allowed, parse_connect, wrap_launch, NetworkBridge, SandboxMounts, SandboxNetwork,
+        SandboxOptions, SandboxViolation, SandboxViolationKind,
+    };
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, UnixStream};
+
+    fn claude_spec() -> AgentLaunchSpec {
+        AgentLaunchSpec {
+            program: "claude".to_string(),
+            args: vec!["--print".to_string(), "hello".to_string()],
+            current_dir: "/work/feature".to_string(),
+            envs: vec![("ORCHESTRA_PROCESS_ID".to_string(), "p1".to_string())],
+        }
+    }
+
+    fn mounts() -> SandboxMounts {
+        SandboxMounts {
+            writable: vec!["/work/feature".to_string(), "/work/repo/.git".to_string()],
+            read_only: vec!["/tmp/mcp-p1.json".to_string()],
+        }
+    }
+
+    fn position(args: &[String], window: &[&str]) -> usize {
+        args.windows(window.len())
+            .position(|candidate| candidate == window)
+            .unwrap_or_else(|| panic!("{window:?} not in {args:?}"))
+    }
+
+    #[test]
+    fn test_wrap_launch_mounts_worktree_over_read_only_root() {
+        let wrapped = wrap_launch(&claude_spec(), &mounts(), None);
+        let args = &wrapped.args;
+
+        assert_eq!(wrapped.program, "bwrap");
+        assert_eq!(wrapped.current_dir, "/work/feature");
+        let root = position(args, &["--ro-bind", "/", "/"]);
+        let tmp = position(args, &["--tmpfs", "/tmp"]);
+        let config = position(
+            args,
+            &["--ro-bind-try", "/tmp/mcp-p1.json", "/tmp/mcp-p1.json"],
+        );
+        let worktree = position(args, &["--bind-try", "/work/feature", "/work/feature"]);
+        position(args, &["--bind-try", "/work/repo/.git", "/work/repo/.git"]);
+        assert!(root < tmp && tmp < config && root < worktree);
+        position(args, &["--chdir", "/work/feature"]);
+
+        // Without a bridge the network is shared and the agent runs directly
+        assert!(!args.contains(&"--unshare-net".to_string()));
+        let separator = position(args, &["--"]);
+        assert_eq!(&args[separator + 1..], ["claude", "--print", "hello"]);
+        assert_eq!(wrapped.envs, claude_spec().envs);
+    }
+
+    #[tokio::test]
+    async fn test_wrap_launch_with_network_bridge() {
+        let bridge = NetworkBridge::start(
+            "sandbox-test-wrap",
+            &[8080, 8080, 9001],
+            Vec::new(),
+            Arc::new(|_| {}),
+        )
+        .unwrap();
+        let wrapped = wrap_launch(&claude_spec(), &mounts(), Some(&bridge));
+        let args = &wrapped.args;
+
+        assert!(args.contains(&"--unshare-net".to_string()));
+        let dir = bridge
+            .socket_path("")
+            .to_string_lossy()
+            .trim_end_matches('/')
+            .to_string();
+        position(args, &["--bind", &dir, &dir]);
+        let dir_mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(dir_mode & 0o777, 0o700);
+
+        let separator = position(args, &["--"]);
+        assert_eq!(args[separator + 1..separator + 3], ["sh", "-c"]);
+        let script = &args[separator + 3];
+        assert_eq!(script.matches("TCP6-LISTEN:8080").count(), 1);
+        assert!(script.contains("TCP6-LISTEN:9001"));
+        assert!(script.contains("TCP6-LISTEN:3128"));
+        // The agent starts only once the listeners are up
+        let exec = script.find("exec \"$@\"").unwrap();
+        assert!(script[..exec].contains("grep -q ':2329 "));
+        assert!(script[..exec].contains("grep -q ':0C38 "));
+        assert!(script.ends_with("exec \"$@\""));
+        assert_eq!(&args[separator + 4..], ["sh", "claude", "--print", "hello"]);
+
+        let env = |name: &str| {
+            wrapped
+                .envs
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(env("HTTPS_PROXY").as_deref(), Some("http://127.0.0.1:3128"));
+        assert_eq!(env("NO_PROXY").as_deref(), Some("localhost,127.0.0.1,::1"));
+        assert_eq!(env("ORCHESTRA_PROCESS_ID").as_deref(), Some("p1"));
+
+        drop(bridge);
+        assert!(!std::path::Path::new(&dir).exists());
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        let options = SandboxOptions {
+            network: SandboxNetwork::Restricted,
+            allowed_hosts: vec![" *.Example.com ".to_string(), String::new()],
+        };
+        let allowed = options.allowed_hosts();
+        assert_eq!(allowed, ["api.anthropic.com", "*.example.com"]);
+
+        assert!(host_allowed("api.anthropic.com", 443, &allowed));
+        assert!(host_allowed("API.Anthropic.com", 443, &allowed));
+        assert!(host_allowed("registry.example.com", 443, &allowed));
+        assert!(!host_allowed("example.com", 443, &allowed));
+        assert!(!host_allowed("evil-example.com", 443, &allowed));
+        assert!(!host_allowed("api.anthropic.com.evil.net", 443, &allowed));
+        assert!(!host_allowed("api.anthropic.com", 22, &allowed));
+    }
+
+    #[test]
+    fn test_allowed_host_ports() {
+        let allowed = vec![
+            "registry.example.com:8443".to_string(),
+            "[::1]:8080".to_string(),
+            "bad.example.com:ssh".to_string(),
+        ];
+        assert!(host_allowed("registry.example.com", 8443, &allowed));
+        assert!(!host_allowed("registry.example.com", 443, &allowed));
+        assert!(host_allowed("[::1]", 8080, &allowed));
+        assert!(!host_allowed("::1", 443, &allowed));
+        assert!(!host_allowed("bad.example.com", 22, &allowed));
+        assert!(!host_allowed("bad.example.com", 443, &allowed));
+    }
+
+    #[test]
+    fn test_parse_connect() {
+        assert_eq!(
+            parse_connect("CONNECT api.anthropic.com:443 HTTP/1.1"),
+            Some(("api.anthropic.com".to_string(), 443))
+        );
+        assert_eq!(
+            parse_connect("connect [::1]:8443 HTTP/1.1"),
+            Some(("[::1]".to_string(), 8443))
+        );
+        assert_eq!(parse_connect("GET http://example.com/ HTTP/1.1"), None);
+        assert_eq!(parse_connect("CONNECT example.com HTTP/1.1"), None);
+    }
+
+    #[test]
+    fn test_filesystem_violations_from_output() {
+        let violation = SandboxViolation::from_output_line(
+            "touch: cannot touch '/etc/hosts': Read-only file system",
+        )
+        .unwrap();
+        assert_eq!(violation.kind, SandboxViolationKind::Filesystem);
+        assert!(violation.detail.contains("/etc/hosts"));
+
+        assert!(
+            SandboxViolation::from_output_line("EROFS: read-only file system, open '/usr/x'")
+                .is_some()
+        );
+        assert!(SandboxViolation::from_output_line("Permission denied").is_none());
+    }
+
+    async fn read_response(stream: &mut UnixStream) -> String {
+        let mut response = vec![0u8; 1024];
+        let read = stream.read(&mut response).await.unwrap();
+        String::from_utf8_lossy(&response[..read]).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_network_bridge_forwards_and_filters() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = server.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = server.accept().await {
+                tokio::spawn(async move {
+                    let mut buffer = [0u8; 64];
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    socket.write_all(&buffer[..read]).await.unwrap();
+                });
+            }
+        });
+
+        let violations = Arc::new(Mutex::new(Vec::new()));
+        let recorded = violations.clone();
+        let bridge = NetworkBridge::start(
+            "sandbox-test-bridge",
+            &[port],
+            vec!["127.0.0.1".to_string(), format!("127.0.0.1:{port}")],
+            Arc::new(move |violation| recorded.lock().unwrap().push(violation)),
+        )
+        .unwrap();
+
+        // Forwarded local port
+        let mut forwarded = UnixStream::connect(bridge.socket_path(&format!("port-{port}.sock")))
+            .await
+            .unwrap();
+        forwarded.write_all(b"ping").await.unwrap();
+        assert_eq!(read_response(&mut forwarded).await, "ping");
+
+        // Tunnel to an allowed host
+        let mut tunnel = UnixStream::connect(bridge.socket_path("proxy.sock"))
+            .await
+            .unwrap();
+        tunnel
+            .write_all(format!("CONNECT 127.0.0.1:{port} HTTP/1.1\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        assert!(read_response(&mut tunnel).await.starts_with("HTTP/1.1 200"));
+        tunnel.write_all(b"pong").await.unwrap();
+        assert_eq!(read_response(&mut tunnel).await, "pong");
+
+        // Anything else is refused and reported
+        let mut refused = UnixStream::connect(bridge.socket_path("proxy.sock"))
+            .await
+            .unwrap();
+        refused
+            .write_all(b"CONNECT evil.example.com:443 HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let response = read_response(&mut refused).await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+        assert!(response.contains("evil.example.com:443"));
+
+        // An allowed host on a port that is not allowed
+        let mut refused = UnixStream::connect(bridge.socket_path("proxy.sock"))
+            .await
+            .unwrap();
+        refused
+            .write_all(b"CONNECT 127.0.0.1:22 HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let response = read_response(&mut refused).await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let violations = violations.lock().unwrap();
+        assert_eq!(violations.len(), 2);
+        assert!(violations
+            .iter()
+            .all(|violation| violation.kind == SandboxViolationKind::Network));
+        assert!(violations
+            .iter()
+            .any(|violation| violation.detail.contains("evil.example.com")));
+        assert!(violations
+            .iter()
+            .any(|violation| violation.detail.contains("127.0.0.1:22")));
+    }
+}
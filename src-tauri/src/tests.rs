@@ -1,6 +1,17 @@
 #[cfg(test)]
 mod unit_tests {
+    use crate::agent_backends::AgentBackendRegistry;
+    use crate::claude_errors::RetryPolicy;
+    use crate::diagnostics::ClaudeVersionGate;
+    use crate::launch_profiles::LaunchProfileStore;
+    use crate::logging::Logging;
+    use crate::mcp_config::ExtraMcpServerStore;
     use crate::mcp_manager::{ApprovalRequest, McpManager};
+    use crate::metrics::RunMetrics;
+    use crate::process_metrics::MetricsSampler;
+    use crate::protected_paths::ProtectedPathStore;
+    use crate::redaction::Redactor;
+    use crate::resource_limits::ResourceLimits;
     use crate::{parse_claude_json_line, AppState, ClaudeProcess, ProcessOutput, WorktreeConfig};
     use chrono::Utc;
     use std::collections::HashMap;
@@ -12,6 +23,17 @@ mod unit_tests {
             processes: Mutex::new(HashMap::new()),
             running_processes: Mutex::new(HashMap::new()),
             mcp_manager: McpManager::new(),
+            retry_policy: Mutex::new(RetryPolicy::default()),
+            launch_profiles: Mutex::new(LaunchProfileStore::new()),
+            agent_backends: AgentBackendRegistry::new(),
+            claude_version_gate: ClaudeVersionGate::new(),
+            extra_mcp_servers: Mutex::new(ExtraMcpServerStore::new()),
+            protected_paths: Mutex::new(ProtectedPathStore::new()),
+            resource_limits: Mutex::new(ResourceLimits::default()),
+            process_metrics: Mutex::new(MetricsSampler::new()),
+            run_metrics: Mutex::new(RunMetrics::default()),
+            logging: Logging::default(),
+            redactor: Mutex::new(Arc::new(Redactor::default())),
         }
     }
 
@@ -36,6 +58,15 @@ mod unit_tests {
             task: Some("test task".to_string()),
             started_at: Some(Utc::now().to_rfc3339()),
             last_activity: Some(Utc::now().to_rfc3339()),
+            launch_profile: None,
+            backend: "claude".to_string(),
+            session_id: None,
+            read_only: false,
+            read_only_violations: Vec::new(),
+            sandbox: None,
+            resource_limits: None,
+            resource_usage: None,
+            redactions: 0,
         }
     }
 
@@ -79,6 +110,15 @@ mod unit_tests {
             task: Some("test task".to_string()),
             started_at: Some("2024-01-01T00:00:00Z".to_string()),
             last_activity: Some("2024-01-01T00:01:00Z".to_string()),
+            launch_profile: None,
+            backend: "claude".to_string(),
+            session_id: None,
+            read_only: false,
+            read_only_violations: Vec::new(),
+            sandbox: None,
+            resource_limits: None,
+            resource_usage: None,
+            redactions: 0,
         };
 
         // Test that serialization works
@@ -340,6 +380,7 @@ mod unit_tests {
             tool_name: "test_tool".to_string(),
             input: serde_json::json!({"test": "data"}),
             worktree_id: "test-worktree".to_string(),
+            process_id: None,
             timestamp: chrono::Utc::now().timestamp() as u64,
         };
 
@@ -0,0 +1,323 @@
+use serde_json::Value;
+
+/// Longest `timeout` Claude accepts for a Bash command, in milliseconds.
+pub const MAX_BASH_TIMEOUT_MS: u64 = 600_000;
+
+#[derive(Debug, Clone, Copy)]
+pub enum FieldKind {
+    String,
+    /// A string that must be an absolute path.
+    AbsolutePath,
+    /// An `http` or `https` URL.
+    Url,
+    Integer,
+    Number,
+    Boolean,
+    Enum(&'static [&'static str]),
+    StringArray,
+    /// An array of objects that each follow the given fields.
+    ObjectArray(&'static [FieldSpec]),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+const fn required(name: &'static str, kind: FieldKind) -> FieldSpec {
+    FieldSpec {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, kind: FieldKind) -> FieldSpec {
+    FieldSpec {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+/// The input fields of one of Claude's built-in tools.
+#[derive(Debug)]
+pub struct ToolSchema {
+    pub tool_name: &'static str,
+    pub fields: &'static [FieldSpec],
+}
+
+const EDIT_FIELDS: &[FieldSpec] = &[
+    required("old_string", FieldKind::String),
+    required("new_string", FieldKind::String),
+    optional("replace_all", FieldKind::Boolean),
+];
+
+const TODO_FIELDS: &[FieldSpec] = &[
+    required("content", FieldKind::String),
+    required(
+        "status",
+        FieldKind::Enum(&["pending", "in_progress", "completed"]),
+    ),
+    optional("activeForm", FieldKind::String),
+    optional("id", FieldKind::String),
+    optional("priority", FieldKind::Enum(&["high", "medium", "low"])),
+];
+
+pub const TOOL_SCHEMAS: &[ToolSchema] = &[
+    ToolSchema {
+        tool_name: "Bash",
+        fields: &[
+            required("command", FieldKind::String),
+            optional("timeout", FieldKind::Number),
+            optional("description", FieldKind::String),
+            optional("run_in_background", FieldKind::Boolean),
+        ],
+    },
+    ToolSchema {
+        tool_name: "Read",
+        fields: &[
+            required("file_path", FieldKind::AbsolutePath),
+            optional("offset", FieldKind::Integer),
+            optional("limit", FieldKind::Integer),
+        ],
+    },
+    ToolSchema {
+        tool_name: "Write",
+        fields: &[
+            required("file_path", FieldKind::AbsolutePath),
+            required("content", FieldKind::String),
+        ],
+    },
+    ToolSchema {
+        tool_name: "Edit",
+        fields: &[
+            required("file_path", FieldKind::AbsolutePath),
+            required("old_string", FieldKind::String),
+            required("new_string", FieldKind::String),
+            optional("replace_all", FieldKind::Boolean),
+        ],
+    },
+    ToolSchema {
+        tool_name: "MultiEdit",
+        fields: &[
+            required("file_path", FieldKind::AbsolutePath),
+            required("edits", FieldKind::ObjectArray(EDIT_FIELDS)),
+        ],
+    },
+    ToolSchema {
+        tool_name: "Glob",
+        fields: &[
+            required("pattern", FieldKind::String),
+            optional("path", FieldKind::String),
+        ],
+    },
+    ToolSchema {
+        tool_name: "Grep",
+        fields: &[
+            required("pattern", FieldKind::String),
+            optional("path", FieldKind::String),
+            optional("glob", FieldKind::String),
+            optional("type", FieldKind::String),
+            optional(
+                "output_mode",
+                FieldKind::Enum(&["content", "files_with_matches", "count"]),
+            ),
+            optional("-i", FieldKind::Boolean),
+            optional("-n", FieldKind::Boolean),
+            optional("-A", FieldKind::Integer),
+            optional("-B", FieldKind::Integer),
+            optional("-C", FieldKind::Integer),
+            optional("multiline", FieldKind::Boolean),
+            optional("head_limit", FieldKind::Integer),
+        ],
+    },
+    ToolSchema {
+        tool_name: "LS",
+        fields: &[
+            required("path", FieldKind::AbsolutePath),
+            optional("ignore", FieldKind::StringArray),
+        ],
+    },
+    ToolSchema {
+        tool_name: "WebFetch",
+        fields: &[
+            required("url", FieldKind::Url),
+            required("prompt", FieldKind::String),
+        ],
+    },
+    ToolSchema {
+        tool_name: "WebSearch",
+        fields: &[
+            required("query", FieldKind::String),
+            optional("allowed_domains", FieldKind::StringArray),
+            optional("blocked_domains", FieldKind::StringArray),
+        ],
+    },
+    ToolSchema {
+        tool_name: "NotebookEdit",
+        fields: &[
+            required("notebook_path", FieldKind::AbsolutePath),
+            required("new_source", FieldKind::String),
+            optional("cell_id", FieldKind::String),
+            optional("cell_type", FieldKind::Enum(&["code", "markdown"])),
+            optional(
+                "edit_mode",
+                FieldKind::Enum(&["replace", "insert", "delete"]),
+            ),
+        ],
+    },
+    ToolSchema {
+        tool_name: "TodoWrite",
+        fields: &[required("todos", FieldKind::ObjectArray(TODO_FIELDS))],
+    },
+    ToolSchema {
+        tool_name: "Task",
+        fields: &[
+            required("description", FieldKind::String),
+            required("prompt", FieldKind::String),
+            required("subagent_type", FieldKind::String),
+        ],
+    },
+];
+
+pub fn tool_schema(tool_name: &str) -> Option<&'static ToolSchema> {
+    TOOL_SCHEMAS
+        .iter()
+        .find(|schema| schema.tool_name == tool_name)
+}
+
+/// Check tool input against the tool's schema, reporting every problem at
+/// once. Tools without a known schema (e.g. MCP tools) are not checked.
+pub fn validate_tool_input(tool_name: &str, input: &Value) -> Result<(), String> {
+    let Some(schema) = tool_schema(tool_name) else {
+        return Ok(());
+    };
+
+    let mut errors = Vec::new();
+    validate_object(input, schema.fields, "", &mut errors);
+    if errors.is_empty() {
+        validate_tool_rules(tool_name, input, &mut errors);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid input for {tool_name}: {}",
+            errors.join("; ")
+        ))
+    }
+}
+
+fn validate_object(value: &Value, fields: &[FieldSpec], path: &str, errors: &mut Vec<String>) {
+    let Some(object) = value.as_object() else {
+        errors.push(format!("{} must be an object", display_path(path)));
+        return;
+    };
+
+    for field in fields {
+        let field_path = format!("{path}{}", field.name);
+        match object.get(field.name) {
+            None | Some(Value::Null) if field.required => {
+                errors.push(format!("missing required field '{field_path}'"));
+            }
+            None | Some(Value::Null) => {}
+            Some(value) => validate_field(value, field.kind, &field_path, errors),
+        }
+    }
+
+    for name in object.keys() {
+        if !fields.iter().any(|field| field.name == name) {
+            errors.push(format!("unknown field '{path}{name}'"));
+        }
+    }
+}
+
+fn validate_field(value: &Value, kind: FieldKind, path: &str, errors: &mut Vec<String>) {
+    let expected = match kind {
+        FieldKind::String => (!value.is_string()).then_some("a string"),
+        FieldKind::AbsolutePath => match value.as_str() {
+            None => Some("a string"),
+            Some(path) if !std::path::Path::new(path).is_absolute() => Some("an absolute path"),
+            Some(_) => None,
+        },
+        FieldKind::Url => match value.as_str() {
+            None => Some("a string"),
+            Some(url) if !(url.starts_with("http://") || url.starts_with("https://")) => {
+                Some("an http or https URL")
+            }
+            Some(_) => None,
+        },
+        FieldKind::Integer => (!value.is_u64()).then_some("a non-negative integer"),
+        FieldKind::Number => (!value.is_number()).then_some("a number"),
+        FieldKind::Boolean => (!value.is_boolean()).then_some("a boolean"),
+        FieldKind::Enum(options) => {
+            if value.as_str().is_some_and(|v| options.contains(&v)) {
+                None
+            } else {
+                errors.push(format!("'{path}' must be one of: {}", options.join(", ")));
+                return;
+            }
+        }
+        FieldKind::StringArray => match value.as_array() {
+            Some(items) if items.iter().all(Value::is_string) => None,
+            _ => Some("an array of strings"),
+        },
+        FieldKind::ObjectArray(fields) => match value.as_array() {
+            Some(items) if items.is_empty() => Some("a non-empty array"),
+            Some(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    validate_object(item, fields, &format!("{path}[{index}]."), errors);
+                }
+                None
+            }
+            None => Some("an array"),
+        },
+    };
+
+    if let Some(expected) = expected {
+        errors.push(format!("'{path}' must be {expected}"));
+    }
+}
+
+/// Rules beyond field types that Claude's tools enforce.
+fn validate_tool_rules(tool_name: &str, input: &Value, errors: &mut Vec<String>) {
+    let edits: Vec<&Value> = match tool_name {
+        "Edit" => vec![input],
+        "MultiEdit" => input["edits"].as_array().into_iter().flatten().collect(),
+        "Bash" => {
+            if input["command"]
+                .as_str()
+                .is_some_and(|c| c.trim().is_empty())
+            {
+                errors.push("'command' cannot be empty".to_string());
+            }
+            if input["timeout"]
+                .as_f64()
+                .is_some_and(|timeout| timeout <= 0.0 || timeout > MAX_BASH_TIMEOUT_MS as f64)
+            {
+                errors.push(format!(
+                    "'timeout' must be between 1 and {MAX_BASH_TIMEOUT_MS} ms"
+                ));
+            }
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+
+    for edit in edits {
+        if edit["old_string"] == edit["new_string"] {
+            errors.push("'old_string' and 'new_string' must differ".to_string());
+        }
+    }
+}
+
+fn display_path(path: &str) -> String {
+    match path.trim_end_matches('.') {
+        "" => "input".to_string(),
+        path => format!("'{path}'"),
+    }
+}
@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod tests {
+    use crate::approval_broker::{ApprovalBroker, ApprovalRecord, ApprovalState};
+    use crate::mcp_manager::{ApprovalBehavior, ApprovalResponse};
+    use crate::tool_schemas::{tool_schema, validate_tool_input, TOOL_SCHEMAS};
+    use serde_json::json;
+
+    fn allow_with_input(input: serde_json::Value) -> ApprovalResponse {
+        ApprovalResponse {
+            behavior: ApprovalBehavior::Allow,
+            message: None,
+            updated_input: Some(input),
+            grant: None,
+        }
+    }
+
+    #[test]
+    fn test_builtin_tools_have_schemas() {
+        for tool in [
+            "Bash",
+            "Read",
+            "Write",
+            "Edit",
+            "MultiEdit",
+            "Glob",
+            "Grep",
+            "WebFetch",
+            "WebSearch",
+        ] {
+            assert!(tool_schema(tool).is_some(), "{tool} has no schema");
+        }
+        let mut names: Vec<_> = TOOL_SCHEMAS.iter().map(|s| s.tool_name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), TOOL_SCHEMAS.len());
+    }
+
+    #[test]
+    fn test_valid_inputs_pass() {
+        let cases = [
+            ("Bash", json!({ "command": "npm test", "timeout": 60000 })),
+            (
+                "Read",
+                json!({ "file_path": "/repo/src/main.rs", "offset": 10 }),
+            ),
+            (
+                "Write",
+                json!({ "file_path": "/repo/a.txt", "content": "" }),
+            ),
+            (
+                "Edit",
+                json!({ "file_path": "/repo/a.txt", "old_string": "a", "new_string": "b" }),
+            ),
+            (
+                "MultiEdit",
+                json!({
+                    "file_path": "/repo/a.txt",
+                    "edits": [{ "old_string": "a", "new_string": "b", "replace_all": true }]
+                }),
+            ),
+            (
+                "Grep",
+                json!({ "pattern": "fn main", "-n": true, "output_mode": "content" }),
+            ),
+            (
+                "WebFetch",
+                json!({ "url": "https://example.com", "prompt": "Summarize" }),
+            ),
+        ];
+        for (tool, input) in cases {
+            assert_eq!(validate_tool_input(tool, &input), Ok(()), "{tool}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_tools_are_not_checked() {
+        assert!(validate_tool_input("mcp__github__create_issue", &json!("anything")).is_ok());
+    }
+
+    #[test]
+    fn test_errors_name_every_problem() {
+        let error = validate_tool_input(
+            "Edit",
+            &json!({ "file_path": "relative.txt", "new_string": 3, "extra": true }),
+        )
+        .unwrap_err();
+        assert!(error.starts_with("Invalid input for Edit: "));
+        assert!(error.contains("'file_path' must be an absolute path"));
+        assert!(error.contains("missing required field 'old_string'"));
+        assert!(error.contains("'new_string' must be a string"));
+        assert!(error.contains("unknown field 'extra'"));
+    }
+
+    #[test]
+    fn test_nested_edits_are_checked() {
+        let error = validate_tool_input(
+            "MultiEdit",
+            &json!({ "file_path": "/repo/a.txt", "edits": [{ "old_string": "a" }, 5] }),
+        )
+        .unwrap_err();
+        assert!(error.contains("missing required field 'edits[0].new_string'"));
+        assert!(error.contains("'edits[1]' must be an object"));
+
+        let error = validate_tool_input("MultiEdit", &json!({ "file_path": "/a", "edits": [] }))
+            .unwrap_err();
+        assert!(error.contains("'edits' must be a non-empty array"));
+    }
+
+    #[test]
+    fn test_tool_rules() {
+        assert!(validate_tool_input(
+            "Edit",
+            &json!({ "file_path": "/a", "old_string": "x", "new_string": "x" })
+        )
+        .unwrap_err()
+        .contains("must differ"));
+        assert!(validate_tool_input("Bash", &json!({ "command": "  " }))
+            .unwrap_err()
+            .contains("cannot be empty"));
+        assert!(
+            validate_tool_input("Bash", &json!({ "command": "ls", "timeout": 900000 }))
+                .unwrap_err()
+                .contains("'timeout'")
+        );
+        assert!(
+            validate_tool_input("Read", &json!({ "file_path": "/a", "limit": -1 }))
+                .unwrap_err()
+                .contains("non-negative integer")
+        );
+        assert!(
+            validate_tool_input("WebFetch", &json!({ "url": "file:///etc", "prompt": "p" }))
+                .unwrap_err()
+                .contains("http or https")
+        );
+        assert!(
+            validate_tool_input("Grep", &json!({ "pattern": "x", "output_mode": "lines" }))
+                .unwrap_err()
+                .contains("must be one of")
+        );
+    }
+
+    #[test]
+    fn test_invalid_updated_input_keeps_request_pending() {
+        let broker = ApprovalBroker::default();
+        let _receiver = broker
+            .submit(ApprovalRecord::new(
+                "a1".to_string(),
+                "Write".to_string(),
+                json!({ "file_path": "/repo/a.txt", "content": "old" }),
+                "wt-1".to_string(),
+                1,
+            ))
+            .unwrap();
+
+        let error = broker
+            .respond(
+                "a1",
+                allow_with_input(json!({ "file_path": "/repo/a.txt" })),
+            )
+            .unwrap_err();
+        assert!(error.contains("missing required field 'content'"));
+        assert_eq!(broker.get("a1").unwrap().state, ApprovalState::Pending);
+
+        let record = broker
+            .respond(
+                "a1",
+                allow_with_input(json!({ "file_path": "/repo/a.txt", "content": "new" })),
+            )
+            .unwrap();
+        assert_eq!(record.state, ApprovalState::Answered);
+    }
+
+    #[test]
+    fn test_denials_skip_validation() {
+        let broker = ApprovalBroker::default();
+        let _receiver = broker
+            .submit(ApprovalRecord::new(
+                "a2".to_string(),
+                "Bash".to_string(),
+                json!({ "command": "ls" }),
+                "wt-1".to_string(),
+                1,
+            ))
+            .unwrap();
+        let mut response = allow_with_input(json!({}));
+        response.behavior = ApprovalBehavior::Deny;
+        assert!(broker.respond("a2", response).is_ok());
+    }
+}
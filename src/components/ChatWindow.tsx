@@ -1,5 +1,5 @@
 import { useState, useRef, useEffect } from 'react';
-import { WorktreeConfig, WorktreeChat, ChatMessage, ApprovalRequest, ApprovalResponse } from '../types';
+import { WorktreeConfig, WorktreeChat, ChatMessage, ApprovalRequest, ApprovalResponse, ClaudeProcessStatusEvent } from '../types';
 import { tauriService } from '../services/tauri';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { Loader, CheckCircle, XCircle, AlertCircle } from 'lucide-react';
@@ -62,13 +62,9 @@ export default function ChatWindow({ worktree, chat, onChatUpdated }: ChatWindow
       }
     };
 
+    // The server is shared by every chat on this worktree and stopped when the
+    // worktree is removed, so it is not stopped on unmount
     initializeMcpServer();
-
-    return () => {
-      if (mcpServerId) {
-        tauriService.stopMcpServer(mcpServerId).catch(console.error);
-      }
-    };
   }, [worktree.id, worktree.path]);
 
   // Poll MCP server status periodically
@@ -94,6 +90,7 @@ export default function ChatWindow({ worktree, chat, onChatUpdated }: ChatWindow
     let unlistenOutput: UnlistenFn | undefined;
     let unlistenCompleted: UnlistenFn | undefined;
     let unlistenApproval: UnlistenFn | undefined;
+    let unlistenProcessStatus: UnlistenFn | undefined;
 
     const setupListeners = async () => {
       console.log('🎧 Setting up event listeners for worktree:', worktree.id);
@@ -179,10 +176,24 @@ export default function ChatWindow({ worktree, chat, onChatUpdated }: ChatWindow
         });
       });
 
+      // Track processes blocked on a tool approval
+      unlistenProcessStatus = await listen<ClaudeProcessStatusEvent>('claude-process-status', (event) => {
+        const { process_id, status } = event.payload;
+        onChatUpdated((currentChat) => {
+          if (!currentChat.process || currentChat.process.id !== process_id) {
+            return currentChat;
+          }
+          return {
+            ...currentChat,
+            process: { ...currentChat.process, status },
+          };
+        });
+      });
+
       // Listen for approval requests
       unlistenApproval = await listen('tool-approval-request', (event: any) => {
         console.log('🚨 APPROVAL REQUEST EVENT RECEIVED:', event.payload);
-        const { approval_id, request } = event.payload;
+        const { approval_id, request, file_change, risk } = event.payload;
         
         // Check if we've already processed this approval
         if (processedApprovalIds.current.has(approval_id)) {
@@ -208,6 +219,8 @@ export default function ChatWindow({ worktree, chat, onChatUpdated }: ChatWindow
                 approvalId: approval_id,
                 toolName: request.toolName,
                 input: request.input,
+                fileChange: file_change,
+                risk,
               },
             };
             
@@ -232,6 +245,7 @@ export default function ChatWindow({ worktree, chat, onChatUpdated }: ChatWindow
       if (unlistenOutput) unlistenOutput();
       if (unlistenCompleted) unlistenCompleted();
       if (unlistenApproval) unlistenApproval();
+      if (unlistenProcessStatus) unlistenProcessStatus();
       
       // Clear tracking references to prevent memory leaks
       lastProcessedMessageId.current = null;
@@ -491,11 +505,61 @@ ${chat.messages.map(msg => `[${formatTime(msg.timestamp)}] ${msg.role}: ${msg.co
                         <div className="text-claude-dark-100 mb-3">
                           Claude wants to <span className="font-semibold">{message.approvalRequest.toolName.replace(/_/g, ' ')}</span>
                         </div>
-                        <div className="bg-claude-dark-800 rounded p-3 mb-3">
-                          <pre className="text-xs text-claude-dark-300 whitespace-pre-wrap break-all">
-                            {JSON.stringify(message.approvalRequest.input, null, 2)}
-                          </pre>
-                        </div>
+                        {message.approvalRequest.risk && message.approvalRequest.risk.level !== 'low' && (
+                          <div
+                            className={`rounded p-2 mb-3 text-xs border ${
+                              message.approvalRequest.risk.level === 'critical' || message.approvalRequest.risk.level === 'high'
+                                ? 'bg-red-900/20 border-red-800 text-red-300'
+                                : 'bg-yellow-900/20 border-yellow-800 text-yellow-300'
+                            }`}
+                          >
+                            <div className="font-semibold mb-1 uppercase">{message.approvalRequest.risk.level} risk</div>
+                            <ul className="list-disc list-inside">
+                              {message.approvalRequest.risk.reasons.map((reason, index) => (
+                                <li key={index}>{reason.message}</li>
+                              ))}
+                            </ul>
+                          </div>
+                        )}
+                        {message.approvalRequest.fileChange ? (
+                          <div className="bg-claude-dark-800 rounded p-3 mb-3">
+                            <div className="text-xs text-claude-dark-100 mb-2">
+                              {message.approvalRequest.fileChange.relative_path ?? message.approvalRequest.fileChange.file_path}
+                              <span className="text-claude-dark-400 ml-2">
+                                {message.approvalRequest.fileChange.exists
+                                  ? `${message.approvalRequest.fileChange.size ?? 0} bytes`
+                                  : 'new file'}
+                              </span>
+                            </div>
+                            {message.approvalRequest.fileChange.diff ? (
+                              <pre className="text-xs whitespace-pre-wrap break-all">
+                                {message.approvalRequest.fileChange.diff.split('\n').map((line, index) => (
+                                  <div
+                                    key={index}
+                                    className={
+                                      line.startsWith('+') ? 'text-green-400'
+                                        : line.startsWith('-') ? 'text-red-400'
+                                        : line.startsWith('@@') ? 'text-blue-400'
+                                        : 'text-claude-dark-300'
+                                    }
+                                  >
+                                    {line}
+                                  </div>
+                                ))}
+                              </pre>
+                            ) : (
+                              <div className="text-xs text-claude-dark-400">
+                                {message.approvalRequest.fileChange.note}
+                              </div>
+                            )}
+                          </div>
+                        ) : (
+                          <div className="bg-claude-dark-800 rounded p-3 mb-3">
+                            <pre className="text-xs text-claude-dark-300 whitespace-pre-wrap break-all">
+                              {JSON.stringify(message.approvalRequest.input, null, 2)}
+                            </pre>
+                          </div>
+                        )}
                         <div className="flex space-x-2">
                           <button
                             onClick={() => handleApprovalResponse(message.approvalRequest!.approvalId, {
@@ -506,6 +570,17 @@ ${chat.messages.map(msg => `[${formatTime(msg.timestamp)}] ${msg.role}: ${msg.co
                             <CheckCircle className="w-4 h-4" />
                             <span>Approve</span>
                           </button>
+                          <button
+                            onClick={() => handleApprovalResponse(message.approvalRequest!.approvalId, {
+                              behavior: 'allow',
+                              grant: { scope: 'process', matcher: { type: 'exact_input' } },
+                            })}
+                            className="px-4 py-2 text-sm bg-green-800 hover:bg-green-900 text-white rounded transition-colors flex items-center space-x-2"
+                            title="Allow this exact request again without asking until the process ends"
+                          >
+                            <CheckCircle className="w-4 h-4" />
+                            <span>Allow for session</span>
+                          </button>
                           <button
                             onClick={() => handleApprovalResponse(message.approvalRequest!.approvalId, {
                               behavior: 'deny',
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { WorktreeConfig, ClaudeProcess, GitWorktreeInfo, McpServerConfig, ApprovalRequest, ApprovalResponse } from '../types';
+import { WorktreeConfig, ClaudeProcess, GitWorktreeInfo, McpServerConfig, ApprovalRequest, ApprovalResponse, RetryPolicy, LaunchProfile, AgentBackendInfo, EnvironmentReport, McpRuntimeInfo, ExtraMcpServer, McpServerStatus, LogEntry, AppLogEntry, AppLogQuery, McpTransport, ApprovalRecord, ApprovalGrant, SandboxOptions, ResourceLimits, ProcessMetrics } from '../types';
 
 export const tauriService = {
   async createWorktree(
@@ -42,13 +42,25 @@ export const tauriService = {
     worktreePath: string,
     worktreeId: string,
     userMessage: string,
-    permissionMode?: string
+    permissionMode?: string,
+    launchProfile?: string,
+    repoPath?: string,
+    backend?: string,
+    resumeSessionId?: string,
+    readOnly?: boolean,
+    sandbox?: SandboxOptions
   ): Promise<ClaudeProcess> {
     return await invoke('start_claude_process', { 
       worktreePath, 
       worktreeId, 
       userMessage, 
-      permissionMode 
+      permissionMode,
+      launchProfile,
+      repoPath,
+      backend,
+      resumeSessionId,
+      readOnly,
+      sandbox
     });
   },
 
@@ -56,13 +68,25 @@ export const tauriService = {
     worktreePath: string,
     worktreeId: string,
     message: string,
-    permissionMode?: string
+    permissionMode?: string,
+    launchProfile?: string,
+    repoPath?: string,
+    backend?: string,
+    resumeSessionId?: string,
+    readOnly?: boolean,
+    sandbox?: SandboxOptions
   ): Promise<void> {
     return await invoke('send_message_to_claude', { 
       worktreePath, 
       worktreeId, 
       message, 
-      permissionMode 
+      permissionMode,
+      launchProfile,
+      repoPath,
+      backend,
+      resumeSessionId,
+      readOnly,
+      sandbox
     });
   },
 
@@ -74,6 +98,62 @@ export const tauriService = {
     return await invoke('list_processes');
   },
 
+  async getProcessMetrics(processId: string): Promise<ProcessMetrics[]> {
+    return await invoke('get_process_metrics', { processId });
+  },
+
+  async getRetryPolicy(): Promise<RetryPolicy> {
+    return await invoke('get_retry_policy');
+  },
+
+  async setRetryPolicy(policy: RetryPolicy): Promise<void> {
+    return await invoke('set_retry_policy', { policy });
+  },
+
+  async getResourceLimits(): Promise<ResourceLimits> {
+    return await invoke('get_resource_limits');
+  },
+
+  async setResourceLimits(limits: ResourceLimits): Promise<void> {
+    return await invoke('set_resource_limits', { limits });
+  },
+
+  async getRedactionPatterns(): Promise<string[]> {
+    return await invoke('get_redaction_patterns');
+  },
+
+  async setRedactionPatterns(patterns: string[]): Promise<void> {
+    return await invoke('set_redaction_patterns', { patterns });
+  },
+
+  async listLaunchProfiles(): Promise<LaunchProfile[]> {
+    return await invoke('list_launch_profiles');
+  },
+
+  async saveLaunchProfile(profile: LaunchProfile): Promise<void> {
+    return await invoke('save_launch_profile', { profile });
+  },
+
+  async deleteLaunchProfile(name: string): Promise<void> {
+    return await invoke('delete_launch_profile', { name });
+  },
+
+  async setWorktreeLaunchProfile(worktreeId: string, profileName?: string): Promise<void> {
+    return await invoke('set_worktree_launch_profile', { worktreeId, profileName });
+  },
+
+  async setRepoLaunchProfile(repoPath: string, profileName?: string): Promise<void> {
+    return await invoke('set_repo_launch_profile', { repoPath, profileName });
+  },
+
+  async listAgentBackends(): Promise<AgentBackendInfo[]> {
+    return await invoke('list_agent_backends');
+  },
+
+  async diagnoseEnvironment(claudeBinary?: string): Promise<EnvironmentReport> {
+    return await invoke('diagnose_environment', { claudeBinary });
+  },
+
   async validateGitRepo(repoPath: string): Promise<string> {
     return await invoke('validate_git_repo', { repoPath });
   },
@@ -100,6 +180,66 @@ export const tauriService = {
     return await invoke('get_mcp_server_status', { serverId });
   },
 
+  async getMcpTransport(): Promise<McpTransport> {
+    return await invoke('get_mcp_transport');
+  },
+
+  async setMcpTransport(transport: McpTransport): Promise<void> {
+    return await invoke('set_mcp_transport', { transport });
+  },
+
+  async getMcpServerLogs(serverId: string, tail?: number): Promise<LogEntry[]> {
+    return await invoke('get_mcp_server_logs', { serverId, tail });
+  },
+
+  async getLogs(query: AppLogQuery = {}): Promise<AppLogEntry[]> {
+    return await invoke('get_logs', { ...query });
+  },
+
+  async getLogLevel(): Promise<string> {
+    return await invoke('get_log_level');
+  },
+
+  async setLogLevel(level: string): Promise<void> {
+    return await invoke('set_log_level', { level });
+  },
+
+  async listMcpServerStatuses(): Promise<McpServerStatus[]> {
+    return await invoke('list_mcp_server_statuses');
+  },
+
+  async getMcpRuntimeInfo(): Promise<McpRuntimeInfo> {
+    return await invoke('get_mcp_runtime_info');
+  },
+
+  async setMcpServerPathOverride(path: string | null): Promise<McpRuntimeInfo> {
+    return await invoke('set_mcp_server_path_override', { path });
+  },
+
+  async getWorktreeMcpServers(worktreeId: string): Promise<ExtraMcpServer[]> {
+    return await invoke('get_worktree_mcp_servers', { worktreeId });
+  },
+
+  async setWorktreeMcpServers(worktreeId: string, servers: ExtraMcpServer[]): Promise<void> {
+    return await invoke('set_worktree_mcp_servers', { worktreeId, servers });
+  },
+
+  async getRepoMcpServers(repoPath: string): Promise<ExtraMcpServer[]> {
+    return await invoke('get_repo_mcp_servers', { repoPath });
+  },
+
+  async setRepoMcpServers(repoPath: string, servers: ExtraMcpServer[]): Promise<void> {
+    return await invoke('set_repo_mcp_servers', { repoPath, servers });
+  },
+
+  async getRepoProtectedPaths(repoPath: string): Promise<string[]> {
+    return await invoke('get_repo_protected_paths', { repoPath });
+  },
+
+  async setRepoProtectedPaths(repoPath: string, patterns: string[]): Promise<void> {
+    return await invoke('set_repo_protected_paths', { repoPath, patterns });
+  },
+
   async requestToolApproval(request: ApprovalRequest): Promise<string> {
     return await invoke('request_tool_approval', { request });
   },
@@ -121,4 +261,20 @@ export const tauriService = {
   async getPendingApprovals(): Promise<Array<[string, ApprovalRequest]>> {
     return await invoke('get_pending_approvals');
   },
+
+  async listApprovals(): Promise<ApprovalRecord[]> {
+    return await invoke('list_approvals');
+  },
+
+  async getApprovalHistory(limit?: number): Promise<ApprovalRecord[]> {
+    return await invoke('get_approval_history', { limit });
+  },
+
+  async listApprovalGrants(): Promise<ApprovalGrant[]> {
+    return await invoke('list_approval_grants');
+  },
+
+  async revokeApprovalGrant(grantId: string): Promise<ApprovalGrant> {
+    return await invoke('revoke_approval_grant', { grantId });
+  },
 };
\ No newline at end of file
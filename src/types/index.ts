@@ -15,10 +15,37 @@ export interface ClaudeProcess {
   id: string;
   worktree_id: string;
   pid?: number;
-  status: 'idle' | 'running' | 'stopped' | 'error' | 'starting' | 'completed';
+  status: 'idle' | 'running' | 'awaiting_approval' | 'stopped' | 'error' | 'starting' | 'completed';
   task?: string;
   started_at?: string;
   last_activity?: string;
+  launch_profile?: string | null;
+  backend?: string;
+  session_id?: string | null;
+  read_only?: boolean;
+  read_only_violations?: string[];
+  sandbox?: SandboxOptions | null;
+  resource_limits?: AppliedLimits | null;
+  resource_usage?: ResourceUsage | null;
+  // Secrets masked in output and approval requests
+  redactions?: number;
+}
+
+// Payload of the `process-metrics` event is an array of these, one per running process
+export interface ProcessMetrics {
+  process_id: string;
+  worktree_id: string;
+  timestamp: string;
+  cpu_percent: number;
+  rss_mb: number;
+  child_processes: number;
+  open_ports: number[];
+}
+
+// Payload of the `claude-process-status` event
+export interface ClaudeProcessStatusEvent {
+  process_id: string;
+  status: ClaudeProcess['status'];
 }
 
 export interface ChatMessage {
@@ -32,9 +59,168 @@ export interface ChatMessage {
     approvalId: string;
     toolName: string;
     input: any;
+    fileChange?: FileChangeContext | null;
+    risk?: CommandRisk | null;
   };
 }
 
+export type RiskLevel = 'low' | 'medium' | 'high' | 'critical';
+
+export type RiskCategory =
+  | 'destructive'
+  | 'network'
+  | 'outside_worktree'
+  | 'privilege_escalation'
+  | 'history_rewrite';
+
+// Sent with `tool-approval-request` for Bash commands
+export interface CommandRisk {
+  level: RiskLevel;
+  reasons: {
+    category: RiskCategory;
+    level: RiskLevel;
+    message: string;
+  }[];
+}
+
+// Sent with `tool-approval-request` for Edit, MultiEdit and Write
+export interface FileChangeContext {
+  file_path: string;
+  relative_path?: string | null;
+  exists: boolean;
+  size?: number | null;
+  diff?: string | null;
+  note?: string | null;
+}
+
+export type ClaudeErrorCode =
+  | 'binary_not_found'
+  | 'not_authenticated'
+  | 'rate_limited'
+  | 'overloaded'
+  | 'context_too_long'
+  | 'invalid_flags'
+  | 'mcp_config_error'
+  | 'sandbox_unavailable'
+  | 'unknown';
+
+export interface ClaudeCompletedEvent {
+  process_id: string;
+  success: boolean;
+  error_code?: ClaudeErrorCode | null;
+  error_message?: string | null;
+}
+
+export interface RetryPolicy {
+  max_retries: number;
+  initial_delay_ms: number;
+  max_delay_ms: number;
+}
+
+// Limits for each agent process tree; unset fields are unlimited
+export interface ResourceLimits {
+  cpu_percent?: number | null;
+  memory_mb?: number | null;
+  open_files?: number | null;
+  max_processes?: number | null;
+}
+
+export interface AppliedLimits {
+  limits: ResourceLimits;
+  cgroup?: string | null;
+  unenforced?: string[];
+}
+
+export interface ResourceUsage {
+  cpu_seconds: number;
+  memory_mb: number;
+  open_files: number;
+  processes: number;
+}
+
+export interface LaunchProfile {
+  name: string;
+  backend?: string | null;
+  binary_path?: string | null;
+  model?: string | null;
+  max_turns?: number | null;
+  allowed_tools?: string[];
+  disallowed_tools?: string[];
+  append_system_prompt?: string | null;
+  env?: Record<string, string>;
+  extra_args?: string[];
+}
+
+export interface AgentBackendInfo {
+  id: string;
+  name: string;
+  supports_permission_prompt: boolean;
+  supports_resume: boolean;
+}
+
+export interface DiagnosticCheck {
+  name: string;
+  status: 'ok' | 'warning' | 'error';
+  detail: string;
+  version?: string | null;
+  fix?: string | null;
+}
+
+export interface EnvironmentReport {
+  ok: boolean;
+  checks: DiagnosticCheck[];
+}
+
+export interface ExtraMcpServer {
+  name: string;
+  command: string;
+  args?: string[];
+  env?: Record<string, string>;
+}
+
+export type McpServerState = 'running' | 'restarting' | 'failed' | 'stopped';
+
+// Payload of the `mcp-server-status` event
+export interface McpServerStatus {
+  server_id: string;
+  worktree_id: string;
+  state: McpServerState;
+  restart_count: number;
+  message?: string | null;
+}
+
+export interface LogEntry {
+  timestamp: string;
+  level: 'debug' | 'info' | 'warn' | 'error';
+  message: string;
+}
+
+export interface AppLogEntry extends LogEntry {
+  target: string;
+  fields: Record<string, unknown>;
+  process_id?: string;
+  worktree_id?: string;
+  approval_id?: string;
+}
+
+export interface AppLogQuery {
+  tail?: number;
+  level?: LogEntry['level'];
+  processId?: string;
+  worktreeId?: string;
+  approvalId?: string;
+}
+
+export type McpTransport = 'stdio' | 'sse';
+
+export type McpServerSource = 'override' | 'bundled' | 'development';
+
+export interface McpRuntimeInfo {
+  server_path: string;
+  source: McpServerSource;
+  node_version: string;
+}
+
 export interface WorktreeChat {
   worktree_id: string;
   messages: ChatMessage[];
@@ -72,6 +258,7 @@ export interface ApprovalRequest {
   toolName: string;
   input: any;
   worktreeId: string;
+  processId?: string | null;
   timestamp: number;
 }
 
@@ -79,6 +266,91 @@ export interface ApprovalResponse {
   behavior: 'allow' | 'deny';
   message?: string;
   updatedInput?: any;
+  grant?: GrantRequest;
+}
+
+export type GrantScope = 'once' | 'process' | 'worktree' | 'repo';
+
+export type GrantMatcher =
+  | { type: 'exact_input'; input?: any; digest?: string }
+  | { type: 'tool' }
+  | { type: 'command_prefix'; prefix: string };
+
+// "Always allow" part of an approval response
+export interface GrantRequest {
+  scope: GrantScope;
+  matcher: GrantMatcher;
+}
+
+export interface ApprovalGrant {
+  grant_id: string;
+  tool_name: string;
+  matcher: GrantMatcher;
+  scope: GrantScope;
+  process_id?: string | null;
+  worktree_id?: string | null;
+  repo_path?: string | null;
+  created_at: string;
+}
+
+export interface ProtectedPathMatch {
+  path: string;
+  pattern: string;
+}
+
+export interface ToolApprovalDeniedEvent {
+  approval_id: string;
+  tool_name: string;
+  worktree_id: string;
+  process_id?: string | null;
+  read_only: boolean;
+  protected_paths: ProtectedPathMatch[];
+  message: string;
+}
+
+export interface ProtectedPathsChangedEvent {
+  process_id: string;
+  worktree_id: string;
+  changes: ProtectedPathMatch[];
+}
+
+export interface ReadOnlyViolationEvent {
+  process_id: string;
+  violations: string[];
+}
+
+export type SandboxNetwork = 'full' | 'restricted';
+
+export interface SandboxOptions {
+  network?: SandboxNetwork;
+  // Hosts besides api.anthropic.com a restricted sandbox may reach, on port
+  // 443 unless given as host:port
+  allowed_hosts?: string[];
+}
+
+export interface SandboxViolationEvent {
+  process_id: string;
+  kind: 'filesystem' | 'network';
+  detail: string;
+}
+
+export type ApprovalState = 'pending' | 'answered' | 'expired' | 'cancelled';
+
+export interface ApprovalRecord {
+  approval_id: string;
+  tool_name: string;
+  input: any;
+  worktree_id: string;
+  process_id?: string | null;
+  repo_path?: string | null;
+  timestamp: number;
+  state: ApprovalState;
+  response?: {
+    behavior: 'Allow' | 'Deny';
+    message?: string | null;
+    updatedInput?: any;
+  } | null;
+  resolved_at?: number | null;
 }
 
 export interface AppState {